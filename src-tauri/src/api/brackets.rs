@@ -0,0 +1,190 @@
+use crate::api::grammar::grammar::{AutoClosingPairs, Grammar};
+use crate::api::grammar::token::Tokenizer;
+use crate::api::language::get_language;
+
+// region: ---Tauri Command
+
+// offset of the cursor in bytes
+#[tauri::command]
+pub async fn get_bracket_match(
+    text: &str,
+    language: &str,
+    offset: usize,
+) -> Result<BracketMatch, String> {
+    let language = get_language(language).ok_or("unknown language")?;
+    Ok(bracket_match(language.get_grammar(), text, offset))
+}
+
+#[tauri::command]
+pub async fn get_auto_closing_pairs(language: &str) -> Result<AutoClosingPairs, String> {
+    let language = get_language(language).ok_or("unknown language")?;
+    Ok(language.get_grammar().get_auto_closing_pairs().clone())
+}
+
+// endregion
+
+// region: ---Bracket Match
+
+#[derive(Debug, serde::Serialize)]
+pub struct BracketMatch {
+    pub bracket: Option<(usize, usize)>,   // bracket at the cursor
+    pub matching: Option<(usize, usize)>,  // bracket matching the bracket at the cursor
+    pub enclosing: Option<(usize, usize)>, // innermost pair of brackets around the cursor
+    pub unbalanced: Vec<(usize, usize)>,   // brackets without a matching bracket in the text
+}
+
+pub fn bracket_match(grammar: &Grammar, text: &str, offset: usize) -> BracketMatch {
+    let pairs = match_brackets(grammar, text);
+
+    // bracket after the cursor first, then the bracket before it
+    let at_cursor = |side: fn(&(usize, usize)) -> usize| {
+        pairs.matched.iter().find_map(|(open, close)| {
+            if side(open) == offset {
+                Some((*open, *close))
+            } else if side(close) == offset {
+                Some((*close, *open))
+            } else {
+                None
+            }
+        })
+    };
+    let (bracket, matching) = at_cursor(|span| span.0)
+        .or_else(|| at_cursor(|span| span.1))
+        .map_or((None, None), |(bracket, matching)| {
+            (Some(bracket), Some(matching))
+        });
+
+    let enclosing = pairs
+        .matched
+        .iter()
+        .filter(|(open, close)| open.1 <= offset && offset <= close.0)
+        .min_by_key(|(open, close)| close.1 - open.0)
+        .map(|(open, close)| (open.0, close.1));
+
+    BracketMatch {
+        bracket,
+        matching,
+        enclosing,
+        unbalanced: pairs.unbalanced,
+    }
+}
+
+pub(crate) struct BracketPairs {
+    pub(crate) matched: Vec<((usize, usize), (usize, usize))>, // (open, close) spans
+    pub(crate) unbalanced: Vec<(usize, usize)>,
+}
+
+fn match_brackets(grammar: &Grammar, text: &str) -> BracketPairs {
+    pair_brackets(grammar, &tokens(grammar, text))
+}
+
+// brackets of the (definition, span) tokens paired by their definition
+pub(crate) fn pair_brackets(grammar: &Grammar, tokens: &[(usize, (usize, usize))]) -> BracketPairs {
+    let brackets = grammar.get_brackets();
+    let mut pairs = BracketPairs {
+        matched: Vec::new(),
+        unbalanced: Vec::new(),
+    };
+    let mut stack: Vec<(usize, (usize, usize))> = Vec::new(); // (close definition, open span)
+
+    for &(definition, span) in tokens {
+        if let Some((_, close)) = brackets.iter().find(|(open, _)| *open == definition) {
+            stack.push((*close, span));
+        } else if brackets.iter().any(|(_, close)| *close == definition) {
+            match stack.iter().rposition(|(close, _)| *close == definition) {
+                Some(position) => {
+                    // the brackets opened after the matching one are never closed
+                    for (_, open) in stack.drain(position + 1..) {
+                        pairs.unbalanced.push(open);
+                    }
+                    let (_, open) = stack.pop().unwrap();
+                    pairs.matched.push((open, span));
+                }
+                None => pairs.unbalanced.push(span),
+            }
+        }
+    }
+
+    pairs
+        .unbalanced
+        .extend(stack.into_iter().map(|(_, open)| open));
+    pairs.unbalanced.sort();
+    pairs
+}
+
+// (definition, span) of the tokens of the parse tree, or of the tokenizer if the text is invalid
+fn tokens(grammar: &Grammar, text: &str) -> Vec<(usize, (usize, usize))> {
+    match grammar.parse(text) {
+        Some(context) => context
+            .tokens()
+            .iter()
+            .map(|token| (token.definition_index(), token.span()))
+            .collect(),
+        None => lossy_tokens(grammar, text),
+    }
+}
+
+// the characters matched by no token definition are skipped
+pub(crate) fn lossy_tokens(grammar: &Grammar, text: &str) -> Vec<(usize, (usize, usize))> {
+    Tokenizer::new(text, grammar.get_tokens_definition())
+        .get_all_tokens_lossy()
+        .iter()
+        .map(|token| (token.definition_index(), token.span()))
+        .collect()
+}
+
+// endregion
+
+// region: ---Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::language::json::get_json_grammar;
+
+    #[test]
+    fn nested_brackets_are_matched() {
+        let pairs = match_brackets(&get_json_grammar(), "{\"a\": [1, {}]}");
+        assert_eq!(
+            pairs.matched,
+            [((10, 11), (11, 12)), ((6, 7), (12, 13)), ((0, 1), (13, 14))]
+        );
+        assert!(pairs.unbalanced.is_empty());
+    }
+
+    #[test]
+    fn unclosed_and_stray_brackets_are_unbalanced() {
+        // the text does not parse, the brackets come from the tokenizer
+        let pairs = match_brackets(&get_json_grammar(), "[{] ]");
+        assert_eq!(pairs.matched, [((0, 1), (2, 3))]);
+        assert_eq!(pairs.unbalanced, [(1, 2), (4, 5)]);
+    }
+
+    #[test]
+    fn bracket_at_the_cursor_and_its_match() {
+        let grammar = get_json_grammar();
+        let text = "[1, [2]]";
+
+        let before = bracket_match(&grammar, text, 4);
+        assert_eq!(before.bracket, Some((4, 5)));
+        assert_eq!(before.matching, Some((6, 7)));
+
+        // the bracket before the cursor when there is none after it
+        let after = bracket_match(&grammar, text, 8);
+        assert_eq!(after.bracket, Some((7, 8)));
+        assert_eq!(after.matching, Some((0, 1)));
+    }
+
+    #[test]
+    fn enclosing_pair_is_the_innermost() {
+        let grammar = get_json_grammar();
+        let result = bracket_match(&grammar, "[1, [2, 3]]", 6);
+        assert_eq!(result.bracket, None);
+        assert_eq!(result.enclosing, Some((4, 10)));
+
+        let outside = bracket_match(&grammar, " [1]", 0);
+        assert_eq!(outside.enclosing, None);
+    }
+}
+
+// endregion
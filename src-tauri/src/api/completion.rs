@@ -0,0 +1,434 @@
+use crate::api::grammar::token::Tokenizer;
+use crate::api::language::{get_language, Language};
+use serde_json::Value;
+use std::sync::Mutex;
+use tauri::State;
+
+// region: ---Tauri Command
+
+// offset of the cursor in bytes
+#[tauri::command]
+pub async fn get_completions(
+    text: &str,
+    language: &str,
+    offset: usize,
+    providers: State<'_, CompletionProviders>,
+) -> Result<Completion, String> {
+    let language = get_language(language).ok_or("unknown language")?;
+    let text = text.get(..offset).ok_or("invalid offset")?;
+    Ok(complete(&language, text, &providers))
+}
+
+// suggest the words after the trigger (note names after [[, ...)
+#[tauri::command]
+pub async fn register_completion_words(
+    language: &str,
+    trigger: &str,
+    terminator: Option<&str>,
+    kind: &str,
+    words: Vec<String>,
+    providers: State<'_, CompletionProviders>,
+) -> Result<(), String> {
+    let language = get_language(language).ok_or("unknown language")?;
+    providers.register(Box::new(WordsProvider {
+        grammar: Box::from(language.get_grammar().get_name()),
+        trigger: Box::from(trigger),
+        terminator: terminator.map(Box::from),
+        kind: Box::from(kind),
+        words: words.into_iter().map(String::into_boxed_str).collect(),
+    }));
+    Ok(())
+}
+
+// suggest the property names of the schema in the json objects
+#[tauri::command]
+pub async fn register_json_schema(
+    schema: Value,
+    providers: State<'_, CompletionProviders>,
+) -> Result<(), String> {
+    providers.register(Box::new(JsonSchemaProvider { schema }));
+    Ok(())
+}
+
+// endregion
+
+// region: ---Completion
+
+#[derive(Debug, serde::Serialize)]
+pub struct Completion {
+    pub expected_tokens: Vec<Box<str>>,
+    pub fragments: Vec<Box<str>>,
+    pub items: Vec<CompletionItem>,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct CompletionItem {
+    pub label: String,
+    pub kind: Box<str>,
+    pub insert_text: String,
+    pub start: usize, // the text between start and the cursor is replaced by insert_text
+}
+
+#[derive(Debug)]
+pub struct ExpectedToken {
+    pub name: Box<str>,
+    pub fragment: Option<Box<str>>,
+    pub literal: Option<String>, // text inserted for the token, if there is only one
+}
+
+pub struct CompletionContext<'a> {
+    pub language: &'a Language,
+    pub text: &'a str,    // text before the cursor
+    pub partial: &'a str, // start of the token being typed
+    pub expected: &'a [ExpectedToken],
+}
+
+impl<'a> CompletionContext<'a> {
+    pub fn partial_start(&self) -> usize {
+        self.text.len() - self.partial.len()
+    }
+}
+
+pub fn complete(language: &Language, text: &str, providers: &CompletionProviders) -> Completion {
+    let grammar = language.get_grammar();
+    let (offset, expected) = grammar.expected(text);
+
+    let expected: Vec<ExpectedToken> = expected
+        .into_iter()
+        .map(|(definition_index, fragment_index)| {
+            let definition = grammar.get_token_definition(definition_index);
+            ExpectedToken {
+                name: Box::from(definition.name()),
+                fragment: fragment_index.map(|fragment_index| {
+                    Box::from(grammar.get_fragment(fragment_index).get_name())
+                }),
+                literal: definition.literal(),
+            }
+        })
+        .collect();
+
+    let context = CompletionContext {
+        language,
+        text,
+        partial: &text[offset.min(text.len())..],
+        expected: &expected,
+    };
+
+    let mut items = GrammarProvider.provide(&context);
+    for provider in providers.0.lock().unwrap().iter() {
+        items.extend(provider.provide(&context));
+    }
+
+    let mut fragments: Vec<Box<str>> = Vec::new();
+    for fragment in expected.iter().filter_map(|token| token.fragment.clone()) {
+        if !fragments.contains(&fragment) {
+            fragments.push(fragment);
+        }
+    }
+
+    Completion {
+        expected_tokens: expected.into_iter().map(|token| token.name).collect(),
+        fragments,
+        items,
+    }
+}
+
+// endregion
+
+// region: ---Providers
+
+pub trait CompletionProvider: Send + Sync {
+    // a provider replaces the registered provider with the same name
+    fn name(&self) -> String;
+
+    fn provide(&self, context: &CompletionContext) -> Vec<CompletionItem>;
+}
+
+#[derive(Default)]
+pub struct CompletionProviders(Mutex<Vec<Box<dyn CompletionProvider>>>);
+
+impl CompletionProviders {
+    pub fn register(&self, provider: Box<dyn CompletionProvider>) {
+        let mut providers = self.0.lock().unwrap();
+        providers.retain(|registered| registered.name() != provider.name());
+        providers.push(provider);
+    }
+}
+
+// the expected tokens having a single text (keywords, punctuation)
+struct GrammarProvider;
+
+impl CompletionProvider for GrammarProvider {
+    fn name(&self) -> String {
+        String::from("grammar")
+    }
+
+    fn provide(&self, context: &CompletionContext) -> Vec<CompletionItem> {
+        context
+            .expected
+            .iter()
+            .filter_map(|token| token.literal.as_ref())
+            .filter(|literal| literal.starts_with(context.partial))
+            .map(|literal| CompletionItem {
+                label: literal.clone(),
+                kind: Box::from("keyword"),
+                insert_text: literal.clone(),
+                start: context.partial_start(),
+            })
+            .collect()
+    }
+}
+
+pub struct WordsProvider {
+    pub grammar: Box<str>, // name of the grammar of the language
+    pub trigger: Box<str>,
+    pub terminator: Option<Box<str>>,
+    pub kind: Box<str>,
+    pub words: Vec<Box<str>>,
+}
+
+impl CompletionProvider for WordsProvider {
+    fn name(&self) -> String {
+        format!("words:{}:{}", self.grammar, self.trigger)
+    }
+
+    fn provide(&self, context: &CompletionContext) -> Vec<CompletionItem> {
+        if context.language.get_grammar().get_name() != self.grammar.as_ref() {
+            return Vec::new();
+        }
+
+        // the trigger must be on the line of the cursor and not terminated
+        let line_start = context.text.rfind('\n').map(|index| index + 1).unwrap_or(0);
+        let line = &context.text[line_start..];
+        let start = match line.rfind(self.trigger.as_ref()) {
+            Some(index) => index + self.trigger.len(),
+            None => return Vec::new(),
+        };
+        let partial = &line[start..];
+        if let Some(terminator) = &self.terminator {
+            if partial.contains(terminator.as_ref()) {
+                return Vec::new();
+            }
+        }
+
+        let partial = partial.to_lowercase();
+        self.words
+            .iter()
+            .filter(|word| word.to_lowercase().starts_with(&partial))
+            .map(|word| CompletionItem {
+                label: word.to_string(),
+                kind: self.kind.clone(),
+                insert_text: word.to_string(),
+                start: line_start + start,
+            })
+            .collect()
+    }
+}
+
+pub struct JsonSchemaProvider {
+    pub schema: Value,
+}
+
+impl CompletionProvider for JsonSchemaProvider {
+    fn name(&self) -> String {
+        String::from("json-schema")
+    }
+
+    fn provide(&self, context: &CompletionContext) -> Vec<CompletionItem> {
+        let grammar = context.language.get_grammar();
+        if grammar.get_name() != "JSON" {
+            return Vec::new();
+        }
+
+        // only where a key of an object is expected
+        let is_key = context.expected.iter().any(|token| {
+            token.name.as_ref() == "STRING" && token.fragment.as_deref() == Some("member")
+        });
+        if !is_key {
+            return Vec::new();
+        }
+
+        let mut schema = &self.schema;
+        for segment in json_path(context) {
+            let next = match segment {
+                Some(key) => schema
+                    .get("properties")
+                    .and_then(|properties| properties.get(&key)),
+                None => schema.get("items"),
+            };
+            match next {
+                Some(next) => schema = next,
+                None => return Vec::new(),
+            }
+        }
+
+        let properties = match schema.get("properties").and_then(Value::as_object) {
+            Some(properties) => properties,
+            None => return Vec::new(),
+        };
+        let partial = context.partial.trim_start_matches('"');
+        properties
+            .keys()
+            .filter(|key| key.starts_with(partial))
+            .map(|key| CompletionItem {
+                label: key.clone(),
+                kind: Box::from("property"),
+                insert_text: format!("\"{}\"", key),
+                start: context.partial_start(),
+            })
+            .collect()
+    }
+}
+
+// keys (Some) and array items (None) leading to the object around the cursor
+fn json_path(context: &CompletionContext) -> Vec<Option<String>> {
+    let grammar = context.language.get_grammar();
+    let text = &context.text[..context.partial_start()];
+    let name = |definition_index: usize| grammar.get_token_definition(definition_index).name();
+
+    let mut tokenizer = Tokenizer::new(text, grammar.get_tokens_definition());
+    let tokens = tokenizer.get_all_tokens_lossy();
+
+    // current key of the objects (Some) and arrays (None) around the cursor
+    let mut stack: Vec<Option<Option<String>>> = Vec::new();
+    for (index, token) in tokens.iter().enumerate() {
+        match name(token.definition_index()) {
+            "LEFT_BRACE" => stack.push(Some(None)),
+            "LEFT_BRACKET" => stack.push(None),
+            "RIGHT_BRACE" | "RIGHT_BRACKET" => {
+                stack.pop();
+            }
+            "COMMA" => {
+                if let Some(Some(key)) = stack.last_mut() {
+                    *key = None;
+                }
+            }
+            "STRING" => {
+                let is_key = tokens
+                    .get(index + 1)
+                    .is_some_and(|next| name(next.definition_index()) == "COLON");
+                if let (true, Some(Some(key))) = (is_key, stack.last_mut()) {
+                    *key = Some(token.slice().trim_matches('"').to_string());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // the last object is the one of the cursor
+    stack.pop();
+    stack
+        .into_iter()
+        .map(|segment| segment.map(Option::unwrap_or_default))
+        .collect()
+}
+
+// endregion
+
+// region: ---Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    // labels of the items of the providers but the grammar
+    fn labels(language: &str, text: &str, providers: &CompletionProviders) -> Vec<String> {
+        let language = get_language(language).unwrap();
+        let mut labels: Vec<String> = complete(&language, text, providers)
+            .items
+            .into_iter()
+            .filter(|item| item.kind.as_ref() != "keyword")
+            .map(|item| item.label)
+            .collect();
+        labels.sort();
+        labels
+    }
+
+    fn schema_providers() -> CompletionProviders {
+        let providers = CompletionProviders::default();
+        providers.register(Box::new(JsonSchemaProvider {
+            schema: json!({
+                "properties": {
+                    "name": {},
+                    "nested": { "properties": { "inner": {}, "other": {} } },
+                    "list": { "items": { "properties": { "item": {} } } },
+                }
+            }),
+        }));
+        providers
+    }
+
+    #[test]
+    fn keywords_are_completed_from_the_grammar() {
+        let language = get_language("json").unwrap();
+        let providers = CompletionProviders::default();
+        let completion = complete(&language, "[tr", &providers);
+        assert_eq!(completion.items.len(), 1);
+        assert_eq!(completion.items[0].insert_text, "true");
+        assert_eq!(completion.items[0].start, 1);
+
+        let completion = complete(&language, "{\"a\": 1", &providers);
+        let mut keywords: Vec<String> = completion
+            .items
+            .into_iter()
+            .map(|item| item.label)
+            .collect();
+        keywords.sort();
+        assert_eq!(keywords, [",", "}"]);
+    }
+
+    #[test]
+    fn schema_properties_follow_the_path_of_the_cursor() {
+        let providers = schema_providers();
+        assert_eq!(labels("json", "{", &providers), ["list", "name", "nested"]);
+        assert_eq!(labels("json", "{\"n", &providers), ["name", "nested"]);
+        assert_eq!(
+            labels("json", "{\"name\": 1, \"nested\": {", &providers),
+            ["inner", "other"]
+        );
+        assert_eq!(labels("json", "{\"list\": [{}, {", &providers), ["item"]);
+    }
+
+    #[test]
+    fn schema_properties_are_only_suggested_for_keys() {
+        let providers = schema_providers();
+        assert!(labels("json", "{\"name\": ", &providers).is_empty());
+        assert!(labels("json", "{\"unknown\": {", &providers).is_empty());
+    }
+
+    #[test]
+    fn json_path_skips_the_closed_groups() {
+        let language = get_language("json").unwrap();
+        let text = "{\"a\": {\"b\": 1}, \"c\": [1, {\"d\": {";
+        let context = CompletionContext {
+            language: &language,
+            text,
+            partial: "",
+            expected: &[],
+        };
+        assert_eq!(
+            json_path(&context),
+            [Some(String::from("c")), None, Some(String::from("d"))]
+        );
+    }
+
+    #[test]
+    fn words_follow_their_trigger_until_the_terminator() {
+        let providers = CompletionProviders::default();
+        providers.register(Box::new(WordsProvider {
+            grammar: Box::from("Markdown"),
+            trigger: Box::from("[["),
+            terminator: Some(Box::from("]]")),
+            kind: Box::from("note"),
+            words: vec![Box::from("Alpha"), Box::from("Beta"), Box::from("alpine")],
+        }));
+
+        assert_eq!(labels("md", "see [[al", &providers), ["Alpha", "alpine"]);
+        assert!(labels("md", "see [[al]] b", &providers).is_empty());
+        assert!(labels("md", "[[al\nb", &providers).is_empty());
+        assert!(labels("json", "[[al", &providers).is_empty());
+    }
+}
+
+// endregion
@@ -0,0 +1,900 @@
+use crate::api::fs::text::read_text;
+use crate::api::workspace::Workspace;
+use crate::error::Error;
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::hash::Hash;
+use tauri::State;
+
+// region: ---Tauri Command
+
+// the lines of `old` changed into the ones of `new`, grouped in hunks
+#[tauri::command]
+pub async fn diff_texts(
+    old: &str,
+    new: &str,
+    options: Option<DiffOptions>,
+) -> Result<TextDiff, Error> {
+    Ok(diff(old, new, &options.unwrap_or_default()))
+}
+
+// the files are read as text, relative paths are from the workspace root
+#[tauri::command]
+pub async fn diff_files(
+    old_path: &str,
+    new_path: &str,
+    options: Option<DiffOptions>,
+    workspace: State<'_, Workspace>,
+) -> Result<TextDiff, Error> {
+    let old = read_text(workspace.resolve(old_path)?, false)?;
+    let new = read_text(workspace.resolve(new_path)?, false)?;
+    let mut options = options.unwrap_or_default();
+    options.old_name = options.old_name.or(Some(old_path.to_string()));
+    options.new_name = options.new_name.or(Some(new_path.to_string()));
+    Ok(diff(&old.text, &new.text, &options))
+}
+
+// the changes of `ours` and `theirs` since `base` applied together, the lines changed on
+// both sides differently are left between conflict markers
+#[tauri::command]
+pub async fn merge_texts(
+    base: &str,
+    ours: &str,
+    theirs: &str,
+    labels: Option<MergeLabels>,
+) -> Result<Merge, Error> {
+    Ok(merge(base, ours, theirs, &labels.unwrap_or_default()))
+}
+
+// endregion
+
+// region: ---Options
+
+/// # DiffOptions
+/// {
+///     context: usize,           // 3, unchanged lines around the changes of a hunk
+///     words: bool,              // false, the changed words of the modified lines
+///     ignoreWhitespace: bool,   // false, lines differing by their blanks are equal
+///     unified: bool,            // false, the diff as unified diff text too
+///     oldName?: string,         // names of the unified diff header
+///     newName?: string,
+/// }
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct DiffOptions {
+    pub context: usize,
+    pub words: bool,
+    pub ignore_whitespace: bool,
+    pub unified: bool,
+    pub old_name: Option<String>,
+    pub new_name: Option<String>,
+}
+
+impl Default for DiffOptions {
+    fn default() -> Self {
+        DiffOptions {
+            context: 3,
+            words: false,
+            ignore_whitespace: false,
+            unified: false,
+            old_name: None,
+            new_name: None,
+        }
+    }
+}
+
+/// # MergeLabels
+/// {
+///     ours: string,   // "ours", after <<<<<<<
+///     theirs: string, // "theirs", after >>>>>>>
+/// }
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(default)]
+pub struct MergeLabels {
+    pub ours: String,
+    pub theirs: String,
+}
+
+impl Default for MergeLabels {
+    fn default() -> Self {
+        MergeLabels {
+            ours: String::from("ours"),
+            theirs: String::from("theirs"),
+        }
+    }
+}
+
+// endregion
+
+// region: ---Diff
+
+/// # TextDiff
+/// {
+///     hunks: [{
+///         oldStart: number, // 0 based lines
+///         oldLines: number,
+///         newStart: number,
+///         newLines: number,
+///         lines: [{
+///             kind: "equal" | "added" | "removed",
+///             text: string,          // without the line ending
+///             oldLine?: number,
+///             newLine?: number,
+///             words?: [[start, end], ...], // byte ranges of the changed words in the text
+///         }, ...],
+///     }, ...],
+///     added: number,  // lines
+///     removed: number,
+///     unified?: string,
+/// }
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct TextDiff {
+    pub hunks: Vec<Hunk>,
+    pub added: usize,
+    pub removed: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unified: Option<String>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Hunk {
+    pub old_start: usize,
+    pub old_lines: usize,
+    pub new_start: usize,
+    pub new_lines: usize,
+    pub lines: Vec<DiffLine>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DiffKind {
+    Equal,
+    Added,
+    Removed,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffLine {
+    pub kind: DiffKind,
+    pub text: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub old_line: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new_line: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub words: Option<Vec<(usize, usize)>>,
+}
+
+// a range of `old` replaced by a range of `new`, one of them is empty for an insertion or
+// a deletion (end excluded)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Change {
+    pub old_start: usize,
+    pub old_end: usize,
+    pub new_start: usize,
+    pub new_end: usize,
+}
+
+pub fn diff(old: &str, new: &str, options: &DiffOptions) -> TextDiff {
+    let old_lines = split_lines(old);
+    let new_lines = split_lines(new);
+    // the line endings are not compared
+    let changes = match options.ignore_whitespace {
+        true => {
+            let key = |line: &&str| line.split_whitespace().collect::<Vec<_>>().join(" ");
+            changes(
+                &old_lines.iter().map(key).collect::<Vec<_>>(),
+                &new_lines.iter().map(key).collect::<Vec<_>>(),
+            )
+        }
+        false => changes(
+            &old_lines
+                .iter()
+                .map(|line| trim_line_ending(line))
+                .collect::<Vec<_>>(),
+            &new_lines
+                .iter()
+                .map(|line| trim_line_ending(line))
+                .collect::<Vec<_>>(),
+        ),
+    };
+
+    let mut text_diff = TextDiff {
+        hunks: hunks(&old_lines, &new_lines, &changes, options),
+        added: changes
+            .iter()
+            .map(|change| change.new_end - change.new_start)
+            .sum(),
+        removed: changes
+            .iter()
+            .map(|change| change.old_end - change.old_start)
+            .sum(),
+        unified: None,
+    };
+    if options.unified {
+        text_diff.unified = Some(unified(&text_diff, options));
+    }
+    text_diff
+}
+
+// the lines with their line ending, the last one may have none
+pub fn split_lines(text: &str) -> Vec<&str> {
+    text.split_inclusive('\n').collect()
+}
+
+fn trim_line_ending(line: &str) -> &str {
+    line.strip_suffix('\n')
+        .map(|line| line.strip_suffix('\r').unwrap_or(line))
+        .unwrap_or(line)
+}
+
+fn hunks(old: &[&str], new: &[&str], changes: &[Change], options: &DiffOptions) -> Vec<Hunk> {
+    // the changes closer than twice the context share a hunk
+    let mut groups: Vec<&[Change]> = Vec::new();
+    let mut first = 0;
+    for index in 1..=changes.len() {
+        let is_last = index == changes.len()
+            || changes[index].old_start - changes[index - 1].old_end > 2 * options.context;
+        if is_last {
+            groups.push(&changes[first..index]);
+            first = index;
+        }
+    }
+
+    let mut hunks = Vec::new();
+    for group in groups.into_iter().filter(|group| !group.is_empty()) {
+        let (head, tail) = (group[0], group[group.len() - 1]);
+        let before = head.old_start.min(options.context);
+        let after = (old.len() - tail.old_end).min(options.context);
+        let mut hunk = Hunk {
+            old_start: head.old_start - before,
+            old_lines: tail.old_end + after - (head.old_start - before),
+            new_start: head.new_start - before,
+            new_lines: tail.new_end + after - (head.new_start - before),
+            lines: Vec::new(),
+        };
+
+        let (mut old_line, mut new_line) = (hunk.old_start, hunk.new_start);
+        for change in group {
+            while old_line < change.old_start {
+                hunk.lines
+                    .push(equal_line(old[old_line], old_line, new_line));
+                old_line += 1;
+                new_line += 1;
+            }
+            let mut lines = changed_lines(old, new, change, options.words);
+            hunk.lines.append(&mut lines);
+            old_line = change.old_end;
+            new_line = change.new_end;
+        }
+        while old_line < tail.old_end + after {
+            hunk.lines
+                .push(equal_line(old[old_line], old_line, new_line));
+            old_line += 1;
+            new_line += 1;
+        }
+        hunks.push(hunk);
+    }
+    hunks
+}
+
+fn equal_line(text: &str, old_line: usize, new_line: usize) -> DiffLine {
+    DiffLine {
+        kind: DiffKind::Equal,
+        text: trim_line_ending(text).to_string(),
+        old_line: Some(old_line),
+        new_line: Some(new_line),
+        words: None,
+    }
+}
+
+// the removed lines then the added ones, with their changed words
+fn changed_lines(old: &[&str], new: &[&str], change: &Change, words: bool) -> Vec<DiffLine> {
+    let removed = &old[change.old_start..change.old_end];
+    let added = &new[change.new_start..change.new_end];
+    let (removed_words, added_words) = match words && !removed.is_empty() && !added.is_empty() {
+        true => word_ranges(removed, added),
+        false => (Vec::new(), Vec::new()),
+    };
+
+    let mut lines = Vec::new();
+    for (index, text) in removed.iter().enumerate() {
+        lines.push(DiffLine {
+            kind: DiffKind::Removed,
+            text: trim_line_ending(text).to_string(),
+            old_line: Some(change.old_start + index),
+            new_line: None,
+            words: removed_words.get(index).cloned(),
+        });
+    }
+    for (index, text) in added.iter().enumerate() {
+        lines.push(DiffLine {
+            kind: DiffKind::Added,
+            text: trim_line_ending(text).to_string(),
+            old_line: None,
+            new_line: Some(change.new_start + index),
+            words: added_words.get(index).cloned(),
+        });
+    }
+    lines
+}
+
+// byte ranges of the changed words, by line
+type LineRanges = Vec<Vec<(usize, usize)>>;
+
+// the changed words of every line of both blocks
+fn word_ranges(old: &[&str], new: &[&str]) -> (LineRanges, LineRanges) {
+    let old_words = words(old);
+    let new_words = words(new);
+    let old_texts: Vec<&str> = old_words.iter().map(|word| word.text).collect();
+    let new_texts: Vec<&str> = new_words.iter().map(|word| word.text).collect();
+
+    let mut old_ranges = vec![Vec::new(); old.len()];
+    let mut new_ranges = vec![Vec::new(); new.len()];
+    for change in changes(&old_texts, &new_texts) {
+        for word in &old_words[change.old_start..change.old_end] {
+            add_range(&mut old_ranges[word.line], word.range);
+        }
+        for word in &new_words[change.new_start..change.new_end] {
+            add_range(&mut new_ranges[word.line], word.range);
+        }
+    }
+    (old_ranges, new_ranges)
+}
+
+struct Word<'a> {
+    text: &'a str,
+    line: usize,
+    range: (usize, usize), // in the line
+}
+
+// runs of word characters, runs of blanks and single other characters
+fn words<'a>(lines: &[&'a str]) -> Vec<Word<'a>> {
+    let mut words = Vec::new();
+    for (line, text) in lines.iter().enumerate() {
+        let text = trim_line_ending(text);
+        let mut chars = text.char_indices().peekable();
+        while let Some((start, c)) = chars.next() {
+            let class = char_class(c);
+            let mut end = start + c.len_utf8();
+            if class != CharClass::Other {
+                while let Some((next, c)) = chars.peek().copied() {
+                    if char_class(c) != class {
+                        break;
+                    }
+                    end = next + c.len_utf8();
+                    chars.next();
+                }
+            }
+            words.push(Word {
+                text: &text[start..end],
+                line,
+                range: (start, end),
+            });
+        }
+        // the line break is a word, the words of two lines are never merged
+        words.push(Word {
+            text: "\n",
+            line,
+            range: (text.len(), text.len()),
+        });
+    }
+    words
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum CharClass {
+    Word,
+    Blank,
+    Other,
+}
+
+fn char_class(c: char) -> CharClass {
+    if c.is_alphanumeric() || c == '_' {
+        CharClass::Word
+    } else if c.is_whitespace() {
+        CharClass::Blank
+    } else {
+        CharClass::Other
+    }
+}
+
+// the range is merged with the previous one when they touch, empty ranges are left out
+fn add_range(ranges: &mut Vec<(usize, usize)>, range: (usize, usize)) {
+    if range.0 == range.1 {
+        return;
+    }
+    match ranges.last_mut() {
+        Some(last) if last.1 == range.0 => last.1 = range.1,
+        _ => ranges.push(range),
+    }
+}
+
+// the hunks as `diff -u` prints them
+pub fn unified(text_diff: &TextDiff, options: &DiffOptions) -> String {
+    let mut unified = String::new();
+    if text_diff.hunks.is_empty() {
+        return unified;
+    }
+    let _ = writeln!(
+        unified,
+        "--- {}",
+        options.old_name.as_deref().unwrap_or("old")
+    );
+    let _ = writeln!(
+        unified,
+        "+++ {}",
+        options.new_name.as_deref().unwrap_or("new")
+    );
+
+    // 1 based, an empty range starts at the line before it
+    let range = |start: usize, lines: usize| match lines {
+        0 => format!("{},0", start),
+        1 => format!("{}", start + 1),
+        _ => format!("{},{}", start + 1, lines),
+    };
+    for hunk in &text_diff.hunks {
+        let _ = writeln!(
+            unified,
+            "@@ -{} +{} @@",
+            range(hunk.old_start, hunk.old_lines),
+            range(hunk.new_start, hunk.new_lines)
+        );
+        for line in &hunk.lines {
+            let prefix = match line.kind {
+                DiffKind::Equal => ' ',
+                DiffKind::Added => '+',
+                DiffKind::Removed => '-',
+            };
+            let _ = writeln!(unified, "{}{}", prefix, line.text);
+        }
+    }
+    unified
+}
+
+// endregion
+
+// region: ---Myers
+
+// the shortest list of changes turning `old` into `new`
+pub fn changes<T: Eq + Hash>(old: &[T], new: &[T]) -> Vec<Change> {
+    // the items are compared by their id
+    let mut ids: HashMap<&T, u32> = HashMap::new();
+    let mut id = |item| {
+        let next = ids.len() as u32;
+        *ids.entry(item).or_insert(next)
+    };
+    let old_ids: Vec<u32> = old.iter().map(&mut id).collect();
+    let new_ids: Vec<u32> = new.iter().map(&mut id).collect();
+
+    let mut removed = vec![false; old.len()];
+    let mut added = vec![false; new.len()];
+    compare(
+        &old_ids,
+        &new_ids,
+        (0, old.len()),
+        (0, new.len()),
+        &mut removed,
+        &mut added,
+    );
+
+    let mut changes = Vec::new();
+    let (mut x, mut y) = (0, 0);
+    while x < old.len() || y < new.len() {
+        while x < old.len() && y < new.len() && !removed[x] && !added[y] {
+            x += 1;
+            y += 1;
+        }
+        let (old_start, new_start) = (x, y);
+        while x < old.len() && removed[x] {
+            x += 1;
+        }
+        while y < new.len() && added[y] {
+            y += 1;
+        }
+        if (x, y) == (old_start, new_start) {
+            break;
+        }
+        changes.push(Change {
+            old_start,
+            old_end: x,
+            new_start,
+            new_end: y,
+        });
+    }
+    changes
+}
+
+// the removed and added items of the ranges, split at the middle of an optimal path
+// (linear space divide and conquer)
+fn compare(
+    old: &[u32],
+    new: &[u32],
+    (mut x0, mut x1): (usize, usize),
+    (mut y0, mut y1): (usize, usize),
+    removed: &mut [bool],
+    added: &mut [bool],
+) {
+    while x0 < x1 && y0 < y1 && old[x0] == new[y0] {
+        x0 += 1;
+        y0 += 1;
+    }
+    while x0 < x1 && y0 < y1 && old[x1 - 1] == new[y1 - 1] {
+        x1 -= 1;
+        y1 -= 1;
+    }
+    if x0 == x1 {
+        added[y0..y1].fill(true);
+        return;
+    }
+    if y0 == y1 {
+        removed[x0..x1].fill(true);
+        return;
+    }
+    let (x, y) = middle_snake(old, new, (x0, x1), (y0, y1));
+    compare(old, new, (x0, x), (y0, y), removed, added);
+    compare(old, new, (x, x1), (y, y1), removed, added);
+}
+
+// a point of an optimal path found by searching from both ends at once, it is neither the
+// start nor the end when the ranges have no common start or end
+fn middle_snake(
+    old: &[u32],
+    new: &[u32],
+    (x0, x1): (usize, usize),
+    (y0, y1): (usize, usize),
+) -> (usize, usize) {
+    let (n, m) = ((x1 - x0) as isize, (y1 - y0) as isize);
+    let delta = n - m;
+    let odd = delta % 2 != 0;
+    let max = (n + m + 1) / 2;
+    let offset = max + 1;
+
+    // furthest x on every diagonal k = x - y, from the start and from the end
+    let mut forward = vec![0isize; 2 * offset as usize + 1];
+    let mut backward = vec![0isize; 2 * offset as usize + 1];
+    let at = |k: isize| (k + offset) as usize;
+
+    for d in 0..=max {
+        for k in (-d..=d).step_by(2) {
+            let mut x = match k == -d || (k != d && forward[at(k - 1)] < forward[at(k + 1)]) {
+                true => forward[at(k + 1)],
+                false => forward[at(k - 1)] + 1,
+            };
+            let mut y = x - k;
+            while x < n && y < m && old[x0 + x as usize] == new[y0 + y as usize] {
+                x += 1;
+                y += 1;
+            }
+            forward[at(k)] = x;
+            let reverse_k = delta - k;
+            if odd && (-(d - 1)..=d - 1).contains(&reverse_k) && x + backward[at(reverse_k)] >= n {
+                return (x0 + x as usize, y0 + y as usize);
+            }
+        }
+        for k in (-d..=d).step_by(2) {
+            let mut x = match k == -d || (k != d && backward[at(k - 1)] < backward[at(k + 1)]) {
+                true => backward[at(k + 1)],
+                false => backward[at(k - 1)] + 1,
+            };
+            let mut y = x - k;
+            while x < n && y < m && old[x1 - 1 - x as usize] == new[y1 - 1 - y as usize] {
+                x += 1;
+                y += 1;
+            }
+            backward[at(k)] = x;
+            let forward_k = delta - k;
+            if !odd && (-d..=d).contains(&forward_k) && forward[at(forward_k)] + x >= n {
+                return (x1 - x as usize, y1 - y as usize);
+            }
+        }
+    }
+    unreachable!("the paths from both ends always meet")
+}
+
+// endregion
+
+// region: ---Merge
+
+/// # Merge
+/// {
+///     text: string,
+///     conflicts: [{
+///         line: number,   // 0 based line of the <<<<<<< marker in the text
+///         lines: number,  // with the markers
+///         base: string,   // text of every side in the conflict
+///         ours: string,
+///         theirs: string,
+///     }, ...],
+///     clean: bool,       // no conflict
+/// }
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct Merge {
+    pub text: String,
+    pub conflicts: Vec<MergeConflict>,
+    pub clean: bool,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct MergeConflict {
+    pub line: usize,
+    pub lines: usize,
+    pub base: String,
+    pub ours: String,
+    pub theirs: String,
+}
+
+// three-way merge of the lines (diff3), a conflict is a range of the base changed on both
+// sides into different lines
+pub fn merge(base: &str, ours: &str, theirs: &str, labels: &MergeLabels) -> Merge {
+    let base_lines = split_lines(base);
+    let our_lines = split_lines(ours);
+    let their_lines = split_lines(theirs);
+    let our_changes = changes(&base_lines, &our_lines);
+    let their_changes = changes(&base_lines, &their_lines);
+
+    let mut all: Vec<(Change, bool)> = our_changes
+        .iter()
+        .map(|change| (*change, true))
+        .chain(their_changes.iter().map(|change| (*change, false)))
+        .collect();
+    all.sort_by_key(|(change, _)| (change.old_start, change.old_end));
+
+    let mut text = String::new();
+    let mut conflicts = Vec::new();
+    let mut line_count = 0;
+    let push = |text: &mut String, lines: &[&str]| {
+        for line in lines {
+            text.push_str(line);
+        }
+        lines.len()
+    };
+
+    let mut base_line = 0;
+    let mut index = 0;
+    while index < all.len() {
+        // the changes overlapping or touching the first one
+        let (start, mut end) = (all[index].0.old_start, all[index].0.old_end);
+        let (mut has_ours, mut has_theirs) = (false, false);
+        let mut next = index;
+        while next < all.len() && all[next].0.old_start <= end {
+            end = end.max(all[next].0.old_end);
+            match all[next].1 {
+                true => has_ours = true,
+                false => has_theirs = true,
+            }
+            next += 1;
+        }
+        index = next;
+
+        line_count += push(&mut text, &base_lines[base_line..start]);
+        base_line = end;
+
+        let (our_start, our_end) = side_range(&our_changes, start, end);
+        let (their_start, their_end) = side_range(&their_changes, start, end);
+        let our_block = &our_lines[our_start..our_end];
+        let their_block = &their_lines[their_start..their_end];
+
+        if !has_theirs || (has_ours && our_block == their_block) {
+            line_count += push(&mut text, our_block);
+        } else if !has_ours {
+            line_count += push(&mut text, their_block);
+        } else {
+            let first_line = line_count;
+            ensure_line_ending(&mut text);
+            let _ = writeln!(text, "<<<<<<< {}", labels.ours);
+            line_count += 1 + push(&mut text, our_block);
+            ensure_line_ending(&mut text);
+            text.push_str("=======\n");
+            line_count += 1 + push(&mut text, their_block);
+            ensure_line_ending(&mut text);
+            let _ = writeln!(text, ">>>>>>> {}", labels.theirs);
+            line_count += 1;
+            conflicts.push(MergeConflict {
+                line: first_line,
+                lines: line_count - first_line,
+                base: base_lines[start..end].concat(),
+                ours: our_block.concat(),
+                theirs: their_block.concat(),
+            });
+        }
+    }
+    push(&mut text, &base_lines[base_line..]);
+
+    Merge {
+        text,
+        clean: conflicts.is_empty(),
+        conflicts,
+    }
+}
+
+// the lines of a side for a range of the base, the changes of the side are in the range
+// or out of it
+fn side_range(changes: &[Change], start: usize, end: usize) -> (usize, usize) {
+    let mut before: isize = 0;
+    let mut inside: isize = 0;
+    for change in changes {
+        let delta = (change.new_end - change.new_start) as isize
+            - (change.old_end - change.old_start) as isize;
+        if change.old_start < start {
+            before += delta;
+        } else if change.old_start <= end {
+            inside += delta;
+        }
+    }
+    (
+        (start as isize + before) as usize,
+        (end as isize + before + inside) as usize,
+    )
+}
+
+// a marker never ends the last line of a side without a line ending
+fn ensure_line_ending(text: &mut String) {
+    if !text.is_empty() && !text.ends_with('\n') {
+        text.push('\n');
+    }
+}
+
+// endregion
+
+// region: ---Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn change(old_start: usize, old_end: usize, new_start: usize, new_end: usize) -> Change {
+        Change {
+            old_start,
+            old_end,
+            new_start,
+            new_end,
+        }
+    }
+
+    // (kind, text) of the lines of the hunks
+    fn lines(text_diff: &TextDiff) -> Vec<(DiffKind, &str)> {
+        text_diff
+            .hunks
+            .iter()
+            .flat_map(|hunk| &hunk.lines)
+            .map(|line| (line.kind, line.text.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn empty_inputs_have_no_changes() {
+        let empty: [&str; 0] = [];
+        assert!(changes(&empty, &empty).is_empty());
+
+        let text_diff = diff("", "", &DiffOptions::default());
+        assert!(text_diff.hunks.is_empty());
+        assert_eq!((text_diff.added, text_diff.removed), (0, 0));
+        assert_eq!(unified(&text_diff, &DiffOptions::default()), "");
+
+        let added = diff("", "a\nb\n", &DiffOptions::default());
+        assert_eq!((added.added, added.removed), (2, 0));
+        assert_eq!(added.hunks[0].old_lines, 0);
+    }
+
+    #[test]
+    fn insertions_and_deletions_are_single_changes() {
+        assert_eq!(changes(&[1, 2, 3], &[1, 4, 5, 2, 3]), [change(1, 1, 1, 3)]);
+        assert_eq!(changes(&[1, 2, 3, 4], &[1, 4]), [change(1, 3, 1, 1)]);
+        assert_eq!(
+            changes(&[1, 2, 3], &[4, 2, 5]),
+            [change(0, 1, 0, 1), change(2, 3, 2, 3)]
+        );
+    }
+
+    #[test]
+    fn insert_only_and_delete_only_diffs() {
+        let options = DiffOptions {
+            context: 1,
+            ..DiffOptions::default()
+        };
+        let inserted = diff("a\nb\nc\n", "a\nb\nx\nc\n", &options);
+        assert_eq!((inserted.added, inserted.removed), (1, 0));
+        assert_eq!(
+            lines(&inserted),
+            [
+                (DiffKind::Equal, "b"),
+                (DiffKind::Added, "x"),
+                (DiffKind::Equal, "c"),
+            ]
+        );
+
+        let deleted = diff("a\nb\nc\nd\n", "a\nd\n", &options);
+        assert_eq!((deleted.added, deleted.removed), (0, 2));
+        assert_eq!(
+            lines(&deleted),
+            [
+                (DiffKind::Equal, "a"),
+                (DiffKind::Removed, "b"),
+                (DiffKind::Removed, "c"),
+                (DiffKind::Equal, "d"),
+            ]
+        );
+    }
+
+    #[test]
+    fn last_line_without_a_line_ending() {
+        assert_eq!(split_lines("a\nb"), ["a\n", "b"]);
+        assert_eq!(split_lines("a\r\nb\n"), ["a\r\n", "b\n"]);
+
+        // the line endings are not compared
+        assert!(diff("a\nb", "a\r\nb\n", &DiffOptions::default())
+            .hunks
+            .is_empty());
+
+        let options = DiffOptions {
+            unified: true,
+            ..DiffOptions::default()
+        };
+        let text_diff = diff("a\nb", "a\nc", &options);
+        assert_eq!(
+            text_diff.unified.as_deref(),
+            Some("--- old\n+++ new\n@@ -1,2 +1,2 @@\n a\n-b\n+c\n")
+        );
+    }
+
+    #[test]
+    fn changes_on_one_side_merge_cleanly() {
+        let base = "a\nb\nc\nd\n";
+        let merged = merge(
+            base,
+            "a\nB\nc\nd\n",
+            "a\nb\nc\nD\n",
+            &MergeLabels::default(),
+        );
+        assert!(merged.clean);
+        assert_eq!(merged.text, "a\nB\nc\nD\n");
+
+        // the same change on both sides is no conflict
+        let same = merge(
+            base,
+            "a\nx\nc\nd\n",
+            "a\nx\nc\nd\n",
+            &MergeLabels::default(),
+        );
+        assert!(same.clean);
+        assert_eq!(same.text, "a\nx\nc\nd\n");
+    }
+
+    #[test]
+    fn different_changes_of_a_line_conflict() {
+        let merged = merge("a\nb\nc", "a\nx\nc", "a\ny\nc", &MergeLabels::default());
+        assert!(!merged.clean);
+        assert_eq!(
+            merged.text,
+            "a\n<<<<<<< ours\nx\n=======\ny\n>>>>>>> theirs\nc"
+        );
+        let conflict = &merged.conflicts[0];
+        assert_eq!((conflict.line, conflict.lines), (1, 5));
+        assert_eq!(
+            (
+                conflict.base.as_str(),
+                conflict.ours.as_str(),
+                conflict.theirs.as_str()
+            ),
+            ("b\n", "x\n", "y\n")
+        );
+    }
+
+    #[test]
+    fn conflict_markers_start_their_own_line() {
+        let labels = MergeLabels {
+            ours: String::from("mine"),
+            theirs: String::from("disk"),
+        };
+        let merged = merge("a\nb", "a\nx", "a\ny", &labels);
+        assert_eq!(
+            merged.text,
+            "a\n<<<<<<< mine\nx\n=======\ny\n>>>>>>> disk\n"
+        );
+    }
+}
+
+// endregion
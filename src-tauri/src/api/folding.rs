@@ -0,0 +1,271 @@
+use crate::api::brackets::{lossy_tokens, pair_brackets};
+use crate::api::grammar::rules::Context;
+use crate::api::language::{get_language, Language};
+use crate::api::outline::{context_text, Target};
+use std::fmt::{Display, Formatter};
+
+// region: ---Tauri Command
+
+#[tauri::command]
+pub async fn get_folding_ranges(text: &str, language: &str) -> Result<Vec<FoldingRange>, String> {
+    let language = get_language(language).ok_or(Error::UnknownLanguage)?;
+    folding_ranges(&language, text).map_err(|err| err.to_string())
+}
+
+// endregion
+
+// region: ---Error
+#[derive(Debug)]
+pub enum Error {
+    UnknownLanguage,
+    Rule(crate::api::outline::Error),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::UnknownLanguage => write!(f, "unknown language"),
+            Error::Rule(err) => write!(f, "folding rules: {}", err),
+        }
+    }
+}
+
+impl From<Error> for String {
+    fn from(err: Error) -> Self {
+        err.to_string()
+    }
+}
+
+// endregion
+
+// region: ---Folding Rule
+
+/// # FoldingRule
+/// {
+///     fragment?: string,  // fragment or token folded when it spans several lines
+///     token?: string,
+///     kind: string,       // "region", "code", "frontmatter", "section", ...
+///     level?: char,       // the range goes until the next fragment or token with the same
+///                         // or a lower number of this char at its start (markdown sections)
+/// }
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FoldingRule {
+    pub fragment: Option<Box<str>>,
+    pub token: Option<Box<str>>,
+    pub kind: Box<str>,
+    pub level: Option<char>,
+}
+
+// endregion
+
+// region: ---Folding Range
+
+#[derive(Debug, serde::Serialize)]
+pub struct FoldingRange {
+    pub start_line: usize, // first line of the range (0 based)
+    pub end_line: usize,   // last line of the range
+    pub kind: Box<str>,
+}
+
+pub fn folding_ranges(language: &Language, text: &str) -> Result<Vec<FoldingRange>, Error> {
+    let grammar = language.get_grammar();
+    let rules = language
+        .get_folding_rules()
+        .iter()
+        .map(|rule| {
+            let name = rule.fragment.as_ref().or(rule.token.as_ref());
+            let target = Target::new(name.map(|name| name.as_ref()).unwrap_or(""), grammar);
+            target.map(|target| (rule, target)).map_err(Error::Rule)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let region = FoldingRule {
+        fragment: None,
+        token: None,
+        kind: Box::from("region"),
+        level: None,
+    };
+
+    // (span, kind) of the folded contexts, level of the contexts folded until the next one
+    let mut spans: Vec<((usize, usize), &FoldingRule)> = Vec::new();
+    let mut levels: Vec<(usize, usize, &FoldingRule)> = Vec::new(); // (start, level, rule)
+    match grammar.parse(text) {
+        Some(context) => collect_spans(&context, &rules, &mut spans, &mut levels),
+        None => {
+            // the text being edited is not valid: the rules of the tokens apply to the tokens
+            // found in it and its matched brackets are folded as regions
+            let tokens = lossy_tokens(grammar, text);
+            for (definition, span) in &tokens {
+                let target = Target::Token(*definition);
+                if let Some((rule, _)) =
+                    rules.iter().find(|(_, rule_target)| *rule_target == target)
+                {
+                    add_span(rule, *span, &text[span.0..span.1], &mut spans, &mut levels);
+                }
+            }
+            for (open, close) in pair_brackets(grammar, &tokens).matched {
+                spans.push(((open.0, close.1), &region));
+            }
+        }
+    }
+
+    for (index, (start, level, rule)) in levels.iter().enumerate() {
+        let end = levels[index + 1..]
+            .iter()
+            .find(|(_, next_level, next_rule)| {
+                std::ptr::eq(*next_rule, *rule) && next_level <= level
+            })
+            .map(|(next_start, _, _)| *next_start)
+            .unwrap_or(text.len());
+        spans.push(((*start, end), rule));
+    }
+
+    let lines = LineIndex::new(text);
+    let mut ranges: Vec<FoldingRange> = spans
+        .into_iter()
+        .filter_map(|((start, end), rule)| {
+            // the trailing blank lines are not folded
+            let end = start + text[start..end].trim_end().len();
+            let start_line = lines.line(start);
+            let end_line = lines.line(end.saturating_sub(1).max(start));
+            if end_line > start_line {
+                Some(FoldingRange {
+                    start_line,
+                    end_line,
+                    kind: rule.kind.clone(),
+                })
+            } else {
+                None
+            }
+        })
+        .collect();
+
+    ranges.sort_by_key(|range| (range.start_line, std::cmp::Reverse(range.end_line)));
+    Ok(ranges)
+}
+
+fn collect_spans<'a>(
+    context: &Context,
+    rules: &[(&'a FoldingRule, Target)],
+    spans: &mut Vec<((usize, usize), &'a FoldingRule)>,
+    levels: &mut Vec<(usize, usize, &'a FoldingRule)>,
+) {
+    if let Some((rule, _)) = rules.iter().find(|(_, target)| target.matches(context)) {
+        if let Some(span) = context.span() {
+            add_span(rule, span, &context_text(context), spans, levels);
+        }
+    }
+
+    for child in context.children() {
+        collect_spans(child, rules, spans, levels);
+    }
+}
+
+fn add_span<'a>(
+    rule: &'a FoldingRule,
+    span: (usize, usize),
+    text: &str,
+    spans: &mut Vec<((usize, usize), &'a FoldingRule)>,
+    levels: &mut Vec<(usize, usize, &'a FoldingRule)>,
+) {
+    match rule.level {
+        Some(level) => {
+            let count = text.chars().take_while(|c| *c == level).count();
+            levels.push((span.0, count, rule));
+        }
+        None => spans.push((span, rule)),
+    }
+}
+
+// line of a byte offset
+pub(crate) struct LineIndex {
+    line_starts: Vec<usize>,
+}
+
+impl LineIndex {
+    pub(crate) fn new(text: &str) -> Self {
+        let mut line_starts = vec![0];
+        line_starts.extend(text.match_indices('\n').map(|(index, _)| index + 1));
+        LineIndex { line_starts }
+    }
+
+    pub(crate) fn line(&self, offset: usize) -> usize {
+        match self.line_starts.binary_search(&offset) {
+            Ok(line) => line,
+            Err(line) => line - 1,
+        }
+    }
+
+    // byte offset of the start of the line
+    pub(crate) fn line_start(&self, line: usize) -> usize {
+        self.line_starts[line]
+    }
+}
+
+// endregion
+
+// region: ---Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // (start line, end line, kind) of the ranges
+    fn ranges(language: &str, text: &str) -> Vec<(usize, usize, String)> {
+        folding_ranges(&get_language(language).unwrap(), text)
+            .unwrap()
+            .into_iter()
+            .map(|range| (range.start_line, range.end_line, range.kind.to_string()))
+            .collect()
+    }
+
+    fn range(start: usize, end: usize, kind: &str) -> (usize, usize, String) {
+        (start, end, kind.to_string())
+    }
+
+    #[test]
+    fn json_groups_on_several_lines_are_folded() {
+        let text = "{\n  \"a\": [1, 2],\n  \"b\": {\n    \"c\": 1\n  }\n}\n";
+        assert_eq!(
+            ranges("json", text),
+            [range(0, 5, "region"), range(2, 4, "region")]
+        );
+    }
+
+    #[test]
+    fn invalid_json_folds_its_matched_brackets() {
+        let text = "{\n  \"a\": [\n    1,\n  ],\n  \"b\": \n}\n[";
+        assert_eq!(
+            ranges("json", text),
+            [range(0, 5, "region"), range(1, 3, "region")]
+        );
+    }
+
+    #[test]
+    fn markdown_sections_end_before_the_next_heading() {
+        let text = "# A\ntext\n## B\ntext\n\n# C\n```\ncode\n```\n";
+        assert_eq!(
+            ranges("markdown", text),
+            [
+                range(0, 3, "section"),
+                range(2, 3, "section"),
+                range(5, 8, "section"),
+                range(6, 8, "code"),
+            ]
+        );
+    }
+
+    #[test]
+    fn line_index_finds_the_line_of_an_offset() {
+        let lines = LineIndex::new("ab\ncd\n\nef");
+        assert_eq!(lines.line(0), 0);
+        assert_eq!(lines.line(2), 0);
+        assert_eq!(lines.line(3), 1);
+        assert_eq!(lines.line(6), 2);
+        assert_eq!(lines.line(9), 3);
+        assert_eq!(lines.line_start(3), 7);
+    }
+}
+
+// endregion
@@ -0,0 +1,492 @@
+use crate::api::grammar::grammar::Grammar;
+use crate::api::grammar::rules::TokenContext;
+use crate::api::language::{get_language, Language};
+use std::fmt::{Display, Formatter};
+
+// region: ---Tauri Command
+
+// offsets are byte offsets in the text
+#[tauri::command]
+pub async fn format_document(
+    text: &str,
+    language: &str,
+    options: Option<FormatOptions>,
+) -> Result<String, String> {
+    let language = get_language(language).ok_or(Error::UnknownLanguage)?;
+    format(&language, text, &options.unwrap_or_default()).map_err(|err| err.to_string())
+}
+
+#[tauri::command]
+pub async fn format_selection(
+    text: &str,
+    language: &str,
+    start: usize,
+    end: usize,
+    options: Option<FormatOptions>,
+) -> Result<TextEdit, String> {
+    let language = get_language(language).ok_or(Error::UnknownLanguage)?;
+    format_range(&language, text, (start, end), &options.unwrap_or_default())
+        .map_err(|err| err.to_string())
+}
+
+// endregion
+
+// region: ---Error
+#[derive(Debug)]
+pub enum Error {
+    UnknownLanguage,
+    NoFormatRules,
+    UnknownToken(Box<str>),
+    InvalidText,
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::UnknownLanguage => write!(f, "unknown language"),
+            Error::NoFormatRules => write!(f, "the language has no format rules"),
+            Error::UnknownToken(name) => write!(f, "unknown token in format rules: {}", name),
+            Error::InvalidText => write!(f, "the text does not match the grammar"),
+        }
+    }
+}
+
+impl From<Error> for String {
+    fn from(err: Error) -> Self {
+        err.to_string()
+    }
+}
+
+// endregion
+
+// region: ---Format Rules
+
+/// # FormatRules
+/// {
+///     indent: string,                       // "    "
+///     lineWidth: usize,                     // groups longer than this are broken on several lines
+///     groups: [[openToken, closeToken], ...],
+///     separators: [tokenName, ...],         // a broken group goes to the next line after them
+///     spaceBefore: [tokenName, ...],
+///     spaceAfter: [tokenName, ...],
+///     trailingSeparator?: string,           // added after the last value of a broken group
+///     finalNewline: bool,
+/// }
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct FormatRules {
+    pub indent: Box<str>,
+    pub line_width: usize,
+    pub groups: Vec<(Box<str>, Box<str>)>,
+    pub separators: Vec<Box<str>>,
+    pub space_before: Vec<Box<str>>,
+    pub space_after: Vec<Box<str>>,
+    pub trailing_separator: Option<Box<str>>,
+    pub final_newline: bool,
+}
+
+impl Default for FormatRules {
+    fn default() -> Self {
+        FormatRules {
+            indent: Box::from("    "),
+            line_width: 80,
+            groups: Vec::new(),
+            separators: Vec::new(),
+            space_before: Vec::new(),
+            space_after: Vec::new(),
+            trailing_separator: None,
+            final_newline: true,
+        }
+    }
+}
+
+// editor settings overriding the rules of the language
+#[derive(Debug, Default, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FormatOptions {
+    pub indent: Option<Box<str>>,
+    pub line_width: Option<usize>,
+    pub trailing_separator: Option<bool>,
+}
+
+// format rules with the token names replaced by their index in the grammar
+struct ResolvedRules {
+    indent: Box<str>,
+    line_width: usize,
+    groups: Vec<(usize, usize)>,
+    separators: Vec<usize>,
+    space_before: Vec<usize>,
+    space_after: Vec<usize>,
+    trailing_separator: Option<Box<str>>,
+    final_newline: bool,
+}
+
+impl ResolvedRules {
+    fn new(rules: &FormatRules, grammar: &Grammar, options: &FormatOptions) -> Result<Self, Error> {
+        let index = |name: &str| {
+            grammar
+                .get_token_definition_index(name)
+                .ok_or_else(|| Error::UnknownToken(Box::from(name)))
+        };
+        let indexes = |names: &[Box<str>]| {
+            names
+                .iter()
+                .map(|name| index(name))
+                .collect::<Result<Vec<_>, _>>()
+        };
+
+        let mut groups = Vec::with_capacity(rules.groups.len());
+        for (open, close) in &rules.groups {
+            groups.push((index(open)?, index(close)?));
+        }
+
+        let trailing_separator = match options.trailing_separator {
+            Some(false) => None,
+            _ => rules.trailing_separator.clone(),
+        };
+
+        Ok(ResolvedRules {
+            indent: options.indent.clone().unwrap_or(rules.indent.clone()),
+            line_width: options.line_width.unwrap_or(rules.line_width),
+            groups,
+            separators: indexes(&rules.separators)?,
+            space_before: indexes(&rules.space_before)?,
+            space_after: indexes(&rules.space_after)?,
+            trailing_separator,
+            final_newline: rules.final_newline,
+        })
+    }
+}
+
+// endregion
+
+// region: ---Format
+
+#[derive(Debug, serde::Serialize)]
+pub struct TextEdit {
+    pub start: usize,
+    pub end: usize,
+    pub text: String,
+}
+
+pub fn format(language: &Language, text: &str, options: &FormatOptions) -> Result<String, Error> {
+    let rules = language.get_format_rules().ok_or(Error::NoFormatRules)?;
+    let grammar = language.get_grammar();
+    let rules = ResolvedRules::new(rules, grammar, options)?;
+
+    let context = grammar.parse(text).ok_or(Error::InvalidText)?;
+    let tokens = context.tokens();
+
+    let matching = match_groups(&rules, &tokens);
+    let mut printer = Printer::new(&rules, &tokens, matching, String::new(), 0);
+    printer.print_tokens(0, tokens.len(), false);
+    Ok(printer.finish())
+}
+
+// format the smallest group containing the range, or the whole text if there is none
+pub fn format_range(
+    language: &Language,
+    text: &str,
+    range: (usize, usize),
+    options: &FormatOptions,
+) -> Result<TextEdit, Error> {
+    let rules = language.get_format_rules().ok_or(Error::NoFormatRules)?;
+    let grammar = language.get_grammar();
+    let resolved_rules = ResolvedRules::new(rules, grammar, options)?;
+
+    let context = grammar.parse(text).ok_or(Error::InvalidText)?;
+    let tokens = context.tokens();
+    let matching = match_groups(&resolved_rules, &tokens);
+
+    let group = matching
+        .iter()
+        .enumerate()
+        .filter_map(|(open, close)| close.map(|close| (open, close)))
+        .filter(|(open, close)| {
+            tokens[*open].span().0 <= range.0 && range.1 <= tokens[*close].span().1
+        })
+        .min_by_key(|(open, close)| close - open);
+
+    let (open, close) = match group {
+        Some(group) => group,
+        None => {
+            return Ok(TextEdit {
+                start: 0,
+                end: text.len(),
+                text: format(language, text, options)?,
+            })
+        }
+    };
+
+    // keep the indentation of the line of the group
+    let start = tokens[open].span().0;
+    let line_start = text[..start]
+        .rfind('\n')
+        .map(|index| index + 1)
+        .unwrap_or(0);
+    let line = &text[line_start..start];
+    let base_indent: String = line
+        .chars()
+        .take_while(|c| *c == ' ' || *c == '\t')
+        .collect();
+    let column = line.chars().count();
+
+    let mut printer = Printer::new(&resolved_rules, &tokens, matching, base_indent, column);
+    printer.print_group(open, close);
+
+    Ok(TextEdit {
+        start,
+        end: tokens[close].span().1,
+        text: printer.out,
+    })
+}
+
+// index of the close token of every open token
+fn match_groups(rules: &ResolvedRules, tokens: &[&TokenContext]) -> Vec<Option<usize>> {
+    let mut matching = vec![None; tokens.len()];
+    let mut stack: Vec<(usize, usize)> = Vec::new(); // (token index, close definition index)
+
+    for (index, token) in tokens.iter().enumerate() {
+        let definition = token.definition_index();
+        if let Some((open_index, close_definition)) = stack.last() {
+            if *close_definition == definition {
+                matching[*open_index] = Some(index);
+                stack.pop();
+                continue;
+            }
+        }
+        if let Some((_, close)) = rules.groups.iter().find(|(open, _)| *open == definition) {
+            stack.push((index, *close));
+        }
+    }
+
+    matching
+}
+
+struct Printer<'a> {
+    rules: &'a ResolvedRules,
+    tokens: &'a [&'a TokenContext],
+    matching: Vec<Option<usize>>,
+    base_indent: String,
+    level: usize,
+    column: usize,
+    out: String,
+}
+
+impl<'a> Printer<'a> {
+    fn new(
+        rules: &'a ResolvedRules,
+        tokens: &'a [&'a TokenContext],
+        matching: Vec<Option<usize>>,
+        base_indent: String,
+        column: usize,
+    ) -> Self {
+        Printer {
+            rules,
+            tokens,
+            matching,
+            base_indent,
+            level: 0,
+            column,
+            out: String::new(),
+        }
+    }
+
+    fn finish(mut self) -> String {
+        if self.rules.final_newline && !self.out.is_empty() {
+            self.out.push('\n');
+        }
+        self.out
+    }
+
+    // print the tokens [from, to[, a broken group goes to the next line after its separators
+    fn print_tokens(&mut self, from: usize, to: usize, broken: bool) {
+        let mut index = from;
+        while index < to {
+            let next = match self.matching[index] {
+                Some(close) => {
+                    self.print_group(index, close);
+                    close + 1
+                }
+                None => {
+                    self.write(self.tokens[index].text());
+                    index + 1
+                }
+            };
+
+            if next < to {
+                if broken && self.is_separator(next - 1) {
+                    self.newline();
+                } else if self.space_between(next - 1, next) {
+                    self.write(" ");
+                }
+            }
+            index = next;
+        }
+    }
+
+    fn print_group(&mut self, open: usize, close: usize) {
+        let flat = self.flat_tokens(open, close);
+        if open + 1 == close || self.column + self.width(&flat) <= self.rules.line_width {
+            self.print_flat(&flat);
+            return;
+        }
+
+        // the trailing separator is written by the rules
+        let mut end = close;
+        if self.is_separator(close - 1) {
+            end -= 1;
+        }
+
+        self.write(self.tokens[open].text());
+        self.level += 1;
+        self.newline();
+        self.print_tokens(open + 1, end, true);
+        if let Some(trailing_separator) = &self.rules.trailing_separator {
+            self.write(trailing_separator);
+        }
+        self.level -= 1;
+        self.newline();
+        self.write(self.tokens[close].text());
+    }
+
+    // tokens of the group printed on one line (without trailing separators)
+    fn flat_tokens(&self, open: usize, close: usize) -> Vec<usize> {
+        (open..=close)
+            .filter(|index| {
+                !(self.is_separator(*index)
+                    && self
+                        .rules
+                        .groups
+                        .iter()
+                        .any(|(_, close)| self.tokens[index + 1].definition_index() == *close))
+            })
+            .collect()
+    }
+
+    fn print_flat(&mut self, indexes: &[usize]) {
+        for (position, index) in indexes.iter().enumerate() {
+            if position > 0 && self.space_between(indexes[position - 1], *index) {
+                self.write(" ");
+            }
+            self.write(self.tokens[*index].text());
+        }
+    }
+
+    fn width(&self, indexes: &[usize]) -> usize {
+        let mut width = 0;
+        for (position, index) in indexes.iter().enumerate() {
+            if position > 0 && self.space_between(indexes[position - 1], *index) {
+                width += 1;
+            }
+            width += self.tokens[*index].text().chars().count();
+        }
+        width
+    }
+
+    fn is_separator(&self, index: usize) -> bool {
+        self.rules
+            .separators
+            .contains(&self.tokens[index].definition_index())
+    }
+
+    fn space_between(&self, left: usize, right: usize) -> bool {
+        self.rules
+            .space_after
+            .contains(&self.tokens[left].definition_index())
+            || self
+                .rules
+                .space_before
+                .contains(&self.tokens[right].definition_index())
+    }
+
+    fn write(&mut self, text: &str) {
+        self.out.push_str(text);
+        match text.rfind('\n') {
+            Some(index) => self.column = text[index + 1..].chars().count(),
+            None => self.column += text.chars().count(),
+        }
+    }
+
+    fn newline(&mut self) {
+        self.out.push('\n');
+        self.out.push_str(&self.base_indent);
+        for _ in 0..self.level {
+            self.out.push_str(&self.rules.indent);
+        }
+        self.column =
+            self.base_indent.chars().count() + self.level * self.rules.indent.chars().count();
+    }
+}
+
+// endregion
+
+// region: ---Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::language::json::get_json_language;
+
+    fn format_json(text: &str, options: FormatOptions) -> String {
+        format(&get_json_language(), text, &options).unwrap()
+    }
+
+    #[test]
+    fn short_groups_stay_on_one_line() {
+        let text = format_json(r#"{"a":1,"b":[true,null]}"#, FormatOptions::default());
+        assert_eq!(text, "{\"a\": 1, \"b\": [true, null]}\n");
+    }
+
+    #[test]
+    fn long_groups_are_broken_and_indented() {
+        let options = FormatOptions {
+            line_width: Some(16),
+            ..FormatOptions::default()
+        };
+        let text = format_json(r#"{"a":1,"b":[1,2]}"#, options);
+        assert_eq!(text, "{\n    \"a\": 1,\n    \"b\": [1, 2]\n}\n");
+    }
+
+    #[test]
+    fn options_override_the_rules() {
+        let options = FormatOptions {
+            indent: Some(Box::from("\t")),
+            line_width: Some(0),
+            trailing_separator: None,
+        };
+        let text = format_json("[1,[]]", options);
+        assert_eq!(text, "[\n\t1,\n\t[]\n]\n");
+    }
+
+    #[test]
+    fn invalid_text_is_an_error() {
+        let result = format(&get_json_language(), "{", &FormatOptions::default());
+        assert!(matches!(result, Err(Error::InvalidText)));
+    }
+
+    #[test]
+    fn range_formats_the_smallest_group() {
+        let text = r#"{"a": [1,2], "b": 3}"#;
+        let start = text.find('1').unwrap();
+        let edit = format_range(
+            &get_json_language(),
+            text,
+            (start, start + 1),
+            &FormatOptions::default(),
+        )
+        .unwrap();
+        assert_eq!((edit.start, edit.end), (6, 11));
+        assert_eq!(edit.text, "[1, 2]");
+    }
+
+    #[test]
+    fn range_outside_groups_formats_the_text() {
+        let edit = format_range(&get_json_language(), "1", (0, 1), &FormatOptions::default());
+        let edit = edit.unwrap();
+        assert_eq!((edit.start, edit.end), (0, 1));
+        assert_eq!(edit.text, "1\n");
+    }
+}
+
+// endregion
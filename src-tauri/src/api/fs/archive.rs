@@ -0,0 +1,332 @@
+use crate::api::fs::listing::natural_cmp;
+use crate::api::fs::text::write_atomic;
+use crate::api::fs::vfs;
+use crate::api::workspace::Workspace;
+use crate::error::Error;
+use crate::APP_DATA_FOLDER_NAME;
+use std::collections::BTreeMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+use tauri::api::path::data_dir;
+use tauri::State;
+use xxhash_rust::xxh3::Xxh3;
+use zip::result::ZipError;
+use zip::{DateTime, ZipArchive};
+
+pub(crate) const EXTRACTED_FOLDER_NAME: &str = "archives";
+
+// larger entries are not extracted
+const MAX_EXTRACTED_SIZE: u64 = 256 * 1024 * 1024;
+
+// region: ---Tauri Command
+
+// copy of a file of an archive in the app data folder, read-only, for the viewers
+#[tauri::command]
+pub async fn extract_archive_file(
+    path: &str,
+    workspace: State<'_, Workspace>,
+) -> Result<String, Error> {
+    let path = workspace.resolve(path)?;
+    let (archive_path, name) =
+        split_archive_path(&path).ok_or_else(|| Error::NotFound(path.display().to_string()))?;
+    let extracted = Archive::open(&archive_path)?.extract(&name, &extracted_folder())?;
+    Ok(extracted.to_string_lossy().to_string())
+}
+
+// endregion
+
+// region: ---Archive
+
+#[derive(Debug, Clone)]
+pub struct ArchiveEntry {
+    pub name: String, // path in the archive, with / separators and without a trailing /
+    pub is_folder: bool,
+    pub size: u64,             // uncompressed
+    pub modified: Option<u64>, // milliseconds since the unix epoch, the zip time has no time zone
+}
+
+// entries of a zip file, the folders without an entry of their own are added
+pub struct Archive {
+    path: PathBuf,
+    entries: BTreeMap<String, ArchiveEntry>,
+    indices: BTreeMap<String, usize>, // of the files in the zip
+}
+
+impl Archive {
+    pub fn open(path: &Path) -> Result<Archive, Error> {
+        let mut zip = ZipArchive::new(vfs::open(path)?).map_err(|err| zip_error(path, err))?;
+        let mut entries = BTreeMap::new();
+        let mut indices = BTreeMap::new();
+
+        for index in 0..zip.len() {
+            let file = zip
+                .by_index_raw(index)
+                .map_err(|err| zip_error(path, err))?;
+            // the names going out of the archive (../, absolute) are skipped
+            let name = match file.enclosed_name() {
+                Some(name) => name.to_string_lossy().replace('\\', "/"),
+                None => continue,
+            };
+            let name = name.trim_end_matches('/').to_string();
+            if name.is_empty() {
+                continue;
+            }
+
+            let mut parent = name.as_str();
+            while let Some((folder, _)) = parent.rsplit_once('/') {
+                entries
+                    .entry(folder.to_string())
+                    .or_insert_with(|| ArchiveEntry {
+                        name: folder.to_string(),
+                        is_folder: true,
+                        size: 0,
+                        modified: None,
+                    });
+                parent = folder;
+            }
+            if !file.is_dir() {
+                indices.insert(name.clone(), index);
+            }
+            entries.insert(
+                name.clone(),
+                ArchiveEntry {
+                    name,
+                    is_folder: file.is_dir(),
+                    size: file.size(),
+                    modified: to_millis(file.last_modified()),
+                },
+            );
+        }
+
+        Ok(Archive {
+            path: path.to_path_buf(),
+            entries,
+            indices,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    // the root of the archive is ""
+    pub fn entry(&self, name: &str) -> Option<&ArchiveEntry> {
+        self.entries.get(name)
+    }
+
+    // folders first, in the natural order of the names
+    pub fn children(&self, folder: &str) -> Vec<&ArchiveEntry> {
+        let prefix = match folder.is_empty() {
+            true => String::new(),
+            false => format!("{}/", folder),
+        };
+        let mut children: Vec<&ArchiveEntry> = self
+            .entries
+            .range(prefix.clone()..)
+            .take_while(|(name, _)| name.starts_with(&prefix))
+            .filter(|(name, _)| !name[prefix.len()..].contains('/'))
+            .map(|(_, entry)| entry)
+            .collect();
+        children.sort_by(|left, right| {
+            right
+                .is_folder
+                .cmp(&left.is_folder)
+                .then_with(|| natural_cmp(&left.name, &right.name))
+        });
+        children
+    }
+
+    // the first `limit` bytes of the file, all of them when None
+    pub fn read(&self, name: &str, limit: Option<u64>) -> Result<Vec<u8>, Error> {
+        let index = *self
+            .indices
+            .get(name)
+            .ok_or_else(|| Error::NotFound(self.path.join(name).display().to_string()))?;
+        let mut zip =
+            ZipArchive::new(vfs::open(&self.path)?).map_err(|err| zip_error(&self.path, err))?;
+        let file = zip
+            .by_index(index)
+            .map_err(|err| zip_error(&self.path, err))?;
+
+        let mut bytes = Vec::new();
+        file.take(limit.unwrap_or(MAX_EXTRACTED_SIZE))
+            .read_to_end(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    // the same file is extracted again only when the archive changed
+    pub fn extract(&self, name: &str, folder: &Path) -> Result<PathBuf, Error> {
+        let entry = self
+            .entries
+            .get(name)
+            .filter(|entry| !entry.is_folder)
+            .ok_or_else(|| Error::NotFound(self.path.join(name).display().to_string()))?;
+        if entry.size > MAX_EXTRACTED_SIZE {
+            return Err(Error::Unsupported(format!(
+                "{} is too large",
+                self.path.join(name).display()
+            )));
+        }
+
+        let metadata = vfs::stat(&self.path)?;
+        let modified = metadata
+            .modified
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, |duration| duration.as_nanos());
+        let mut hasher = Xxh3::new();
+        hasher.update(self.path.as_os_str().as_encoded_bytes());
+        hasher.update(&[0]);
+        hasher.update(name.as_bytes());
+        hasher.update(&metadata.len.to_le_bytes());
+        hasher.update(&modified.to_le_bytes());
+        let file_name = name.rsplit('/').next().unwrap_or(name);
+        let extracted = folder
+            .join(format!("{:016x}", hasher.digest()))
+            .join(file_name);
+        if vfs::is_file(&extracted) {
+            return Ok(extracted);
+        }
+
+        let bytes = self.read(name, None)?;
+        if let Some(parent) = extracted.parent() {
+            vfs::create_dir_all(parent)?;
+        }
+        write_atomic(&extracted, &bytes)?;
+        vfs::set_readonly(&extracted, true)?;
+        Ok(extracted)
+    }
+}
+
+pub fn is_archive(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("zip"))
+        && vfs::is_file(path)
+}
+
+// the archive containing the path and the name of the entry in it
+pub fn split_archive_path(path: &Path) -> Option<(PathBuf, String)> {
+    let archive = path
+        .ancestors()
+        .skip(1)
+        .find(|ancestor| is_archive(ancestor))?;
+    let name = path
+        .strip_prefix(archive)
+        .ok()?
+        .to_string_lossy()
+        .replace('\\', "/");
+    Some((archive.to_path_buf(), name))
+}
+
+// $DATA/Workspace/archives is in the scope of the asset protocol in tauri.conf.json
+fn extracted_folder() -> PathBuf {
+    data_dir()
+        .unwrap_or_default()
+        .join(APP_DATA_FOLDER_NAME)
+        .join(EXTRACTED_FOLDER_NAME)
+}
+
+fn zip_error(path: &Path, err: ZipError) -> Error {
+    match err {
+        ZipError::Io(err) => err.into(),
+        ZipError::FileNotFound => Error::NotFound(path.display().to_string()),
+        _ => Error::Unsupported(format!("{}: {}", path.display(), err)),
+    }
+}
+
+// days from the civil date, the time of the zip is taken as utc
+fn to_millis(time: DateTime) -> Option<u64> {
+    let (year, month, day) = (time.year() as i64, time.month() as i64, time.day() as i64);
+    if !(1..=12).contains(&month) || day < 1 {
+        return None;
+    }
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146097 + day_of_era - 719468;
+
+    let seconds =
+        days * 86400 + time.hour() as i64 * 3600 + time.minute() as i64 * 60 + time.second() as i64;
+    u64::try_from(seconds * 1000).ok()
+}
+
+// endregion
+
+// region: ---Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::fs::vfs::MemoryVfs;
+    use std::io::{Cursor, Write};
+    use std::sync::Arc;
+    use zip::write::FileOptions;
+    use zip::ZipWriter;
+
+    // zip of the (name, text) files in memory at folder/a.zip
+    fn zip_in_memory(folder: &Path, files: &[(&str, &str)]) -> PathBuf {
+        vfs::mount(folder, Arc::new(MemoryVfs::new()));
+        vfs::create_dir_all(folder).unwrap();
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, text) in files {
+            zip.start_file(*name, FileOptions::default()).unwrap();
+            zip.write_all(text.as_bytes()).unwrap();
+        }
+        let path = folder.join("a.zip");
+        vfs::write(&path, zip.finish().unwrap().get_ref()).unwrap();
+        path
+    }
+
+    #[test]
+    fn folders_are_added_and_listed_first() {
+        let folder = Path::new("/archive-list");
+        let path = zip_in_memory(folder, &[("b.txt", "b"), ("docs/a.txt", "a")]);
+        let archive = Archive::open(&path).unwrap();
+        let names: Vec<&str> = archive
+            .children("")
+            .iter()
+            .map(|entry| entry.name.as_str())
+            .collect();
+        assert_eq!(names, ["docs", "b.txt"]);
+        assert_eq!(archive.read("docs/a.txt", None).unwrap(), b"a");
+        vfs::unmount(folder);
+    }
+
+    #[test]
+    fn files_are_extracted_once_and_read_only() {
+        let folder = Path::new("/archive-extract");
+        let path = zip_in_memory(folder, &[("docs/a.txt", "a")]);
+        let archive = Archive::open(&path).unwrap();
+        let extracted_folder = folder.join("extracted");
+
+        let extracted = archive.extract("docs/a.txt", &extracted_folder).unwrap();
+        assert!(extracted.starts_with(&extracted_folder) && extracted.ends_with("a.txt"));
+        assert_eq!(vfs::read(&extracted).unwrap(), b"a");
+        assert!(vfs::stat(&extracted).unwrap().readonly);
+        assert_eq!(
+            archive.extract("docs/a.txt", &extracted_folder).unwrap(),
+            extracted
+        );
+        assert!(matches!(
+            archive.extract("docs", &extracted_folder),
+            Err(Error::NotFound(_))
+        ));
+        vfs::unmount(folder);
+    }
+
+    #[test]
+    fn paths_in_archives_are_split() {
+        let folder = Path::new("/archive-split");
+        let path = zip_in_memory(folder, &[("docs/a.txt", "a")]);
+        assert_eq!(
+            split_archive_path(&path.join("docs/a.txt")),
+            Some((path.clone(), String::from("docs/a.txt")))
+        );
+        assert_eq!(split_archive_path(&folder.join("b.txt")), None);
+        vfs::unmount(folder);
+    }
+}
+
+// endregion
@@ -0,0 +1,356 @@
+use crate::api::diff::{merge, Merge, MergeLabels};
+use crate::api::fs::history::History;
+use crate::api::fs::text::{read_text, write_text, Encoding, LineEnding, TextFile};
+use crate::api::fs::vfs;
+use crate::api::workspace::{Workspace, WorkspaceListener};
+use crate::error::Error;
+use crate::event::{
+    emit_document_conflict, emit_document_reloaded, DocumentConflict, DocumentReloaded, FsChange,
+};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::UNIX_EPOCH;
+use tauri::{AppHandle, State};
+use xxhash_rust::xxh3::xxh3_128;
+
+// region: ---Tauri Command
+
+// the text of the file, tracked until it is closed: a change on disk while the document
+// has unsaved edits is emitted as a `document-conflict` event, otherwise the document takes
+// the new text, emitted as a `document-reloaded` event
+#[tauri::command]
+pub async fn open_document(
+    path: &str,
+    app: AppHandle,
+    workspace: State<'_, Workspace>,
+    documents: State<'_, Documents>,
+) -> Result<TextFile, Error> {
+    *documents.app.lock().unwrap() = Some(app);
+    documents.open(&workspace.resolve(path)?)
+}
+
+// the buffer of the document has edits that are not saved
+#[tauri::command]
+pub async fn set_document_dirty(
+    path: &str,
+    dirty: bool,
+    workspace: State<'_, Workspace>,
+    documents: State<'_, Documents>,
+) -> Result<(), Error> {
+    let path = workspace.resolve(path)?;
+    let mut open = documents.open.lock().unwrap();
+    let document = open.get_mut(&path).ok_or_else(|| not_open(&path))?;
+    document.dirty = dirty;
+    Ok(())
+}
+
+// in the encoding and line ending the file had when it was opened, refused with a `Modified`
+// error when the file changed on disk since it was opened, saved or merged, unless `force`
+// (the text on disk and the saved text are kept in the history of the file)
+#[tauri::command]
+pub async fn save_document(
+    path: &str,
+    text: &str,
+    force: Option<bool>,
+    workspace: State<'_, Workspace>,
+    documents: State<'_, Documents>,
+    history: State<'_, History>,
+) -> Result<(), Error> {
+    let path = workspace.resolve_writable(path)?;
+
+    // the watcher waits for the new state of the document, the save is never a conflict
+    let mut open = documents.open.lock().unwrap();
+    let document = open.get_mut(&path).ok_or_else(|| not_open(&path))?;
+    if !force.unwrap_or(false) && document.disk.has_changed(&path) {
+        return Err(Error::Modified(path.display().to_string()));
+    }
+
+    history.record(&path);
+    write_text(
+        &path,
+        text,
+        Some(document.encoding),
+        Some(document.line_ending),
+    )?;
+    history.record(&path);
+
+    document.base = text.replace("\r\n", "\n");
+    document.disk = DiskState::read(&path)?;
+    document.dirty = false;
+    document.conflict = false;
+    Ok(())
+}
+
+// three-way merge of the text saved last, the buffer and the file on disk,
+// the file on disk becomes the saved text of the document
+#[tauri::command]
+pub async fn merge_document(
+    path: &str,
+    text: &str,
+    workspace: State<'_, Workspace>,
+    documents: State<'_, Documents>,
+) -> Result<DocumentMerge, Error> {
+    let path = workspace.resolve(path)?;
+    let mut open = documents.open.lock().unwrap();
+    let document = open.get_mut(&path).ok_or_else(|| not_open(&path))?;
+
+    let disk = read_text(&path, false)?.text;
+    let labels = MergeLabels {
+        ours: String::from("editor"),
+        theirs: String::from("disk"),
+    };
+    let merge = merge(&document.base, &text.replace("\r\n", "\n"), &disk, &labels);
+
+    document.base = disk.clone();
+    document.disk = DiskState::read(&path)?;
+    document.conflict = false;
+    Ok(DocumentMerge { merge, disk })
+}
+
+#[tauri::command]
+pub async fn close_document(
+    path: &str,
+    workspace: State<'_, Workspace>,
+    documents: State<'_, Documents>,
+) -> Result<(), Error> {
+    let path = workspace.resolve(path)?;
+    documents.open.lock().unwrap().remove(&path);
+    Ok(())
+}
+
+// endregion
+
+// region: ---Documents
+
+/// # DocumentMerge
+/// {
+///     ...Merge, // the buffer (<<<<<<< editor) merged with the file (>>>>>>> disk)
+///     disk: string,
+/// }
+#[derive(Debug, serde::Serialize)]
+pub struct DocumentMerge {
+    #[serde(flatten)]
+    pub merge: Merge,
+    pub disk: String,
+}
+
+// the files open in the tabs of the editor, checked against the changes of the workspace
+#[derive(Clone, Default)]
+pub struct Documents {
+    open: Arc<Mutex<HashMap<PathBuf, Document>>>,
+    app: Arc<Mutex<Option<AppHandle>>>,
+}
+
+struct Document {
+    base: String,    // text read or saved last, with \n line endings
+    disk: DiskState, // of the file when the base was read
+    encoding: Encoding,
+    line_ending: LineEnding,
+    dirty: bool,    // the buffer has unsaved edits
+    conflict: bool, // emitted once until the document is saved or merged
+}
+
+impl Documents {
+    fn open(&self, path: &Path) -> Result<TextFile, Error> {
+        let file = read_text(path, false)?;
+        self.open.lock().unwrap().insert(
+            path.to_path_buf(),
+            Document {
+                base: file.text.clone(),
+                disk: DiskState::read(path)?,
+                encoding: file.encoding,
+                line_ending: file.line_ending,
+                dirty: false,
+                conflict: false,
+            },
+        );
+        Ok(file)
+    }
+
+    fn update(&self, changes: &[FsChange]) {
+        let mut events = Vec::new();
+        {
+            let mut open = self.open.lock().unwrap();
+            for change in changes {
+                // a document renamed in the explorer follows its file
+                if let FsChange::Renamed { from, to } = change {
+                    if let Some(document) = open.remove(Path::new(from)) {
+                        open.insert(PathBuf::from(to), document);
+                    }
+                }
+                for path in change.paths() {
+                    let path = Path::new(path);
+                    if let Some(document) = open.get_mut(path) {
+                        if let Some(event) = document.check(path) {
+                            events.push(event);
+                        }
+                    }
+                }
+            }
+        }
+
+        if events.is_empty() {
+            return;
+        }
+        if let Some(app) = self.app.lock().unwrap().as_ref() {
+            for event in events {
+                match event {
+                    DocumentEvent::Conflict(conflict) => emit_document_conflict(app, conflict),
+                    DocumentEvent::Reloaded(reloaded) => emit_document_reloaded(app, reloaded),
+                }
+            }
+        }
+    }
+}
+
+impl WorkspaceListener for Documents {
+    fn changed(&self, _root: &Path, changes: &[FsChange]) {
+        self.update(changes);
+    }
+}
+
+impl Document {
+    // a clean document takes the text on disk, a dirty one is in conflict with it
+    fn check(&mut self, path: &Path) -> Option<DocumentEvent> {
+        if !self.disk.has_changed(path) {
+            return None;
+        }
+        if !self.dirty {
+            if let Ok(file) = read_text(path, false) {
+                self.base = file.text;
+                self.disk = DiskState::read(path).ok()?;
+                return Some(DocumentEvent::Reloaded(DocumentReloaded {
+                    path: path.to_string_lossy().to_string(),
+                    text: self.base.clone(),
+                }));
+            }
+        }
+        if self.conflict {
+            return None;
+        }
+        self.conflict = true;
+        Some(DocumentEvent::Conflict(DocumentConflict {
+            path: path.to_string_lossy().to_string(),
+            deleted: !vfs::exists(path),
+        }))
+    }
+}
+
+enum DocumentEvent {
+    Conflict(DocumentConflict),
+    Reloaded(DocumentReloaded),
+}
+
+fn not_open(path: &Path) -> Error {
+    Error::NotFound(format!("{} is not an open document", path.display()))
+}
+
+// modification time and content hash of a file, None for a missing file
+#[derive(Debug, Clone, PartialEq)]
+struct DiskState(Option<(u64, u128)>);
+
+impl DiskState {
+    fn read(path: &Path) -> Result<DiskState, Error> {
+        if !vfs::exists(path) {
+            return Ok(DiskState(None));
+        }
+        Ok(DiskState(Some((
+            modified_time(path),
+            xxh3_128(&vfs::read(path)?),
+        ))))
+    }
+
+    // the content is only hashed when the modification time is not the same
+    fn has_changed(&mut self, path: &Path) -> bool {
+        let (modified, hash) = match (self.0, vfs::exists(path)) {
+            (None, exists) => return exists,
+            (Some(_), false) => return true,
+            (Some(state), true) => state,
+        };
+        let current_modified = modified_time(path);
+        if current_modified == modified {
+            return false;
+        }
+        match vfs::read(path) {
+            Ok(bytes) if xxh3_128(&bytes) == hash => {
+                self.0 = Some((current_modified, hash));
+                false
+            }
+            _ => true,
+        }
+    }
+}
+
+fn modified_time(path: &Path) -> u64 {
+    vfs::stat(path)
+        .ok()
+        .and_then(|stat| stat.modified)
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |duration| duration.as_nanos() as u64)
+}
+
+// endregion
+
+// region: ---Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::fs::vfs::MemoryVfs;
+
+    // a document opened from a file of a memory folder
+    fn open_in_memory(folder: &Path, text: &str) -> (Documents, PathBuf) {
+        vfs::mount(folder, Arc::new(MemoryVfs::new()));
+        vfs::create_dir_all(folder).unwrap();
+        let path = folder.join("a.txt");
+        vfs::write(&path, text.as_bytes()).unwrap();
+        let documents = Documents::default();
+        documents.open(&path).unwrap();
+        (documents, path)
+    }
+
+    fn check(documents: &Documents, path: &Path) -> Option<DocumentEvent> {
+        documents
+            .open
+            .lock()
+            .unwrap()
+            .get_mut(path)
+            .unwrap()
+            .check(path)
+    }
+
+    #[test]
+    fn clean_documents_are_reloaded() {
+        let folder = Path::new("/documents-reloaded");
+        let (documents, path) = open_in_memory(folder, "a\n");
+        assert!(check(&documents, &path).is_none());
+
+        vfs::write(&path, b"b\r\nc").unwrap();
+        match check(&documents, &path) {
+            Some(DocumentEvent::Reloaded(reloaded)) => assert_eq!(reloaded.text, "b\nc"),
+            _ => panic!("the document is not reloaded"),
+        }
+        assert_eq!(documents.open.lock().unwrap()[&path].base, "b\nc");
+        assert!(check(&documents, &path).is_none());
+        vfs::unmount(folder);
+    }
+
+    #[test]
+    fn dirty_documents_conflict_once() {
+        let folder = Path::new("/documents-conflict");
+        let (documents, path) = open_in_memory(folder, "a\n");
+        documents.open.lock().unwrap().get_mut(&path).unwrap().dirty = true;
+
+        vfs::write(&path, b"b\n").unwrap();
+        match check(&documents, &path) {
+            Some(DocumentEvent::Conflict(conflict)) => assert!(!conflict.deleted),
+            _ => panic!("the document is not in conflict"),
+        }
+        assert!(check(&documents, &path).is_none());
+        assert_eq!(documents.open.lock().unwrap()[&path].base, "a\n");
+        vfs::unmount(folder);
+    }
+}
+
+// endregion
@@ -0,0 +1,456 @@
+use crate::api::fs::listing::{walk_files, ListOptions};
+use crate::api::fs::text::{read_text, write_atomic};
+use crate::api::fs::vfs;
+use crate::api::search::Searches;
+use crate::api::workspace::Workspace;
+use crate::error::Error;
+use crate::event::{
+    emit_duplicates_finished, emit_duplicates_found, DuplicatesFinished, DuplicatesFound,
+};
+use crate::APP_DATA_FOLDER_NAME;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::UNIX_EPOCH;
+use tauri::api::path::data_dir;
+use tauri::{AppHandle, State};
+use xxhash_rust::xxh3::Xxh3;
+
+const DUPLICATES_FOLDER_NAME: &str = "duplicates";
+
+// the cache is dropped when its format changes
+const CACHE_VERSION: u32 = 1;
+
+// larger notes are not compared by their text
+const MAX_NOTE_SIZE: u64 = 1024 * 1024;
+
+// files compared by their normalized text
+const NOTE_EXTENSIONS: [&str; 3] = ["md", "markdown", "txt"];
+
+// region: ---Tauri Command
+
+// hash the files of the workspace on a background thread, the groups are emitted as
+// `duplicates-found` events and the end as a `duplicates-finished` event
+// (the scan is cancelled with `cancel_search`)
+#[tauri::command]
+pub async fn start_duplicate_scan(
+    options: Option<ListOptions>,
+    app: AppHandle,
+    workspace: State<'_, Workspace>,
+    searches: State<'_, Searches>,
+) -> Result<u32, Error> {
+    let root = workspace
+        .root()
+        .ok_or_else(|| Error::InvalidPath(String::from("no workspace is open")))?;
+    let options = options.unwrap_or_default();
+
+    let (id, cancelled) = searches.start();
+    let running = searches.running.clone();
+    thread::spawn(move || {
+        let result = find_duplicates(&root, &options, &cancelled, |group| {
+            emit_duplicates_found(&app, DuplicatesFound { id, group })
+        });
+        running.lock().unwrap().remove(&id);
+
+        let finished = match result {
+            Ok(summary) => DuplicatesFinished {
+                id,
+                summary,
+                error: None,
+            },
+            Err(err) => DuplicatesFinished {
+                id,
+                summary: DuplicateSummary::default(),
+                error: Some(err),
+            },
+        };
+        emit_duplicates_finished(&app, finished);
+    });
+    Ok(id)
+}
+
+// endregion
+
+// region: ---Duplicate Group
+
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DuplicateKind {
+    Identical, // same bytes
+    Similar,   // notes with the same normalized text
+}
+
+/// # DuplicateGroup
+/// {
+///     kind: "identical" | "similar",
+///     hash: string,
+///     size: number, // bytes of each file, of the largest one for similar notes
+///     files: [{ path, relativePath, modified }, ...], // oldest first
+/// }
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct DuplicateGroup {
+    pub kind: DuplicateKind,
+    pub hash: String,
+    pub size: u64,
+    pub files: Vec<DuplicateFile>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateFile {
+    pub path: String,
+    pub relative_path: String,
+    pub modified: u64, // milliseconds since the unix epoch
+}
+
+/// # DuplicateSummary
+/// {
+///     files: number,     // files scanned
+///     groups: number,
+///     wasted: number,    // bytes of the identical copies past the first one
+///     cancelled: bool,
+/// }
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct DuplicateSummary {
+    pub files: usize,
+    pub groups: usize,
+    pub wasted: u64,
+    pub cancelled: bool,
+}
+
+struct ScannedFile {
+    path: PathBuf,
+    relative_path: String,
+    size: u64,
+    modified: u64,
+}
+
+impl ScannedFile {
+    fn is_note(&self) -> bool {
+        self.size <= MAX_NOTE_SIZE
+            && self.path.extension().is_some_and(|extension| {
+                NOTE_EXTENSIONS
+                    .iter()
+                    .any(|note| extension.eq_ignore_ascii_case(note))
+            })
+    }
+
+    fn to_duplicate(&self) -> DuplicateFile {
+        DuplicateFile {
+            path: self.path.to_string_lossy().to_string(),
+            relative_path: self.relative_path.clone(),
+            modified: self.modified,
+        }
+    }
+}
+
+// the files are grouped by size, only the files sharing their size are hashed
+pub fn find_duplicates<F: FnMut(DuplicateGroup)>(
+    root: &Path,
+    options: &ListOptions,
+    cancelled: &AtomicBool,
+    mut on_group: F,
+) -> Result<DuplicateSummary, Error> {
+    let mut cache = HashCache::load(root);
+    let mut summary = DuplicateSummary::default();
+
+    let mut files = Vec::new();
+    walk_files(root, options, |path| {
+        if cancelled.load(Ordering::Relaxed) {
+            return false;
+        }
+        if let Ok(stat) = vfs::stat(path) {
+            files.push(ScannedFile {
+                relative_path: relative_path(root, path),
+                path: path.to_path_buf(),
+                size: stat.len,
+                modified: stat
+                    .modified
+                    .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                    .map_or(0, |duration| duration.as_millis() as u64),
+            });
+        }
+        true
+    })?;
+    if cancelled.load(Ordering::Relaxed) {
+        summary.cancelled = true;
+        return Ok(summary);
+    }
+    summary.files = files.len();
+
+    // the largest files first, they waste the most space (empty files are all identical)
+    let mut by_size: BTreeMap<u64, Vec<&ScannedFile>> = BTreeMap::new();
+    for file in files.iter().filter(|file| file.size > 0) {
+        by_size.entry(file.size).or_default().push(file);
+    }
+    for (size, same_size) in by_size.iter().rev() {
+        if same_size.len() < 2 {
+            continue;
+        }
+        if cancelled.load(Ordering::Relaxed) {
+            summary.cancelled = true;
+            break;
+        }
+        let mut by_hash: BTreeMap<String, Vec<&ScannedFile>> = BTreeMap::new();
+        for file in same_size {
+            if let Some(hash) = cache.content_hash(file) {
+                by_hash.entry(hash).or_default().push(file);
+            }
+        }
+        for (hash, identical) in by_hash.into_iter().filter(|(_, files)| files.len() > 1) {
+            summary.groups += 1;
+            summary.wasted += size * (identical.len() as u64 - 1);
+            on_group(group(DuplicateKind::Identical, hash, &identical));
+        }
+    }
+
+    // the notes with the same text that are not all identical
+    let mut by_text: BTreeMap<String, Vec<&ScannedFile>> = BTreeMap::new();
+    for file in files.iter().filter(|file| file.is_note()) {
+        if summary.cancelled || cancelled.load(Ordering::Relaxed) {
+            summary.cancelled = true;
+            break;
+        }
+        if let Some(hash) = cache.text_hash(file) {
+            by_text.entry(hash).or_default().push(file);
+        }
+    }
+    if !summary.cancelled {
+        for (hash, similar) in by_text.into_iter().filter(|(_, files)| files.len() > 1) {
+            let contents: HashSet<Option<String>> = similar
+                .iter()
+                .map(|file| cache.content_hash(file))
+                .collect();
+            if contents.len() > 1 {
+                summary.groups += 1;
+                on_group(group(DuplicateKind::Similar, hash, &similar));
+            }
+        }
+    }
+
+    // the cache keeps the files of a complete scan only
+    if !summary.cancelled {
+        let scanned: HashSet<&str> = files
+            .iter()
+            .map(|file| file.relative_path.as_str())
+            .collect();
+        cache
+            .entries
+            .retain(|path, _| scanned.contains(path.as_str()));
+    }
+    if let Err(err) = cache.save() {
+        eprintln!("failed to save the duplicate hashes: {}", err);
+    }
+    Ok(summary)
+}
+
+fn group(kind: DuplicateKind, hash: String, files: &[&ScannedFile]) -> DuplicateGroup {
+    let size = files.iter().map(|file| file.size).max().unwrap_or(0);
+    let mut files: Vec<DuplicateFile> = files.iter().map(|file| file.to_duplicate()).collect();
+    files.sort_by(|left, right| {
+        left.modified
+            .cmp(&right.modified)
+            .then_with(|| left.relative_path.cmp(&right.relative_path))
+    });
+    DuplicateGroup {
+        kind,
+        hash,
+        size,
+        files,
+    }
+}
+
+// lines trimmed and lowercased, with their blanks collapsed and without the empty lines
+fn normalize_text(text: &str) -> String {
+    text.lines()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|line| !line.is_empty())
+        .map(|line| line.to_lowercase())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn relative_path(root: &Path, path: &Path) -> String {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+// endregion
+
+// region: ---Hash Cache
+
+// hashes of the files of a workspace, valid while their size and modification time are the same
+#[derive(Debug, Default, serde::Serialize, serde::Deserialize)]
+struct HashCache {
+    #[serde(skip)]
+    path: PathBuf,
+    version: u32,
+    entries: HashMap<String, CachedHash>, // by relative path
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+struct CachedHash {
+    size: u64,
+    modified: u64,
+    hash: Option<String>,      // of the bytes
+    text_hash: Option<String>, // of the normalized text of the notes
+}
+
+impl HashCache {
+    // empty when it can't be read or is of another version
+    fn load(root: &Path) -> HashCache {
+        let mut hasher = Xxh3::new();
+        hasher.update(root.as_os_str().as_encoded_bytes());
+        let path = data_dir()
+            .unwrap_or_default()
+            .join(APP_DATA_FOLDER_NAME)
+            .join(DUPLICATES_FOLDER_NAME)
+            .join(format!("{:016x}.json", hasher.digest()));
+
+        let mut cache = vfs::read(&path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice::<HashCache>(&bytes).ok())
+            .filter(|cache| cache.version == CACHE_VERSION)
+            .unwrap_or_default();
+        cache.path = path;
+        cache.version = CACHE_VERSION;
+        cache
+    }
+
+    fn save(&self) -> Result<(), Error> {
+        if let Some(parent) = self.path.parent() {
+            vfs::create_dir_all(parent)?;
+        }
+        let json = serde_json::to_vec(self).map_err(|err| Error::Io(err.to_string()))?;
+        write_atomic(&self.path, &json)
+    }
+
+    // the entry of the file, emptied when the file changed
+    fn entry(&mut self, file: &ScannedFile) -> &mut CachedHash {
+        let entry = self
+            .entries
+            .entry(file.relative_path.clone())
+            .or_insert_with(|| CachedHash {
+                size: file.size,
+                modified: file.modified,
+                hash: None,
+                text_hash: None,
+            });
+        if entry.size != file.size || entry.modified != file.modified {
+            *entry = CachedHash {
+                size: file.size,
+                modified: file.modified,
+                hash: None,
+                text_hash: None,
+            };
+        }
+        entry
+    }
+
+    // None when the file can't be read
+    fn content_hash(&mut self, file: &ScannedFile) -> Option<String> {
+        if let Some(hash) = &self.entry(file).hash {
+            return Some(hash.clone());
+        }
+        let hash = hash_file(&file.path)?;
+        self.entry(file).hash = Some(hash.clone());
+        Some(hash)
+    }
+
+    // None for the notes that are binary, unreadable or empty
+    fn text_hash(&mut self, file: &ScannedFile) -> Option<String> {
+        if let Some(hash) = &self.entry(file).text_hash {
+            return Some(hash.clone()).filter(|hash| !hash.is_empty());
+        }
+        let normalized = read_text(&file.path, false)
+            .map(|file| normalize_text(&file.text))
+            .unwrap_or_default();
+        // an empty hash is kept for the notes without text
+        let hash = match normalized.is_empty() {
+            true => String::new(),
+            false => {
+                let mut hasher = Xxh3::new();
+                hasher.update(normalized.as_bytes());
+                format!("{:032x}", hasher.digest128())
+            }
+        };
+        self.entry(file).text_hash = Some(hash.clone());
+        Some(hash).filter(|hash| !hash.is_empty())
+    }
+}
+
+fn hash_file(path: &Path) -> Option<String> {
+    let mut file = vfs::open(path).ok()?;
+    let mut hasher = Xxh3::new();
+    let mut buffer = vec![0; 64 * 1024];
+    loop {
+        match file.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(read) => hasher.update(&buffer[..read]),
+            Err(_) => return None,
+        }
+    }
+    Some(format!("{:032x}", hasher.digest128()))
+}
+
+// endregion
+
+// region: ---Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scanned(relative_path: &str, size: u64, modified: u64) -> ScannedFile {
+        ScannedFile {
+            path: Path::new("/root").join(relative_path),
+            relative_path: relative_path.to_string(),
+            size,
+            modified,
+        }
+    }
+
+    #[test]
+    fn notes_are_normalized_by_line() {
+        let text = "  # Title\r\n\n\nSome   TEXT\there \n\n";
+        assert_eq!(normalize_text(text), "# title\nsome text here");
+        assert_eq!(
+            normalize_text(text),
+            normalize_text("# title\nSome text here")
+        );
+        assert_eq!(normalize_text(" \n\t\n"), "");
+    }
+
+    #[test]
+    fn only_small_text_files_are_notes() {
+        assert!(scanned("a.md", 10, 0).is_note());
+        assert!(scanned("b.TXT", 10, 0).is_note());
+        assert!(!scanned("c.rs", 10, 0).is_note());
+        assert!(!scanned("d.md", MAX_NOTE_SIZE + 1, 0).is_note());
+        assert!(!scanned("README", 10, 0).is_note());
+    }
+
+    #[test]
+    fn groups_list_the_oldest_file_first() {
+        let files = [
+            scanned("b.md", 3, 20),
+            scanned("a.md", 5, 10),
+            scanned("c.md", 4, 10),
+        ];
+        let files: Vec<&ScannedFile> = files.iter().collect();
+        let group = group(DuplicateKind::Similar, String::from("hash"), &files);
+        let paths: Vec<&str> = group
+            .files
+            .iter()
+            .map(|file| file.relative_path.as_str())
+            .collect();
+        assert_eq!(paths, ["a.md", "c.md", "b.md"]);
+        assert_eq!(group.size, 5);
+    }
+}
+
+// endregion
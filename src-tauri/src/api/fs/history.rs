@@ -0,0 +1,431 @@
+use crate::api::diff::{diff, DiffOptions, TextDiff};
+use crate::api::fs::text::{decode_text, write_atomic, TextFile};
+use crate::api::fs::vfs;
+use crate::api::workspace::Workspace;
+use crate::error::Error;
+use crate::APP_DATA_FOLDER_NAME;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::collections::{HashMap, HashSet};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::api::path::data_dir;
+use tauri::State;
+use xxhash_rust::xxh3::xxh3_128;
+
+const HISTORY_FOLDER_NAME: &str = "history";
+const OBJECTS_FOLDER_NAME: &str = "objects";
+const INDEX_FILE_NAME: &str = "index.json";
+
+// larger files are saved without a snapshot
+const MAX_SNAPSHOT_SIZE: u64 = 10 * 1024 * 1024;
+
+const DAY_MILLIS: u64 = 24 * 60 * 60 * 1000;
+
+// region: ---Tauri Command
+
+// the saved versions of the file, newest first
+#[tauri::command]
+pub async fn list_file_versions(
+    path: &str,
+    workspace: State<'_, Workspace>,
+    history: State<'_, History>,
+) -> Result<Vec<FileVersion>, Error> {
+    Ok(history.versions(&workspace.resolve(path)?))
+}
+
+// the lines of the version compared with the file on disk
+#[tauri::command]
+pub async fn diff_file_version(
+    path: &str,
+    id: u64,
+    options: Option<DiffOptions>,
+    workspace: State<'_, Workspace>,
+    history: State<'_, History>,
+) -> Result<VersionDiff, Error> {
+    let path = workspace.resolve(path)?;
+    let (version, bytes) = history.read_version(&path, id)?;
+    let old = decode_text(&path, &bytes, true)?;
+    let new = match vfs::exists(&path) {
+        true => decode_text(&path, &vfs::read(&path)?, true)?.text,
+        false => String::new(),
+    };
+    Ok(VersionDiff {
+        version,
+        diff: diff(&old.text, &new, &options.unwrap_or_default()),
+    })
+}
+
+// the version is written over the file, the text on disk is kept as a version before
+#[tauri::command]
+pub async fn restore_file_version(
+    path: &str,
+    id: u64,
+    workspace: State<'_, Workspace>,
+    history: State<'_, History>,
+) -> Result<TextFile, Error> {
+    let path = workspace.resolve_writable(path)?;
+    let (_, bytes) = history.read_version(&path, id)?;
+    history.record(&path);
+    write_atomic(&path, &bytes)?;
+    history.record(&path);
+    decode_text(&path, &bytes, true)
+}
+
+#[tauri::command]
+pub async fn get_history_retention(history: State<'_, History>) -> Result<Retention, Error> {
+    Ok(history.index.lock().unwrap().retention)
+}
+
+// the versions past the new limits are removed now
+#[tauri::command]
+pub async fn set_history_retention(
+    retention: Retention,
+    history: State<'_, History>,
+) -> Result<(), Error> {
+    history.set_retention(retention);
+    Ok(())
+}
+
+// endregion
+
+// region: ---Versions
+
+/// # FileVersion
+/// {
+///     id: number,
+///     time: number, // milliseconds since the unix epoch
+///     size: number, // bytes
+///     hash: string, // of the content, equal versions have the same hash
+/// }
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct FileVersion {
+    pub id: u64,
+    pub time: u64,
+    pub size: u64,
+    pub hash: String,
+}
+
+// the versions of a file are kept up to `maxVersions` and for `maxAgeDays` days,
+// the last one is always kept (0 for no limit)
+#[derive(Debug, Clone, Copy, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Retention {
+    pub max_versions: usize,
+    pub max_age_days: u64,
+}
+
+impl Default for Retention {
+    fn default() -> Self {
+        Retention {
+            max_versions: 50,
+            max_age_days: 30,
+        }
+    }
+}
+
+#[derive(Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+struct HistoryIndex {
+    next_id: u64,
+    retention: Retention,
+    files: HashMap<String, Vec<FileVersion>>, // by absolute path, oldest first
+}
+
+// versions of the saved files, the contents are stored once in the app data folder
+pub struct History {
+    folder: PathBuf,
+    index: Mutex<HistoryIndex>,
+}
+
+impl History {
+    // the history of the app data folder, empty if it can't be read
+    pub fn load() -> History {
+        let folder = data_dir()
+            .unwrap_or_default()
+            .join(APP_DATA_FOLDER_NAME)
+            .join(HISTORY_FOLDER_NAME);
+        History::load_from(folder)
+    }
+
+    pub fn load_from(folder: PathBuf) -> History {
+        let index = vfs::read(&folder.join(INDEX_FILE_NAME))
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default();
+        History {
+            folder,
+            index: Mutex::new(index),
+        }
+    }
+
+    // the content of the file as a new version, unless it is the last one
+    // (a failing snapshot never fails the save)
+    pub fn record(&self, path: &Path) {
+        if let Err(err) = self.try_record(path) {
+            eprintln!("failed to save a version of {}: {}", path.display(), err);
+        }
+    }
+
+    fn try_record(&self, path: &Path) -> Result<(), Error> {
+        match vfs::stat(path) {
+            Ok(stat) if stat.is_file() && stat.len <= MAX_SNAPSHOT_SIZE => {}
+            _ => return Ok(()),
+        }
+        let bytes = vfs::read(path)?;
+        let hash = format!("{:032x}", xxh3_128(&bytes));
+
+        let mut index = self.index.lock().unwrap();
+        let key = path.to_string_lossy().to_string();
+        let is_last = index
+            .files
+            .get(&key)
+            .and_then(|versions| versions.last())
+            .is_some_and(|last| last.hash == hash);
+        if is_last {
+            return Ok(());
+        }
+
+        self.write_object(&hash, &bytes)?;
+        let version = FileVersion {
+            id: index.next_id,
+            time: now_millis(),
+            size: bytes.len() as u64,
+            hash,
+        };
+        index.next_id += 1;
+        index.files.entry(key).or_default().push(version);
+        self.prune(&mut index);
+        self.save(&index)
+    }
+
+    fn versions(&self, path: &Path) -> Vec<FileVersion> {
+        let index = self.index.lock().unwrap();
+        let mut versions = index
+            .files
+            .get(path.to_string_lossy().as_ref())
+            .cloned()
+            .unwrap_or_default();
+        versions.reverse();
+        versions
+    }
+
+    fn read_version(&self, path: &Path, id: u64) -> Result<(FileVersion, Vec<u8>), Error> {
+        let version = self
+            .versions(path)
+            .into_iter()
+            .find(|version| version.id == id)
+            .ok_or_else(|| Error::NotFound(format!("version {} of {}", id, path.display())))?;
+        let mut bytes = Vec::new();
+        GzDecoder::new(vfs::open(&self.object_path(&version.hash))?).read_to_end(&mut bytes)?;
+        Ok((version, bytes))
+    }
+
+    fn set_retention(&self, retention: Retention) {
+        let mut index = self.index.lock().unwrap();
+        index.retention = retention;
+        self.prune(&mut index);
+        if let Err(err) = self.save(&index) {
+            eprintln!("failed to save the history: {}", err);
+        }
+    }
+
+    // the versions past the retention limits, then the contents of no version
+    fn prune(&self, index: &mut HistoryIndex) {
+        let retention = index.retention;
+        let oldest = match retention.max_age_days {
+            0 => 0,
+            days => now_millis().saturating_sub(days * DAY_MILLIS),
+        };
+        let mut pruned = false;
+        for versions in index.files.values_mut() {
+            let count = versions.len();
+            let mut kept = count;
+            if retention.max_versions > 0 {
+                kept = kept.min(retention.max_versions);
+            }
+            let recent = versions
+                .iter()
+                .filter(|version| version.time >= oldest)
+                .count();
+            kept = kept.min(recent).max(1);
+            if kept < count {
+                versions.drain(..count - kept);
+                pruned = true;
+            }
+        }
+        if !pruned {
+            return;
+        }
+
+        let used: HashSet<&str> = index
+            .files
+            .values()
+            .flatten()
+            .map(|version| version.hash.as_str())
+            .collect();
+        let objects = self.folder.join(OBJECTS_FOLDER_NAME);
+        for object in vfs::list(&objects).unwrap_or_default() {
+            let hash = object
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().to_string())
+                .unwrap_or_default();
+            if !used.contains(hash.as_str()) {
+                let _ = vfs::remove_file(&object);
+            }
+        }
+    }
+
+    fn object_path(&self, hash: &str) -> PathBuf {
+        self.folder
+            .join(OBJECTS_FOLDER_NAME)
+            .join(format!("{}.gz", hash))
+    }
+
+    // the same content is stored once
+    fn write_object(&self, hash: &str, bytes: &[u8]) -> Result<(), Error> {
+        let path = self.object_path(hash);
+        if vfs::exists(&path) {
+            return Ok(());
+        }
+        if let Some(parent) = path.parent() {
+            vfs::create_dir_all(parent)?;
+        }
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(bytes)?;
+        write_atomic(&path, &encoder.finish()?)
+    }
+
+    fn save(&self, index: &HistoryIndex) -> Result<(), Error> {
+        vfs::create_dir_all(&self.folder)?;
+        let json = serde_json::to_vec(index).map_err(|err| Error::Io(err.to_string()))?;
+        write_atomic(&self.folder.join(INDEX_FILE_NAME), &json)
+    }
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_millis() as u64)
+}
+
+// endregion
+
+// region: ---Version Diff
+
+/// # VersionDiff
+/// {
+///     version: FileVersion,
+///     ...TextDiff, // from the version to the file
+/// }
+#[derive(Debug, serde::Serialize)]
+pub struct VersionDiff {
+    pub version: FileVersion,
+    #[serde(flatten)]
+    pub diff: TextDiff,
+}
+
+// endregion
+
+// region: ---Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::fs::vfs::MemoryVfs;
+    use std::sync::Arc;
+
+    // a history kept in the memory folder, with the file a.txt
+    fn history_in_memory(folder: &Path) -> (History, PathBuf) {
+        vfs::mount(folder, Arc::new(MemoryVfs::new()));
+        vfs::create_dir_all(folder).unwrap();
+        let path = folder.join("a.txt");
+        vfs::write(&path, b"1").unwrap();
+        (History::load_from(folder.join(HISTORY_FOLDER_NAME)), path)
+    }
+
+    fn ids(versions: &[FileVersion]) -> Vec<u64> {
+        versions.iter().map(|version| version.id).collect()
+    }
+
+    #[test]
+    fn versions_are_recorded_once_per_content() {
+        let folder = Path::new("/history-record");
+        let (history, path) = history_in_memory(folder);
+        history.record(&path);
+        history.record(&path);
+        vfs::write(&path, b"22").unwrap();
+        history.record(&path);
+
+        let versions = history.versions(&path);
+        assert_eq!(ids(&versions), [1, 0]);
+        assert_eq!(versions[0].size, 2);
+        let (version, bytes) = history.read_version(&path, 0).unwrap();
+        assert_eq!((version.id, bytes.as_slice()), (0, &b"1"[..]));
+        assert!(matches!(
+            history.read_version(&path, 5),
+            Err(Error::NotFound(_))
+        ));
+
+        // the index is read back from its file
+        let loaded = History::load_from(folder.join(HISTORY_FOLDER_NAME));
+        assert_eq!(ids(&loaded.versions(&path)), [1, 0]);
+        vfs::unmount(folder);
+    }
+
+    #[test]
+    fn equal_contents_are_stored_once() {
+        let folder = Path::new("/history-objects");
+        let (history, path) = history_in_memory(folder);
+        let other = folder.join("b.txt");
+        vfs::write(&other, b"1").unwrap();
+        history.record(&path);
+        history.record(&other);
+
+        let objects = vfs::list(&history.folder.join(OBJECTS_FOLDER_NAME)).unwrap();
+        assert_eq!(objects.len(), 1);
+        vfs::unmount(folder);
+    }
+
+    #[test]
+    fn versions_past_the_retention_are_pruned_with_their_contents() {
+        let folder = Path::new("/history-prune");
+        let (history, path) = history_in_memory(folder);
+        for content in ["1", "2", "3"] {
+            vfs::write(&path, content.as_bytes()).unwrap();
+            history.record(&path);
+        }
+        history.set_retention(Retention {
+            max_versions: 2,
+            max_age_days: 0,
+        });
+        assert_eq!(ids(&history.versions(&path)), [2, 1]);
+        let objects = vfs::list(&history.folder.join(OBJECTS_FOLDER_NAME)).unwrap();
+        assert_eq!(objects.len(), 2);
+        vfs::unmount(folder);
+    }
+
+    #[test]
+    fn the_last_version_is_kept_past_the_max_age() {
+        let folder = Path::new("/history-age");
+        let (history, path) = history_in_memory(folder);
+        history.record(&path);
+        vfs::write(&path, b"2").unwrap();
+        history.record(&path);
+        for version in history.index.lock().unwrap().files.values_mut().flatten() {
+            version.time = 0;
+        }
+
+        history.set_retention(Retention {
+            max_versions: 0,
+            max_age_days: 1,
+        });
+        assert_eq!(ids(&history.versions(&path)), [1]);
+        vfs::unmount(folder);
+    }
+}
+
+// endregion
@@ -0,0 +1,577 @@
+use crate::api::fs::operations::{move_entry, ConflictPolicy, Moved, Trash, TrashItem};
+use crate::api::fs::sandbox::{self, Access};
+use crate::api::fs::text::write_atomic;
+use crate::api::fs::vfs;
+use crate::api::search::replace::{apply_edits, FileEdits};
+use crate::api::workspace::Workspace;
+use crate::error::Error;
+use crate::APP_DATA_FOLDER_NAME;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::api::path::data_dir;
+use tauri::State;
+
+const JOURNAL_FILE_NAME: &str = "journal.json";
+
+// oldest entries are forgotten past this
+const MAX_ENTRIES: usize = 100;
+
+// region: ---Tauri Command
+
+// undo the last `count` operations (1 by default), the undone entries are returned
+#[tauri::command]
+pub async fn undo_operations(
+    count: Option<usize>,
+    workspace: State<'_, Workspace>,
+    journal: State<'_, Journal>,
+) -> Result<Vec<JournalEntry>, Error> {
+    journal.undo(count.unwrap_or(1), workspace.root().as_deref())
+}
+
+#[tauri::command]
+pub async fn redo_operations(
+    count: Option<usize>,
+    workspace: State<'_, Workspace>,
+    journal: State<'_, Journal>,
+) -> Result<Vec<JournalEntry>, Error> {
+    journal.redo(count.unwrap_or(1), workspace.root().as_deref())
+}
+
+// the entries of the open workspace
+#[tauri::command]
+pub async fn get_journal(
+    workspace: State<'_, Workspace>,
+    journal: State<'_, Journal>,
+) -> Result<JournalHistory, Error> {
+    let root = workspace.root();
+    let history = journal.history.lock().unwrap();
+    let of_root = |entries: &Vec<JournalEntry>| -> Vec<JournalEntry> {
+        entries
+            .iter()
+            .filter(|entry| root.as_deref().is_some_and(|root| entry.is_of(root)))
+            .cloned()
+            .collect()
+    };
+    Ok(JournalHistory {
+        done: of_root(&history.done),
+        undone: of_root(&history.undone),
+    })
+}
+
+// endregion
+
+// region: ---Journal Entry
+
+/// # JournalEntry
+/// {
+///     operation: { type: "create", path, isFolder, trashId? }
+///         | { type: "move", moves: [{ from, to, overwritten?: TrashItem }, ...] }
+///         | { type: "copy", copies: [{ from, to, overwritten?: TrashItem }, ...], trashIds: [id, ...] }
+///         | { type: "delete", items: [TrashItem, ...] }
+///         | { type: "restore", items: [TrashItem, ...], overwritten: [TrashItem, ...] }
+///         | { type: "replace", files: [{ path, edits: [TextEdit, ...] }, ...] },
+///     root?: string, // workspace of the trash
+///     time: number,  // milliseconds since the unix epoch
+/// }
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct JournalEntry {
+    pub operation: Operation,
+    pub root: Option<String>,
+    pub time: u64,
+}
+
+// the trash ids are the ones of the undone entries, they are put back by redo
+// (the entries overwritten by a move, a copy or a restore are taken out of the trash by undo)
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum Operation {
+    #[serde(rename_all = "camelCase")]
+    Create {
+        path: String,
+        is_folder: bool,
+        trash_id: Option<String>,
+    },
+    Move {
+        moves: Vec<Moved>,
+    },
+    #[serde(rename_all = "camelCase")]
+    Copy {
+        copies: Vec<Moved>,
+        trash_ids: Vec<String>,
+    },
+    Delete {
+        items: Vec<TrashItem>,
+    },
+    Restore {
+        items: Vec<TrashItem>,
+        #[serde(default)]
+        overwritten: Vec<TrashItem>,
+    },
+    Replace {
+        files: Vec<FileEdits>,
+    },
+}
+
+impl JournalEntry {
+    fn is_of(&self, root: &Path) -> bool {
+        self.root.as_deref().map(Path::new) == Some(root)
+    }
+
+    // the journal file can be edited, every path it gives is checked like the ones of the webview
+    fn check(&self, root: &Path) -> Result<(), Error> {
+        let moved_paths = |moves: &[Moved]| -> Vec<String> {
+            moves
+                .iter()
+                .flat_map(|moved| {
+                    let overwritten = moved.overwritten.iter().map(|item| &item.original_path);
+                    [&moved.from, &moved.to].into_iter().chain(overwritten)
+                })
+                .cloned()
+                .collect()
+        };
+        let item_paths = |items: &[TrashItem]| -> Vec<String> {
+            items
+                .iter()
+                .map(|item| item.original_path.clone())
+                .collect()
+        };
+        let paths = match &self.operation {
+            Operation::Create { path, .. } => vec![path.clone()],
+            Operation::Move { moves } => moved_paths(moves),
+            Operation::Copy { copies, .. } => moved_paths(copies),
+            Operation::Delete { items } => item_paths(items),
+            Operation::Restore { items, overwritten } => {
+                [item_paths(items), item_paths(overwritten)].concat()
+            }
+            Operation::Replace { files } => files.iter().map(|file| file.path.clone()).collect(),
+        };
+        for path in paths {
+            sandbox::resolve(Some(root), Path::new(&path), Access::Write)?;
+        }
+        Ok(())
+    }
+
+    fn undo(&mut self) -> Result<(), Error> {
+        match &mut self.operation {
+            Operation::Create { path, trash_id, .. } => {
+                let trash = trash(&self.root)?;
+                *trash_id = Some(trash.put(Path::new(path))?.id);
+            }
+            Operation::Move { moves } => {
+                let trash = trash(&self.root)?;
+                for moved in moves.iter().rev() {
+                    move_back(&moved.to, &moved.from, &trash)?;
+                    restore(&trash, moved.overwritten.as_slice())?;
+                }
+            }
+            Operation::Copy { copies, trash_ids } => {
+                let trash = trash(&self.root)?;
+                *trash_ids = Vec::new();
+                for copy in copies.iter().rev() {
+                    trash_ids.push(trash.put(Path::new(&copy.to))?.id);
+                    restore(&trash, copy.overwritten.as_slice())?;
+                }
+            }
+            Operation::Delete { items } => restore(&trash(&self.root)?, items)?,
+            Operation::Restore { items, overwritten } => {
+                let trash = trash(&self.root)?;
+                put(&trash, items)?;
+                restore(&trash, overwritten)?;
+            }
+            Operation::Replace { files } => {
+                let inverted: Vec<FileEdits> = files.iter().map(FileEdits::inverted).collect();
+                apply_edits(&inverted)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn redo(&mut self) -> Result<(), Error> {
+        match &mut self.operation {
+            Operation::Create { trash_id, .. } => {
+                if let Some(id) = trash_id.take() {
+                    trash(&self.root)?.restore(&id, ConflictPolicy::Fail)?;
+                }
+            }
+            Operation::Move { moves } => {
+                let trash = trash(&self.root)?;
+                for moved in moves.iter_mut() {
+                    put(&trash, moved.overwritten.as_mut_slice())?;
+                    move_back(&moved.from, &moved.to, &trash)?;
+                }
+            }
+            Operation::Copy { copies, trash_ids } => {
+                let trash = trash(&self.root)?;
+                let ids = std::mem::take(trash_ids);
+                for (copy, id) in copies.iter_mut().zip(ids.iter().rev()) {
+                    put(&trash, copy.overwritten.as_mut_slice())?;
+                    trash.restore(id, ConflictPolicy::Fail)?;
+                }
+            }
+            Operation::Delete { items } => put(&trash(&self.root)?, items)?,
+            Operation::Restore { items, overwritten } => {
+                let trash = trash(&self.root)?;
+                put(&trash, overwritten)?;
+                restore(&trash, items)?;
+            }
+            Operation::Replace { files } => apply_edits(files)?,
+        }
+        Ok(())
+    }
+}
+
+fn trash(root: &Option<String>) -> Result<Trash, Error> {
+    let root = root
+        .as_ref()
+        .ok_or_else(|| Error::InvalidPath(String::from("the operation has no workspace")))?;
+    Ok(Trash::new(Path::new(root)))
+}
+
+fn move_back(from: &str, to: &str, trash: &Trash) -> Result<(), Error> {
+    move_entry(Path::new(from), Path::new(to), ConflictPolicy::Fail, trash)?;
+    Ok(())
+}
+
+fn restore(trash: &Trash, items: &[TrashItem]) -> Result<(), Error> {
+    for item in items.iter().rev() {
+        trash.restore(&item.id, ConflictPolicy::Fail)?;
+    }
+    Ok(())
+}
+
+// the items get new ids in the trash
+fn put(trash: &Trash, items: &mut [TrashItem]) -> Result<(), Error> {
+    for item in items.iter_mut() {
+        *item = trash.put(Path::new(&item.original_path))?;
+    }
+    Ok(())
+}
+
+// endregion
+
+// region: ---Journal
+
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct JournalHistory {
+    pub done: Vec<JournalEntry>,   // last one is undone first
+    pub undone: Vec<JournalEntry>, // last one is redone first
+}
+
+// operations of the explorer, saved in the app data folder after every change
+pub struct Journal {
+    path: PathBuf,
+    history: Mutex<JournalHistory>,
+}
+
+impl Journal {
+    // the journal of the app data folder, empty if it can't be read
+    pub fn load() -> Journal {
+        let path = data_dir()
+            .unwrap_or_default()
+            .join(APP_DATA_FOLDER_NAME)
+            .join(JOURNAL_FILE_NAME);
+        Journal::load_from(path)
+    }
+
+    pub fn load_from(path: PathBuf) -> Journal {
+        let history = vfs::read(&path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default();
+        Journal {
+            path,
+            history: Mutex::new(history),
+        }
+    }
+
+    // a new operation can't be redone after the undone ones
+    pub fn record(&self, operation: Operation, root: Option<&Path>) {
+        let mut history = self.history.lock().unwrap();
+        let entry = JournalEntry {
+            operation,
+            root: root.map(|root| root.to_string_lossy().to_string()),
+            time: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |duration| duration.as_millis() as u64),
+        };
+        // only the undone entries of the same workspace are forgotten
+        history.undone.retain(|undone| undone.root != entry.root);
+        history.done.push(entry);
+        if history.done.len() > MAX_ENTRIES {
+            let excess = history.done.len() - MAX_ENTRIES;
+            history.done.drain(..excess);
+        }
+        self.save(&history);
+    }
+
+    pub fn record_moves(&self, moves: &[Moved], root: Option<&Path>) {
+        if !moves.is_empty() {
+            self.record(
+                Operation::Move {
+                    moves: moves.to_vec(),
+                },
+                root,
+            );
+        }
+    }
+
+    // only the operations of the workspace at `root` are applied
+    pub fn undo(&self, count: usize, root: Option<&Path>) -> Result<Vec<JournalEntry>, Error> {
+        self.apply(count, root, true)
+    }
+
+    pub fn redo(&self, count: usize, root: Option<&Path>) -> Result<Vec<JournalEntry>, Error> {
+        self.apply(count, root, false)
+    }
+
+    // the last entries of the workspace are applied, the ones of other workspaces are left in
+    // place; a failing entry stays where it was, the entries applied before are kept
+    fn apply(
+        &self,
+        count: usize,
+        root: Option<&Path>,
+        undo: bool,
+    ) -> Result<Vec<JournalEntry>, Error> {
+        let root = root.ok_or_else(|| Error::InvalidPath(String::from("no workspace is open")))?;
+        let mut guard = self.history.lock().unwrap();
+        let history = &mut *guard;
+        let mut applied = Vec::new();
+        let mut result = Ok(());
+
+        for _ in 0..count {
+            let (from, to) = match undo {
+                true => (&mut history.done, &mut history.undone),
+                false => (&mut history.undone, &mut history.done),
+            };
+            let position = match from.iter().rposition(|entry| entry.is_of(root)) {
+                Some(position) => position,
+                None => break,
+            };
+            let mut entry = from.remove(position);
+            let entry_result = entry.check(root).and_then(|_| match undo {
+                true => entry.undo(),
+                false => entry.redo(),
+            });
+            if let Err(err) = entry_result {
+                from.insert(position, entry);
+                result = Err(err);
+                break;
+            }
+            applied.push(entry.clone());
+            to.push(entry);
+        }
+
+        self.save(history);
+        result.map(|_| applied)
+    }
+
+    fn save(&self, history: &JournalHistory) {
+        let result = (|| {
+            if let Some(parent) = self.path.parent() {
+                vfs::create_dir_all(parent)?;
+            }
+            let json = serde_json::to_vec(history).map_err(|err| Error::Io(err.to_string()))?;
+            write_atomic(&self.path, &json)
+        })();
+        if let Err(err) = result {
+            eprintln!("failed to save the journal: {}", err);
+        }
+    }
+}
+
+// endregion
+
+// region: ---Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::fs::operations::copy_entry;
+    use crate::api::fs::vfs::MemoryVfs;
+    use std::sync::Arc;
+
+    // a journal saved in the memory folder, with a.txt in its workspace
+    fn journal_in_memory(root: &Path) -> Journal {
+        vfs::mount(root, Arc::new(MemoryVfs::new()));
+        vfs::create_dir_all(root).unwrap();
+        vfs::write(&root.join("a.txt"), b"a").unwrap();
+        Journal::load_from(root.join("data").join(JOURNAL_FILE_NAME))
+    }
+
+    fn path(root: &Path, relative: &str) -> String {
+        root.join(relative).to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn moves_are_undone_and_redone() {
+        let root = Path::new("/journal-moves");
+        let journal = journal_in_memory(root);
+        vfs::rename(&root.join("a.txt"), &root.join("b.txt")).unwrap();
+        let moved = Moved {
+            from: path(root, "a.txt"),
+            to: path(root, "b.txt"),
+            overwritten: None,
+        };
+        journal.record_moves(&[moved], Some(root));
+
+        assert_eq!(journal.undo(1, Some(root)).unwrap().len(), 1);
+        assert!(vfs::exists(&root.join("a.txt")) && !vfs::exists(&root.join("b.txt")));
+        assert_eq!(journal.redo(5, Some(root)).unwrap().len(), 1);
+        assert!(vfs::exists(&root.join("b.txt")));
+
+        // the journal is read back from its file
+        let loaded = Journal::load_from(journal.path.clone());
+        assert_eq!(loaded.history.lock().unwrap().done.len(), 1);
+        vfs::unmount(root);
+    }
+
+    #[test]
+    fn overwritten_entries_are_put_back_by_undo() {
+        let root = Path::new("/journal-overwrite");
+        let journal = journal_in_memory(root);
+        let (from, to) = (root.join("b.txt"), root.join("a.txt"));
+        vfs::write(&from, b"b").unwrap();
+        let placed = move_entry(&from, &to, ConflictPolicy::Overwrite, &Trash::new(root))
+            .unwrap()
+            .unwrap();
+        assert!(placed.overwritten.is_some());
+        let moved = Moved {
+            from: path(root, "b.txt"),
+            to: path(root, "a.txt"),
+            overwritten: placed.overwritten,
+        };
+        journal.record_moves(&[moved], Some(root));
+
+        journal.undo(1, Some(root)).unwrap();
+        assert_eq!(vfs::read(&to).unwrap(), b"a");
+        assert_eq!(vfs::read(&from).unwrap(), b"b");
+        assert!(Trash::new(root).list().unwrap().is_empty());
+
+        journal.redo(1, Some(root)).unwrap();
+        assert_eq!(vfs::read(&to).unwrap(), b"b");
+        journal.undo(1, Some(root)).unwrap();
+        assert_eq!(vfs::read(&to).unwrap(), b"a");
+        vfs::unmount(root);
+    }
+
+    #[test]
+    fn overwritten_copies_are_put_back_by_undo() {
+        let root = Path::new("/journal-overwrite-copy");
+        let journal = journal_in_memory(root);
+        vfs::create_dir_all(&root.join("docs")).unwrap();
+        vfs::write(&root.join("docs/a.txt"), b"docs").unwrap();
+        let (from, to) = (root.join("a.txt"), root.join("docs/a.txt"));
+        let placed = copy_entry(&from, &to, ConflictPolicy::Overwrite, &Trash::new(root))
+            .unwrap()
+            .unwrap();
+        let copy = Moved {
+            from: path(root, "a.txt"),
+            to: path(root, "docs/a.txt"),
+            overwritten: placed.overwritten,
+        };
+        journal.record(
+            Operation::Copy {
+                copies: vec![copy],
+                trash_ids: Vec::new(),
+            },
+            Some(root),
+        );
+
+        journal.undo(1, Some(root)).unwrap();
+        assert_eq!(vfs::read(&to).unwrap(), b"docs");
+        journal.redo(1, Some(root)).unwrap();
+        assert_eq!(vfs::read(&to).unwrap(), b"a");
+        vfs::unmount(root);
+    }
+
+    #[test]
+    fn deletes_are_restored_from_the_trash() {
+        let root = Path::new("/journal-delete");
+        let journal = journal_in_memory(root);
+        let item = Trash::new(root).put(&root.join("a.txt")).unwrap();
+        journal.record(Operation::Delete { items: vec![item] }, Some(root));
+
+        journal.undo(1, Some(root)).unwrap();
+        assert_eq!(vfs::read(&root.join("a.txt")).unwrap(), b"a");
+        journal.redo(1, Some(root)).unwrap();
+        assert!(!vfs::exists(&root.join("a.txt")));
+        vfs::unmount(root);
+    }
+
+    #[test]
+    fn entries_of_another_workspace_are_skipped() {
+        let root = Path::new("/journal-other");
+        let journal = journal_in_memory(root);
+        vfs::rename(&root.join("a.txt"), &root.join("b.txt")).unwrap();
+        let moved = Moved {
+            from: path(root, "a.txt"),
+            to: path(root, "b.txt"),
+            overwritten: None,
+        };
+        journal.record_moves(&[moved], Some(root));
+        let elsewhere = || Moved {
+            from: String::from("/elsewhere/x.txt"),
+            to: String::from("/elsewhere/y.txt"),
+            overwritten: None,
+        };
+        journal.record_moves(&[elsewhere()], Some(Path::new("/elsewhere")));
+
+        assert_eq!(journal.undo(1, Some(root)).unwrap().len(), 1);
+        assert!(vfs::exists(&root.join("a.txt")));
+        assert!(journal.undo(1, Some(root)).unwrap().is_empty());
+        let history = journal.history.lock().unwrap().clone();
+        assert_eq!((history.done.len(), history.undone.len()), (1, 1));
+
+        // an operation in the other workspace keeps the entries to redo of this one
+        journal.record_moves(&[elsewhere()], Some(Path::new("/elsewhere")));
+        assert_eq!(journal.redo(1, Some(root)).unwrap().len(), 1);
+        assert!(vfs::exists(&root.join("b.txt")));
+        vfs::unmount(root);
+    }
+
+    #[test]
+    fn edited_entries_outside_of_the_workspace_are_refused() {
+        let root = Path::new("/journal-edited");
+        let journal = journal_in_memory(root);
+        let outside = Path::new("/journal-edited-outside");
+        vfs::mount(outside, Arc::new(MemoryVfs::new()));
+        vfs::create_dir_all(outside).unwrap();
+        let moved = Moved {
+            from: path(outside, "a.txt"),
+            to: path(root, "a.txt"),
+            overwritten: None,
+        };
+        journal.record_moves(&[moved], Some(root));
+
+        let result = journal.undo(1, Some(root));
+        assert!(matches!(result, Err(Error::OutsideWorkspace(_))));
+        assert!(vfs::exists(&root.join("a.txt")) && !vfs::exists(&outside.join("a.txt")));
+        assert_eq!(journal.history.lock().unwrap().done.len(), 1);
+        vfs::unmount(outside);
+        vfs::unmount(root);
+    }
+
+    #[test]
+    fn new_operations_forget_the_undone_ones_and_the_oldest() {
+        let root = Path::new("/journal-record");
+        let journal = journal_in_memory(root);
+        let create = || Operation::Create {
+            path: path(root, "a.txt"),
+            is_folder: false,
+            trash_id: None,
+        };
+        for _ in 0..MAX_ENTRIES + 5 {
+            journal.record(create(), Some(root));
+        }
+        assert_eq!(journal.history.lock().unwrap().done.len(), MAX_ENTRIES);
+
+        journal.undo(1, Some(root)).unwrap();
+        assert!(!vfs::exists(&root.join("a.txt")));
+        journal.record(create(), Some(root));
+        assert!(journal.history.lock().unwrap().undone.is_empty());
+        vfs::unmount(root);
+    }
+}
+
+// endregion
@@ -0,0 +1,146 @@
+use crate::api::fs::text::{Encoding, BINARY_SNIFF_LEN};
+use crate::api::fs::vfs;
+use std::io::Read;
+use std::path::Path;
+
+// region: ---File Kind
+
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FileKind {
+    Text,
+    Image,
+    Pdf,
+    Audio,
+    Video,
+    Archive,
+    Binary, // binary of an unknown format
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct FileType {
+    pub kind: FileKind,
+    pub mime: &'static str,
+}
+
+// prefixes of the known formats, checked in order
+const SIGNATURES: &[(&[u8], FileKind, &str)] = &[
+    (b"\x89PNG\r\n\x1a\n", FileKind::Image, "image/png"),
+    (b"\xff\xd8\xff", FileKind::Image, "image/jpeg"),
+    (b"GIF87a", FileKind::Image, "image/gif"),
+    (b"GIF89a", FileKind::Image, "image/gif"),
+    (b"II*\x00", FileKind::Image, "image/tiff"),
+    (b"MM\x00*", FileKind::Image, "image/tiff"),
+    (b"\x00\x00\x01\x00", FileKind::Image, "image/x-icon"),
+    (b"%PDF-", FileKind::Pdf, "application/pdf"),
+    (b"ID3", FileKind::Audio, "audio/mpeg"),
+    (b"fLaC", FileKind::Audio, "audio/flac"),
+    (b"OggS", FileKind::Audio, "audio/ogg"),
+    (b"\x1a\x45\xdf\xa3", FileKind::Video, "video/webm"),
+    (b"PK\x03\x04", FileKind::Archive, "application/zip"),
+    (b"PK\x05\x06", FileKind::Archive, "application/zip"),
+    (b"\x1f\x8b", FileKind::Archive, "application/gzip"),
+    (b"BZh", FileKind::Archive, "application/x-bzip2"),
+    (b"\xfd7zXZ\x00", FileKind::Archive, "application/x-xz"),
+    (
+        b"7z\xbc\xaf\x27\x1c",
+        FileKind::Archive,
+        "application/x-7z-compressed",
+    ),
+    (b"Rar!\x1a\x07", FileKind::Archive, "application/vnd.rar"),
+];
+
+// RIFF containers, by the format at byte 8
+const RIFF_FORMATS: &[(&[u8], FileKind, &str)] = &[
+    (b"WEBP", FileKind::Image, "image/webp"),
+    (b"WAVE", FileKind::Audio, "audio/wav"),
+    (b"AVI ", FileKind::Video, "video/x-msvideo"),
+];
+
+impl FileType {
+    pub const TEXT: FileType = FileType {
+        kind: FileKind::Text,
+        mime: "text/plain",
+    };
+    pub const BINARY: FileType = FileType {
+        kind: FileKind::Binary,
+        mime: "application/octet-stream",
+    };
+
+    // type of the file from its first bytes, an unreadable file is binary
+    pub fn read(path: &Path) -> FileType {
+        let mut bytes = Vec::with_capacity(BINARY_SNIFF_LEN);
+        match vfs::open(path)
+            .and_then(|file| file.take(BINARY_SNIFF_LEN as u64).read_to_end(&mut bytes))
+        {
+            Ok(_) => FileType::detect(&bytes),
+            Err(_) => FileType::BINARY,
+        }
+    }
+
+    // the bytes are the start of the file
+    pub fn detect(bytes: &[u8]) -> FileType {
+        let found = SIGNATURES
+            .iter()
+            .find(|(signature, _, _)| bytes.starts_with(signature))
+            .or_else(|| match (bytes.get(..4), bytes.get(8..12)) {
+                (Some(b"RIFF"), Some(format)) => RIFF_FORMATS
+                    .iter()
+                    .find(|(signature, _, _)| format == *signature),
+                _ => None,
+            });
+        if let Some((_, kind, mime)) = found {
+            return FileType { kind: *kind, mime };
+        }
+
+        // mp4 and quicktime boxes start with their size then "ftyp" and the brand
+        if bytes.get(4..8) == Some(b"ftyp") {
+            return match bytes.get(8..12) {
+                Some(b"M4A ") | Some(b"M4B ") => FileType {
+                    kind: FileKind::Audio,
+                    mime: "audio/mp4",
+                },
+                Some(b"qt  ") => FileType {
+                    kind: FileKind::Video,
+                    mime: "video/quicktime",
+                },
+                _ => FileType {
+                    kind: FileKind::Video,
+                    mime: "video/mp4",
+                },
+            };
+        }
+        if bytes.get(257..262) == Some(b"ustar") {
+            return FileType {
+                kind: FileKind::Archive,
+                mime: "application/x-tar",
+            };
+        }
+        // mpeg audio frame without id3 tag
+        if bytes.len() >= 2 && bytes[0] == 0xff && matches!(bytes[1], 0xfb | 0xf3 | 0xf2) {
+            return FileType {
+                kind: FileKind::Audio,
+                mime: "audio/mpeg",
+            };
+        }
+
+        match Encoding::detect(bytes) {
+            Some(_) if is_svg(bytes) => FileType {
+                kind: FileKind::Image,
+                mime: "image/svg+xml",
+            },
+            Some(_) => FileType::TEXT,
+            None => FileType::BINARY,
+        }
+    }
+}
+
+// an svg is an xml text with a <svg root
+fn is_svg(bytes: &[u8]) -> bool {
+    let start = String::from_utf8_lossy(&bytes[..bytes.len().min(1024)]).to_lowercase();
+    let start = start.trim_start_matches('\u{feff}').trim_start();
+    (start.starts_with("<?xml") || start.starts_with("<svg") || start.starts_with("<!--"))
+        && start.contains("<svg")
+}
+
+// endregion
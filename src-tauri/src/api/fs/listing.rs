@@ -0,0 +1,428 @@
+use super::is_hidden;
+use super::operations::TRASH_FOLDER_NAME;
+use super::vfs::{self, Stat};
+use crate::error::Error;
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use std::cmp::Ordering;
+use std::io;
+use std::path::{Path, PathBuf};
+
+// region: ---List Options
+
+// files read in every folder for gitignore patterns
+const IGNORE_FILES: [&str; 2] = [".gitignore", ".ignore"];
+
+/// # ListOptions
+/// {
+///     sortBy: "name" | "modified" | "size" | "type", // name is a natural sort ("file2" < "file10")
+///     descending: bool,
+///     foldersFirst: bool,                            // true
+///     showHidden: bool,                              // true
+///     useIgnoreFiles: bool,                          // true, .gitignore and .ignore
+///     exclude: [pattern, ...],                       // gitignore patterns relative to the workspace root
+///     openArchives: bool,                            // false, .zip files are listed as folders
+/// }
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ListOptions {
+    pub sort_by: SortBy,
+    pub descending: bool,
+    pub folders_first: bool,
+    pub show_hidden: bool,
+    pub use_ignore_files: bool,
+    pub exclude: Vec<String>,
+    pub open_archives: bool,
+}
+
+impl Default for ListOptions {
+    fn default() -> Self {
+        ListOptions {
+            sort_by: SortBy::Name,
+            descending: false,
+            folders_first: true,
+            show_hidden: true,
+            use_ignore_files: true,
+            exclude: Vec::new(),
+            open_archives: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SortBy {
+    Name,
+    Modified,
+    Size,
+    Type, // extension, then name
+}
+
+// endregion
+
+// region: ---Listing
+
+// visible entries of the folders of a workspace, the folders are entered from the root
+pub struct Listing {
+    root: PathBuf,
+    options: ListOptions,
+    exclude: Gitignore,
+    ignore_files: Vec<Gitignore>,       // of the entered folders
+    pub(super) ancestors: Vec<PathBuf>, // canonical paths of the folders being generated
+}
+
+impl Listing {
+    pub fn new(root: &Path, options: ListOptions) -> Result<Listing, Error> {
+        let exclude = exclude_matcher(root, &options.exclude)?;
+
+        Ok(Listing {
+            root: root.to_path_buf(),
+            options,
+            exclude,
+            ignore_files: Vec::new(),
+            ancestors: Vec::new(),
+        })
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn options(&self) -> &ListOptions {
+        &self.options
+    }
+
+    // enter the folders from the root to the folder, for the ignore files of its parents
+    pub fn enter_from_root(&mut self, folder: &Path) {
+        let mut folders = vec![folder.to_path_buf()];
+        if let Ok(relative) = folder.strip_prefix(&self.root) {
+            let mut parent = self.root.clone();
+            folders = vec![parent.clone()];
+            for component in relative.components() {
+                parent.push(component);
+                folders.push(parent.clone());
+            }
+        }
+        for folder in folders {
+            self.enter(&folder);
+        }
+    }
+
+    pub fn enter(&mut self, folder: &Path) {
+        let mut builder = GitignoreBuilder::new(folder);
+        if self.options.use_ignore_files {
+            for name in IGNORE_FILES {
+                let path = folder.join(name);
+                if let Ok(bytes) = vfs::read(&path) {
+                    // the valid lines of a file with an invalid one are still used
+                    for line in String::from_utf8_lossy(&bytes).lines() {
+                        let _ = builder.add_line(Some(path.clone()), line);
+                    }
+                }
+            }
+        }
+        self.ignore_files
+            .push(builder.build().unwrap_or_else(|_| Gitignore::empty()));
+    }
+
+    pub fn leave(&mut self) {
+        self.ignore_files.pop();
+    }
+
+    // visible children of the entered folder in the sort order, with their metadata if readable
+    pub fn read_folder(&self, folder: &Path) -> io::Result<Vec<(PathBuf, Option<Stat>)>> {
+        let mut entries: Vec<(PathBuf, Option<Stat>)> = vfs::list(folder)?
+            .into_iter()
+            .map(|path| {
+                let metadata = vfs::stat(&path).or_else(|_| vfs::stat_link(&path)).ok();
+                (path, metadata)
+            })
+            .filter(|(path, metadata)| self.is_visible(path, metadata.as_ref()))
+            .collect();
+        entries.sort_by(|left, right| self.compare(left, right));
+        Ok(entries)
+    }
+
+    // stops at the first visible child
+    pub fn has_visible_children(&mut self, folder: &Path) -> io::Result<bool> {
+        self.enter(folder);
+        let has_children = vfs::list(folder).map(|paths| {
+            paths.into_iter().any(|path| {
+                let metadata = vfs::stat(&path).ok();
+                self.is_visible(&path, metadata.as_ref())
+            })
+        });
+        self.leave();
+        has_children
+    }
+
+    // the entry and its parents are visible from the root, and it is not in .git
+    pub fn is_included(&mut self, path: &Path) -> bool {
+        let relative = match path.strip_prefix(&self.root) {
+            Ok(relative) => relative.to_path_buf(),
+            Err(_) => return false,
+        };
+        let depth = self.ignore_files.len();
+        let mut current = self.root.clone();
+        self.enter(&current);
+
+        let mut included = true;
+        for component in relative.components() {
+            current.push(component);
+            let metadata = vfs::stat(&current).ok();
+            if component.as_os_str() == ".git" || !self.is_visible(&current, metadata.as_ref()) {
+                included = false;
+                break;
+            }
+            if metadata.is_some_and(|metadata| metadata.is_dir) {
+                self.enter(&current);
+            }
+        }
+        self.ignore_files.truncate(depth);
+        included
+    }
+
+    // visible files in the folder and its subfolders, the symlinked folders are not entered
+    pub fn included_files(&mut self, folder: &Path) -> Vec<PathBuf> {
+        let mut files = Vec::new();
+        if !self.is_included(folder) {
+            return files;
+        }
+        let depth = self.ignore_files.len();
+        if let Some(parent) = folder.parent() {
+            self.enter_from_root(parent);
+        }
+        self.walk(folder, &mut |file| {
+            files.push(file.to_path_buf());
+            true
+        });
+        self.ignore_files.truncate(depth);
+        files
+    }
+
+    // the visible files from the path in the listing order until `f` returns false, false when
+    // it was stopped (.git and the symlinked folders are not entered)
+    fn walk(&mut self, path: &Path, f: &mut dyn FnMut(&Path) -> bool) -> bool {
+        let is_link = vfs::stat_link(path).map_or(true, |metadata| metadata.is_symlink);
+        let metadata = vfs::stat(path);
+        if metadata.as_ref().is_ok_and(Stat::is_file) {
+            return f(path);
+        }
+        let is_dir = metadata.is_ok_and(|metadata| metadata.is_dir);
+        if !is_dir || is_link || path.file_name().is_some_and(|name| name == ".git") {
+            return true;
+        }
+
+        self.enter(path);
+        let mut walking = true;
+        for (child, _) in self.read_folder(path).unwrap_or_default() {
+            if !self.walk(&child, f) {
+                walking = false;
+                break;
+            }
+        }
+        self.leave();
+        walking
+    }
+
+    fn is_visible(&self, path: &Path, metadata: Option<&Stat>) -> bool {
+        if path == self.root.join(TRASH_FOLDER_NAME) {
+            return false;
+        }
+        if let Some(metadata) = metadata {
+            if !self.options.show_hidden && is_hidden(path, metadata) {
+                return false;
+            }
+        }
+
+        let is_dir = metadata.is_some_and(|metadata| metadata.is_dir);
+        if path.starts_with(&self.root) && self.exclude.matched(path, is_dir).is_ignore() {
+            return false;
+        }
+
+        // the ignore file of the deepest folder with a matching pattern decides
+        for ignore_file in self.ignore_files.iter().rev() {
+            if !path.starts_with(ignore_file.path()) {
+                continue;
+            }
+            let matched = ignore_file.matched(path, is_dir);
+            if matched.is_ignore() {
+                return false;
+            }
+            if matched.is_whitelist() {
+                return true;
+            }
+        }
+        true
+    }
+
+    fn compare(
+        &self,
+        (left_path, left): &(PathBuf, Option<Stat>),
+        (right_path, right): &(PathBuf, Option<Stat>),
+    ) -> Ordering {
+        let is_dir =
+            |metadata: &Option<Stat>| metadata.as_ref().is_some_and(|metadata| metadata.is_dir);
+        if self.options.folders_first && is_dir(left) != is_dir(right) {
+            return is_dir(right).cmp(&is_dir(left));
+        }
+
+        let name = |path: &PathBuf| {
+            path.file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default()
+        };
+        let by_name = || natural_cmp(&name(left_path), &name(right_path));
+        let ordering = match self.options.sort_by {
+            SortBy::Name => by_name(),
+            SortBy::Modified => {
+                let modified = |metadata: &Option<Stat>| {
+                    metadata.as_ref().and_then(|metadata| metadata.modified)
+                };
+                modified(left).cmp(&modified(right)).then_with(by_name)
+            }
+            SortBy::Size => {
+                let size =
+                    |metadata: &Option<Stat>| metadata.as_ref().map_or(0, |metadata| metadata.len);
+                size(left).cmp(&size(right)).then_with(by_name)
+            }
+            SortBy::Type => {
+                let extension = |path: &PathBuf| {
+                    path.extension()
+                        .map(|extension| extension.to_string_lossy().to_lowercase())
+                };
+                extension(left_path)
+                    .cmp(&extension(right_path))
+                    .then_with(by_name)
+            }
+        };
+
+        if self.options.descending {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    }
+}
+
+// endregion
+
+// region: ---Walk
+
+// the files of the workspace with the ignore rules of the listing, read through the vfs, until
+// `f` returns false (.git, the trash and the symlinked folders are not entered)
+pub fn walk_files<F: FnMut(&Path) -> bool>(
+    root: &Path,
+    options: &ListOptions,
+    mut f: F,
+) -> Result<(), Error> {
+    Listing::new(root, options.clone())?.walk(root, &mut f);
+    Ok(())
+}
+
+fn exclude_matcher(root: &Path, patterns: &[String]) -> Result<Gitignore, Error> {
+    let mut builder = GitignoreBuilder::new(root);
+    for pattern in patterns {
+        builder
+            .add_line(None, pattern)
+            .map_err(|err| Error::InvalidPattern(err.to_string()))?;
+    }
+    builder
+        .build()
+        .map_err(|err| Error::InvalidPattern(err.to_string()))
+}
+
+// endregion
+
+// region: ---Natural Sort
+
+// case insensitive, the numbers are compared by value ("file2" < "file10")
+pub fn natural_cmp(left: &str, right: &str) -> Ordering {
+    let mut left_chars = left.chars().peekable();
+    let mut right_chars = right.chars().peekable();
+
+    loop {
+        let (l, r) = match (left_chars.peek(), right_chars.peek()) {
+            (None, None) => return left.cmp(right),
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(l), Some(r)) => (*l, *r),
+        };
+
+        let ordering = if l.is_ascii_digit() && r.is_ascii_digit() {
+            let left_number = take_number(&mut left_chars);
+            let right_number = take_number(&mut right_chars);
+            left_number
+                .len()
+                .cmp(&right_number.len())
+                .then_with(|| left_number.cmp(&right_number))
+        } else {
+            left_chars.next();
+            right_chars.next();
+            l.to_lowercase().cmp(r.to_lowercase())
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+}
+
+// digits of the number without its leading zeros
+fn take_number(chars: &mut std::iter::Peekable<std::str::Chars>) -> String {
+    let mut number = String::new();
+    while let Some(c) = chars.peek().filter(|c| c.is_ascii_digit()) {
+        if !(number.is_empty() && *c == '0') {
+            number.push(*c);
+        }
+        chars.next();
+    }
+    number
+}
+
+// endregion
+
+// region: ---Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted(names: &[&str]) -> Vec<String> {
+        let mut names: Vec<String> = names.iter().map(|name| name.to_string()).collect();
+        names.sort_by(|left, right| natural_cmp(left, right));
+        names
+    }
+
+    #[test]
+    fn numbers_are_compared_by_value() {
+        assert_eq!(
+            sorted(&["file10", "file2", "file1", "file"]),
+            ["file", "file1", "file2", "file10"]
+        );
+        assert_eq!(
+            sorted(&["v1.10", "v1.9", "v1.1"]),
+            ["v1.1", "v1.9", "v1.10"]
+        );
+    }
+
+    #[test]
+    fn case_is_ignored_until_the_names_are_equal() {
+        assert_eq!(sorted(&["b", "B", "a", "C"]), ["a", "B", "b", "C"]);
+        assert_eq!(natural_cmp("Readme", "README"), "Readme".cmp("README"));
+    }
+
+    #[test]
+    fn leading_zeros_only_break_ties() {
+        assert_eq!(natural_cmp("a007", "a7"), Ordering::Less);
+        assert_eq!(natural_cmp("a007", "a8"), Ordering::Less);
+        assert_eq!(natural_cmp("a010", "a9"), Ordering::Greater);
+    }
+
+    #[test]
+    fn prefixes_come_first() {
+        assert_eq!(natural_cmp("", "a"), Ordering::Less);
+        assert_eq!(natural_cmp("ab", "a"), Ordering::Greater);
+        assert_eq!(natural_cmp("a", "a"), Ordering::Equal);
+    }
+}
+
+// endregion
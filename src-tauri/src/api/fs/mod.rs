@@ -0,0 +1,721 @@
+pub mod archive;
+pub mod documents;
+pub mod duplicates;
+pub mod history;
+pub mod journal;
+pub mod kind;
+pub mod listing;
+pub mod operations;
+pub mod sandbox;
+pub mod text;
+pub mod thumbnail;
+pub mod usage;
+pub mod vfs;
+pub mod watcher;
+
+use crate::api::workspace::Workspace;
+use crate::error::Error as FsError;
+use archive::{is_archive, split_archive_path, Archive, ArchiveEntry};
+use kind::FileType;
+use listing::{ListOptions, Listing};
+use std::{
+    env,
+    io::Error,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+use tauri::State;
+use text::BINARY_SNIFF_LEN;
+use usage::Usage;
+use vfs::Stat;
+
+// region: ---Tauri Command
+
+// relative paths are from the workspace root, the paths outside of it are refused
+#[tauri::command]
+pub async fn get_disk_entry_from_path(
+    path: &str,
+    options: Option<ListOptions>,
+    workspace: State<'_, Workspace>,
+) -> Result<DiskEntry, String> {
+    let path = workspace.resolve(path).map_err(|err| err.to_string())?;
+    get_disk_entry(
+        path,
+        workspace.root().as_deref(),
+        options.unwrap_or_default(),
+    )
+    .map_err(|err| err.to_string())
+}
+
+// entries of the folder without their children, `limit` entries from `offset`
+#[tauri::command]
+pub async fn list_directory(
+    path: &str,
+    offset: Option<usize>,
+    limit: Option<usize>,
+    options: Option<ListOptions>,
+    workspace: State<'_, Workspace>,
+) -> Result<DirectoryPage, FsError> {
+    list_directory_page(
+        workspace.resolve(path)?,
+        workspace.root().as_deref(),
+        options.unwrap_or_default(),
+        offset.unwrap_or(0),
+        limit.unwrap_or(DEFAULT_PAGE_SIZE),
+    )
+}
+
+// endregion
+
+// region: --- DiskEntry
+#[derive(serde::Serialize)]
+pub enum DiskEntry {
+    File(File),
+    Folder(Folder),
+    Error(EntryError), // entry that could not be read
+}
+
+pub fn get_disk_entry<P: AsRef<Path>>(
+    path: P,
+    root: Option<&Path>,
+    options: ListOptions,
+) -> Result<DiskEntry, FsError> {
+    let path = absolute_path(path.as_ref())?;
+    let root = match root {
+        Some(root) => absolute_path(root)?,
+        None => path.clone(),
+    };
+
+    let mut listing = Listing::new(&root, options)?;
+
+    // an entry of an archive
+    if !vfs::exists(&path) {
+        if let Some((archive_path, name)) = split_archive_path(&path) {
+            let archive = Archive::open(&archive_path)?;
+            return get_archive_disk_entry(&archive, &name, &listing, true);
+        }
+    }
+
+    if let Some(parent) = path.parent().filter(|parent| parent.starts_with(&root)) {
+        listing.enter_from_root(parent);
+    }
+    get_disk_entry_path_buf(path, &mut listing)
+}
+
+pub fn get_disk_entry_path_buf(
+    buf_path: PathBuf,
+    listing: &mut Listing,
+) -> Result<DiskEntry, FsError> {
+    if vfs::is_dir(&buf_path) {
+        Ok(DiskEntry::Folder(Folder::generate(buf_path, listing)?))
+    } else if listing.options().open_archives && is_archive(&buf_path) {
+        let archive = Archive::open(&buf_path)?;
+        get_archive_disk_entry(&archive, "", listing, true)
+    } else {
+        Ok(DiskEntry::File(File::generate(buf_path, listing.root())?))
+    }
+}
+
+fn absolute_path(path: &Path) -> Result<PathBuf, Error> {
+    if path.is_absolute() {
+        Ok(path.to_path_buf())
+    } else {
+        Ok(env::current_dir()?.join(path))
+    }
+}
+
+// endregion
+
+// region: ---Metadata
+#[derive(serde::Serialize)]
+pub struct Metadata {
+    path: String,                  // absolute, symlinks are not resolved
+    relative_path: Option<String>, // from the workspace root, None outside of it
+    size: u64,                     // bytes
+    created: Option<u64>,          // milliseconds since the unix epoch, None if unsupported
+    modified: Option<u64>,
+    accessed: Option<u64>,
+    readonly: bool,
+    hidden: bool,
+    symlink: Option<Symlink>,
+}
+
+#[derive(serde::Serialize)]
+pub struct Symlink {
+    target: String,
+    broken: bool,  // the target does not exist
+    is_loop: bool, // the target is a folder containing the link, its children are not read
+    outside: bool, // the target is outside of the workspace, it is not read
+}
+
+impl Metadata {
+    fn read(path: &Path, root: &Path) -> Result<Metadata, Error> {
+        let link_metadata = vfs::stat_link(path)?;
+        let symlink = if link_metadata.is_symlink {
+            Some(Symlink {
+                target: vfs::read_link(path)?.to_string_lossy().to_string(),
+                broken: vfs::stat(path).is_err(),
+                is_loop: false,
+                outside: sandbox::is_outside(root, path),
+            })
+        } else {
+            None
+        };
+        // the metadata of the target, or of the link itself when it is broken
+        let metadata = vfs::stat(path).unwrap_or(link_metadata);
+
+        Ok(Metadata {
+            path: path.to_string_lossy().to_string(),
+            relative_path: path
+                .strip_prefix(root)
+                .ok()
+                .map(|relative| relative.to_string_lossy().replace('\\', "/")),
+            size: metadata.len,
+            created: metadata.created.and_then(to_millis),
+            modified: metadata.modified.and_then(to_millis),
+            accessed: metadata.accessed.and_then(to_millis),
+            readonly: metadata.readonly,
+            hidden: is_hidden(path, &metadata),
+            symlink,
+        })
+    }
+
+    fn is_outside(&self) -> bool {
+        self.symlink
+            .as_ref()
+            .map_or(false, |symlink| symlink.outside)
+    }
+}
+
+fn to_millis(time: SystemTime) -> Option<u64> {
+    time.duration_since(UNIX_EPOCH)
+        .ok()
+        .map(|duration| duration.as_millis() as u64)
+}
+
+pub(crate) fn is_hidden(path: &Path, metadata: &Stat) -> bool {
+    metadata.hidden || is_dot_file(path)
+}
+
+fn is_dot_file(path: &Path) -> bool {
+    path.file_name()
+        .map_or(false, |name| name.to_string_lossy().starts_with('.'))
+}
+
+// endregion
+
+// region: ---File
+#[derive(serde::Serialize)]
+pub struct File {
+    name: Option<String>,      // dot file or file name
+    extension: Option<String>, // .txt
+    data: Option<Vec<u8>>,
+    file_type: FileType, // sniffed from the content
+    metadata: Metadata,
+}
+
+impl File {
+    fn generate(buf_path: PathBuf, root: &Path) -> Result<File, Error> {
+        let file_name = buf_path
+            .file_stem()
+            .map(|name| name.to_string_lossy().to_string());
+        let extension = buf_path
+            .extension()
+            .map(|extension| extension.to_string_lossy().to_string());
+
+        let metadata = Metadata::read(&buf_path, root)?;
+        let file_type = match metadata.is_outside() {
+            true => FileType::BINARY,
+            false => FileType::read(&buf_path),
+        };
+
+        Ok(File {
+            name: file_name,
+            extension,
+            data: None,
+            file_type,
+            metadata,
+        })
+    }
+}
+
+// endregion
+
+// region: ---Folder
+#[derive(serde::Serialize)]
+pub struct Folder {
+    name: Option<String>,
+    has_children: bool,
+    disk_entries: Vec<DiskEntry>, // empty when the folder is listed without its children
+    usage: Option<Usage>,         // of the listed entries, None without its children
+    metadata: Metadata,
+}
+
+impl Folder {
+    fn generate(path: PathBuf, listing: &mut Listing) -> Result<Folder, Error> {
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string());
+        let mut metadata = Metadata::read(&path, listing.root())?;
+        if metadata.is_outside() {
+            return Ok(Folder {
+                name,
+                has_children: false,
+                disk_entries: Vec::new(),
+                usage: None,
+                metadata,
+            });
+        }
+
+        // a symlink to a folder containing it would be generated forever
+        let canonical_path = vfs::canonicalize(&path)?;
+        if listing.ancestors.contains(&canonical_path) {
+            if let Some(symlink) = &mut metadata.symlink {
+                symlink.is_loop = true;
+            }
+            return Ok(Folder {
+                name,
+                has_children: true,
+                disk_entries: Vec::new(),
+                usage: None,
+                metadata,
+            });
+        }
+
+        let mut entries: Vec<DiskEntry> = Vec::new();
+        listing.enter(&path);
+        let entries_result = listing.read_folder(&path);
+
+        listing.ancestors.push(canonical_path);
+        for (path, _) in entries_result.iter().flatten() {
+            entries.push(
+                get_disk_entry_path_buf(path.clone(), listing)
+                    .unwrap_or_else(|err| DiskEntry::Error(EntryError::new(path, err))),
+            );
+        }
+        listing.ancestors.pop();
+        listing.leave();
+        entries_result?;
+
+        Ok(Folder {
+            name,
+            has_children: !entries.is_empty(),
+            usage: Some(usage_of(&entries)),
+            disk_entries: entries,
+            metadata,
+        })
+    }
+
+    // the folder without its children
+    fn generate_shallow(path: PathBuf, listing: &mut Listing) -> Result<Folder, Error> {
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string());
+        let metadata = Metadata::read(&path, listing.root())?;
+        let has_children = !metadata.is_outside() && listing.has_visible_children(&path)?;
+
+        Ok(Folder {
+            name,
+            has_children,
+            disk_entries: Vec::new(),
+            usage: None,
+            metadata,
+        })
+    }
+}
+
+// the files and folders of the entries with the ones of their children
+fn usage_of(entries: &[DiskEntry]) -> Usage {
+    let mut usage = Usage::default();
+    for entry in entries {
+        match entry {
+            DiskEntry::File(file) => usage.add(Usage {
+                size: file.metadata.size,
+                files: 1,
+                folders: 0,
+            }),
+            DiskEntry::Folder(folder) => {
+                usage.add(folder.usage.unwrap_or_default());
+                usage.folders += 1;
+            }
+            DiskEntry::Error(_) => {}
+        }
+    }
+    usage
+}
+
+// endregion
+
+// region: ---EntryError
+#[derive(serde::Serialize)]
+pub struct EntryError {
+    name: Option<String>,
+    error: FsError,
+}
+
+impl EntryError {
+    fn new<E: Into<FsError>>(path: &Path, error: E) -> EntryError {
+        EntryError {
+            name: path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string()),
+            error: error.into(),
+        }
+    }
+}
+
+// endregion
+
+// region: ---Directory Page
+
+const DEFAULT_PAGE_SIZE: usize = 500;
+
+#[derive(serde::Serialize)]
+pub struct DirectoryPage {
+    entries: Vec<DiskEntry>,
+    offset: usize,
+    total: usize, // number of entries in the folder
+    has_more: bool,
+}
+
+pub fn list_directory_page<P: AsRef<Path>>(
+    path: P,
+    root: Option<&Path>,
+    options: ListOptions,
+    offset: usize,
+    limit: usize,
+) -> Result<DirectoryPage, FsError> {
+    let path = absolute_path(path.as_ref())?;
+    let root = match root {
+        Some(root) => absolute_path(root)?,
+        None => path.clone(),
+    };
+
+    let mut listing = Listing::new(&root, options)?;
+
+    // the archive or a folder in it
+    let archive_location = match is_archive(&path) {
+        true => Some((path.clone(), String::new())),
+        false => split_archive_path(&path),
+    };
+    if let Some((archive_path, name)) = archive_location {
+        let archive = Archive::open(&archive_path)?;
+        let children = archive_children(&archive, &name, &listing);
+        let total = children.len();
+        let end = offset.saturating_add(limit).min(total);
+        let entries = children
+            .get(offset..end)
+            .unwrap_or_default()
+            .iter()
+            .map(|entry| {
+                get_archive_disk_entry(&archive, &entry.name, &listing, false).unwrap_or_else(
+                    |err| DiskEntry::Error(EntryError::new(&archive.path().join(&entry.name), err)),
+                )
+            })
+            .collect();
+        return Ok(DirectoryPage {
+            entries,
+            offset,
+            total,
+            has_more: end < total,
+        });
+    }
+
+    listing.enter_from_root(&path);
+
+    // only the metadata is read for the whole folder, the entries of the page are read after
+    let paths = listing.read_folder(&path)?;
+
+    let total = paths.len();
+    let end = offset.saturating_add(limit).min(total);
+    let entries = paths
+        .get(offset..end)
+        .unwrap_or_default()
+        .iter()
+        .map(|(path, _)| get_shallow_disk_entry(path.clone(), &mut listing))
+        .collect();
+
+    Ok(DirectoryPage {
+        entries,
+        offset,
+        total,
+        has_more: end < total,
+    })
+}
+
+fn get_shallow_disk_entry(path: PathBuf, listing: &mut Listing) -> DiskEntry {
+    let entry = if vfs::is_dir(&path) {
+        Folder::generate_shallow(path.clone(), listing).map(DiskEntry::Folder)
+    } else if listing.options().open_archives && is_archive(&path) {
+        return Archive::open(&path)
+            .and_then(|archive| get_archive_disk_entry(&archive, "", listing, false))
+            .unwrap_or_else(|err| DiskEntry::Error(EntryError::new(&path, err)));
+    } else {
+        File::generate(path.clone(), listing.root()).map(DiskEntry::File)
+    };
+    entry.unwrap_or_else(|err| DiskEntry::Error(EntryError::new(&path, err)))
+}
+
+// endregion
+
+// region: ---Archive Entry
+
+// the archive itself for the name "", its folders are listed with their children when `recursive`
+fn get_archive_disk_entry(
+    archive: &Archive,
+    name: &str,
+    listing: &Listing,
+    recursive: bool,
+) -> Result<DiskEntry, FsError> {
+    let path = match name.is_empty() {
+        true => archive.path().to_path_buf(),
+        false => archive.path().join(name),
+    };
+    let entry = match name.is_empty() {
+        true => None,
+        false => Some(
+            archive
+                .entry(name)
+                .ok_or_else(|| FsError::NotFound(path.display().to_string()))?,
+        ),
+    };
+    let metadata = match entry {
+        Some(entry) => Metadata::from_archive_entry(&path, entry, listing.root()),
+        None => Metadata::read(&path, listing.root())?,
+    };
+
+    if let Some(entry) = entry.filter(|entry| !entry.is_folder) {
+        let file_type = archive
+            .read(&entry.name, Some(BINARY_SNIFF_LEN as u64))
+            .map_or(FileType::BINARY, |bytes| FileType::detect(&bytes));
+        return Ok(DiskEntry::File(File {
+            name: path
+                .file_stem()
+                .map(|name| name.to_string_lossy().to_string()),
+            extension: path
+                .extension()
+                .map(|extension| extension.to_string_lossy().to_string()),
+            data: None,
+            file_type,
+            metadata,
+        }));
+    }
+
+    let children = archive_children(archive, name, listing);
+    let disk_entries = match recursive {
+        true => children
+            .iter()
+            .map(|child| {
+                get_archive_disk_entry(archive, &child.name, listing, true).unwrap_or_else(|err| {
+                    DiskEntry::Error(EntryError::new(&archive.path().join(&child.name), err))
+                })
+            })
+            .collect(),
+        false => Vec::new(),
+    };
+    Ok(DiskEntry::Folder(Folder {
+        name: path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string()),
+        has_children: !children.is_empty(),
+        usage: recursive.then(|| usage_of(&disk_entries)),
+        disk_entries,
+        metadata,
+    }))
+}
+
+// the hidden entries are left out unless shown
+fn archive_children<'a>(
+    archive: &'a Archive,
+    name: &str,
+    listing: &Listing,
+) -> Vec<&'a ArchiveEntry> {
+    archive
+        .children(name)
+        .into_iter()
+        .filter(|entry| {
+            listing.options().show_hidden
+                || !entry
+                    .name
+                    .rsplit('/')
+                    .next()
+                    .map_or(false, |name| name.starts_with('.'))
+        })
+        .collect()
+}
+
+impl Metadata {
+    // the entries of an archive are read-only, they have no creation and access times
+    fn from_archive_entry(path: &Path, entry: &ArchiveEntry, root: &Path) -> Metadata {
+        Metadata {
+            path: path.to_string_lossy().to_string(),
+            relative_path: path
+                .strip_prefix(root)
+                .ok()
+                .map(|relative| relative.to_string_lossy().replace('\\', "/")),
+            size: entry.size,
+            created: None,
+            modified: entry.modified,
+            accessed: None,
+            readonly: true,
+            hidden: is_dot_file(path),
+            symlink: None,
+        }
+    }
+}
+
+// endregion
+
+// region: ---Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use listing::SortBy;
+    use std::sync::Arc;
+    use vfs::MemoryVfs;
+
+    // workspace in memory with the (path, text) files, the paths ending with / are folders
+    fn workspace_in_memory(root: &str, files: &[(&str, &str)]) -> PathBuf {
+        let root = PathBuf::from(root);
+        vfs::mount(&root, Arc::new(MemoryVfs::new()));
+        vfs::create_dir_all(&root).unwrap();
+        for (path, text) in files {
+            match path.strip_suffix('/') {
+                Some(folder) => vfs::create_dir_all(&root.join(folder)).unwrap(),
+                None => {
+                    let path = root.join(path);
+                    vfs::create_dir_all(path.parent().unwrap()).unwrap();
+                    vfs::write(&path, text.as_bytes()).unwrap();
+                }
+            }
+        }
+        root
+    }
+
+    fn relative_paths(entries: &[DiskEntry]) -> Vec<String> {
+        entries
+            .iter()
+            .filter_map(|entry| match entry {
+                DiskEntry::File(file) => file.metadata.relative_path.clone(),
+                DiskEntry::Folder(folder) => folder.metadata.relative_path.clone(),
+                DiskEntry::Error(_) => None,
+            })
+            .collect()
+    }
+
+    fn folder(entry: DiskEntry) -> Folder {
+        match entry {
+            DiskEntry::Folder(folder) => folder,
+            _ => panic!("not a folder"),
+        }
+    }
+
+    const FILES: [(&str, &str); 6] = [
+        (".gitignore", "*.log\n"),
+        ("a10.txt", "ten"),
+        ("a2.txt", "two"),
+        ("b.log", "log"),
+        ("docs/x.md", "# x"),
+        ("empty/", ""),
+    ];
+
+    #[test]
+    fn folders_are_listed_first_in_natural_order() {
+        let root = workspace_in_memory("/fs-tree", &FILES);
+        let tree = folder(get_disk_entry(&root, Some(&root), ListOptions::default()).unwrap());
+        assert_eq!(
+            relative_paths(&tree.disk_entries),
+            ["docs", "empty", ".gitignore", "a2.txt", "a10.txt"]
+        );
+        let docs = folder(tree.disk_entries.into_iter().next().unwrap());
+        assert_eq!(relative_paths(&docs.disk_entries), ["docs/x.md"]);
+        assert_eq!(
+            tree.usage,
+            Some(Usage {
+                size: 15,
+                files: 4,
+                folders: 2,
+            })
+        );
+        vfs::unmount(&root);
+    }
+
+    #[test]
+    fn hidden_and_excluded_entries_are_not_listed() {
+        let root = workspace_in_memory("/fs-hidden", &FILES);
+        let options = ListOptions {
+            show_hidden: false,
+            exclude: vec![String::from("docs")],
+            ..ListOptions::default()
+        };
+        let tree = folder(get_disk_entry(&root, Some(&root), options).unwrap());
+        assert_eq!(
+            relative_paths(&tree.disk_entries),
+            ["empty", "a2.txt", "a10.txt"]
+        );
+        vfs::unmount(&root);
+    }
+
+    #[test]
+    fn folders_are_listed_by_pages() {
+        let root = workspace_in_memory("/fs-pages", &FILES);
+        let page = list_directory_page(&root, Some(&root), ListOptions::default(), 1, 2).unwrap();
+        assert_eq!(relative_paths(&page.entries), ["empty", ".gitignore"]);
+        assert_eq!((page.offset, page.total, page.has_more), (1, 5, true));
+
+        let last = list_directory_page(&root, Some(&root), ListOptions::default(), 4, 2).unwrap();
+        assert_eq!(relative_paths(&last.entries), ["a10.txt"]);
+        assert!(!last.has_more);
+
+        // the folders of a page are listed without their children
+        let first = list_directory_page(&root, Some(&root), ListOptions::default(), 0, 1).unwrap();
+        let docs = folder(first.entries.into_iter().next().unwrap());
+        assert!(docs.has_children && docs.disk_entries.is_empty());
+        vfs::unmount(&root);
+    }
+
+    #[test]
+    fn entries_are_sorted_by_the_options() {
+        let root = workspace_in_memory(
+            "/fs-sort",
+            &[("a.txt", "aaa"), ("b.md", "b"), ("c.txt", "cc")],
+        );
+        let by_size = ListOptions {
+            sort_by: SortBy::Size,
+            descending: true,
+            ..ListOptions::default()
+        };
+        let page = list_directory_page(&root, Some(&root), by_size, 0, 10).unwrap();
+        assert_eq!(relative_paths(&page.entries), ["a.txt", "c.txt", "b.md"]);
+
+        let by_type = ListOptions {
+            sort_by: SortBy::Type,
+            ..ListOptions::default()
+        };
+        let page = list_directory_page(&root, Some(&root), by_type, 0, 10).unwrap();
+        assert_eq!(relative_paths(&page.entries), ["b.md", "a.txt", "c.txt"]);
+        vfs::unmount(&root);
+    }
+
+    #[test]
+    fn files_have_their_metadata() {
+        let root = workspace_in_memory("/fs-metadata", &FILES);
+        let file = match get_disk_entry(root.join("docs/x.md"), Some(&root), ListOptions::default())
+        {
+            Ok(DiskEntry::File(file)) => file,
+            _ => panic!("not a file"),
+        };
+        assert_eq!(file.name.as_deref(), Some("x"));
+        assert_eq!(file.extension.as_deref(), Some("md"));
+        assert_eq!(file.metadata.relative_path.as_deref(), Some("docs/x.md"));
+        assert_eq!(file.metadata.size, 3);
+        assert!(file.metadata.modified.is_some() && !file.metadata.hidden);
+
+        let dot_file = Metadata::read(&root.join(".gitignore"), &root).unwrap();
+        assert!(dot_file.hidden);
+        vfs::unmount(&root);
+    }
+}
+
+// endregion
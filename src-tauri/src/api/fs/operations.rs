@@ -0,0 +1,780 @@
+use crate::api::fs::journal::{Journal, Operation};
+use crate::api::fs::vfs;
+use crate::api::fs::watcher::ignore_own_operation;
+use crate::api::workspace::Workspace;
+use crate::error::Error;
+use std::path::{Component, Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::State;
+
+// folder of the workspace root receiving the deleted entries
+pub const TRASH_FOLDER_NAME: &str = ".trash";
+
+// region: ---Tauri Command
+
+#[tauri::command]
+pub async fn create_file(
+    path: &str,
+    workspace: State<'_, Workspace>,
+    journal: State<'_, Journal>,
+) -> Result<(), Error> {
+    create(path, false, &workspace, &journal)
+}
+
+#[tauri::command]
+pub async fn create_folder(
+    path: &str,
+    workspace: State<'_, Workspace>,
+    journal: State<'_, Journal>,
+) -> Result<(), Error> {
+    create(path, true, &workspace, &journal)
+}
+
+// `name` is the new file name, the open files in the entry are returned with their new path
+#[tauri::command]
+pub async fn rename_entry(
+    path: &str,
+    name: &str,
+    open_files: Option<Vec<String>>,
+    workspace: State<'_, Workspace>,
+    journal: State<'_, Journal>,
+) -> Result<MoveReport, Error> {
+    let from = workspace.resolve_writable(path)?;
+    let to = renamed_path(&from, name)?;
+    let trash = Trash::of_workspace(&workspace)?;
+    let mut report = MoveReport::default();
+    if let Some(placed) = move_entry(&from, &to, ConflictPolicy::Fail, &trash)? {
+        report.add_moved(&from, placed, &open_files.unwrap_or_default());
+    }
+    journal.record_moves(&report.moved, workspace.root().as_deref());
+    Ok(report)
+}
+
+// move the entries into the destination folder
+#[tauri::command]
+pub async fn move_entries(
+    paths: Vec<String>,
+    destination: &str,
+    conflict: Option<ConflictPolicy>,
+    open_files: Option<Vec<String>>,
+    workspace: State<'_, Workspace>,
+    journal: State<'_, Journal>,
+) -> Result<MoveReport, Error> {
+    let conflict = conflict.unwrap_or_default();
+    let open_files = open_files.unwrap_or_default();
+    let destination = workspace.resolve_writable(destination)?;
+    let trash = Trash::of_workspace(&workspace)?;
+    let mut report = MoveReport::default();
+    let result = (|| {
+        for path in paths {
+            let from = workspace.resolve_writable(&path)?;
+            let to = path_in_folder(&from, &destination)?;
+            match move_entry(&from, &to, conflict, &trash)? {
+                Some(placed) => report.add_moved(&from, placed, &open_files),
+                None => report.skipped.push(from.to_string_lossy().to_string()),
+            }
+        }
+        Ok(())
+    })();
+
+    // the entries moved before an error are undone together
+    journal.record_moves(&report.moved, workspace.root().as_deref());
+    result.map(|_| report)
+}
+
+// copy the entries and their children into the destination folder
+#[tauri::command]
+pub async fn copy_entries(
+    paths: Vec<String>,
+    destination: &str,
+    conflict: Option<ConflictPolicy>,
+    workspace: State<'_, Workspace>,
+    journal: State<'_, Journal>,
+) -> Result<MoveReport, Error> {
+    let conflict = conflict.unwrap_or_default();
+    let destination = workspace.resolve_writable(destination)?;
+    let trash = Trash::of_workspace(&workspace)?;
+    let mut report = MoveReport::default();
+    let result = (|| {
+        for path in paths {
+            let from = workspace.resolve(&path)?;
+            let to = path_in_folder(&from, &destination)?;
+            match copy_entry(&from, &to, conflict, &trash)? {
+                Some(placed) => report.add_moved(&from, placed, &[]),
+                None => report.skipped.push(from.to_string_lossy().to_string()),
+            }
+        }
+        Ok(())
+    })();
+
+    if !report.moved.is_empty() {
+        journal.record(
+            Operation::Copy {
+                copies: report.moved.clone(),
+                trash_ids: Vec::new(),
+            },
+            workspace.root().as_deref(),
+        );
+    }
+    result.map(|_| report)
+}
+
+// move the entries into the trash of the workspace
+#[tauri::command]
+pub async fn delete_entries(
+    paths: Vec<String>,
+    workspace: State<'_, Workspace>,
+    journal: State<'_, Journal>,
+) -> Result<Vec<TrashItem>, Error> {
+    let root = workspace.root();
+    let trash = Trash::of_workspace(&workspace)?;
+    let mut items = Vec::new();
+    let result = (|| {
+        for path in &paths {
+            items.push(trash.put(&workspace.resolve_writable(path)?)?);
+        }
+        Ok(())
+    })();
+
+    if !items.is_empty() {
+        journal.record(
+            Operation::Delete {
+                items: items.clone(),
+            },
+            root.as_deref(),
+        );
+    }
+    result.map(|_| items)
+}
+
+#[tauri::command]
+pub async fn list_trash(workspace: State<'_, Workspace>) -> Result<Vec<TrashItem>, Error> {
+    Trash::of_workspace(&workspace)?.list()
+}
+
+// put the entry back at its original path, returned with the conflict policy applied
+#[tauri::command]
+pub async fn restore_from_trash(
+    id: &str,
+    conflict: Option<ConflictPolicy>,
+    workspace: State<'_, Workspace>,
+    journal: State<'_, Journal>,
+) -> Result<Option<String>, Error> {
+    let trash = Trash::of_workspace(&workspace)?;
+    let item = trash.get(id)?;
+    workspace.resolve_writable(&item.original_path)?;
+    let restored = trash.restore(id, conflict.unwrap_or_default())?;
+
+    // the entry is only put back in the trash by undo when it was restored at its path
+    let path = restored.map(|placed| {
+        if placed.path == Path::new(&item.original_path) {
+            journal.record(
+                Operation::Restore {
+                    items: vec![item],
+                    overwritten: placed.overwritten.into_iter().collect(),
+                },
+                workspace.root().as_deref(),
+            );
+        }
+        placed.path
+    });
+    Ok(path.map(|path| path.to_string_lossy().to_string()))
+}
+
+fn create(
+    path: &str,
+    is_folder: bool,
+    workspace: &Workspace,
+    journal: &Journal,
+) -> Result<(), Error> {
+    let path = workspace.resolve_writable(path)?;
+    create_entry(&path, is_folder)?;
+    journal.record(
+        Operation::Create {
+            path: path.to_string_lossy().to_string(),
+            is_folder,
+            trash_id: None,
+        },
+        workspace.root().as_deref(),
+    );
+    Ok(())
+}
+
+// endregion
+
+// region: ---Move Report
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct Moved {
+    pub from: String,
+    pub to: String,
+    // the entry replaced by the overwrite policy, put back by undo
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub overwritten: Option<TrashItem>,
+}
+
+#[derive(Debug, Default, serde::Serialize)]
+pub struct MoveReport {
+    pub moved: Vec<Moved>,
+    pub skipped: Vec<String>, // entries left in place by the conflict policy
+    pub open_files: Vec<Moved>, // open files inside the moved entries
+}
+
+impl MoveReport {
+    fn add_moved(&mut self, from: &Path, placed: Placed, open_files: &[String]) {
+        let to = &placed.path;
+        for open_file in open_files {
+            if let Ok(relative) = Path::new(open_file).strip_prefix(from) {
+                let new_path = if relative.as_os_str().is_empty() {
+                    to.to_path_buf()
+                } else {
+                    to.join(relative)
+                };
+                self.open_files.push(Moved {
+                    from: open_file.clone(),
+                    to: new_path.to_string_lossy().to_string(),
+                    overwritten: None,
+                });
+            }
+        }
+        self.moved.push(Moved {
+            from: from.to_string_lossy().to_string(),
+            to: to.to_string_lossy().to_string(),
+            overwritten: placed.overwritten,
+        });
+    }
+}
+
+// endregion
+
+// region: ---Operations
+
+// what to do when the target of a move or a copy exists
+#[derive(Debug, Clone, Copy, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ConflictPolicy {
+    #[default]
+    Fail,
+    Skip,
+    Overwrite,
+    KeepBoth, // the entry is renamed "name (1).ext"
+}
+
+// the new path of a moved or copied entry, with the entry it overwrote
+#[derive(Debug, PartialEq)]
+pub struct Placed {
+    pub path: PathBuf,
+    pub overwritten: Option<TrashItem>,
+}
+
+pub fn create_entry(path: &Path, is_folder: bool) -> Result<(), Error> {
+    ignore_own_operation(path);
+    if is_folder {
+        vfs::create_dir(path)?;
+    } else {
+        vfs::create_file(path)?;
+    }
+    Ok(())
+}
+
+// None when the entry was skipped, an overwritten entry goes to the trash
+pub fn move_entry(
+    from: &Path,
+    to: &Path,
+    conflict: ConflictPolicy,
+    trash: &Trash,
+) -> Result<Option<Placed>, Error> {
+    vfs::stat_link(from)?;
+    if to.starts_with(from) && to != from {
+        return Err(Error::InvalidPath(format!(
+            "cannot move {} into itself",
+            from.display()
+        )));
+    }
+
+    // a change of case on a case insensitive file system is not a conflict
+    let same_entry = match (vfs::canonicalize(from), vfs::canonicalize(to)) {
+        (Ok(from), Ok(to)) => from == to,
+        _ => false,
+    };
+    let placed = if same_entry {
+        Placed {
+            path: to.to_path_buf(),
+            overwritten: None,
+        }
+    } else {
+        match resolve_conflict(to, conflict, trash)? {
+            Some(placed) => placed,
+            None => return Ok(None),
+        }
+    };
+
+    let to = &placed.path;
+    ignore_own_operation(from);
+    ignore_own_operation(to);
+    if let Err(err) = vfs::rename(from, to) {
+        // the rename fails between two file systems
+        if vfs::stat_link(from).is_err() || vfs::exists(to) {
+            return Err(err.into());
+        }
+        copy_recursive(from, to).map_err(|_| Error::from(err))?;
+        remove_entry(from)?;
+    }
+    Ok(Some(placed))
+}
+
+pub fn copy_entry(
+    from: &Path,
+    to: &Path,
+    conflict: ConflictPolicy,
+    trash: &Trash,
+) -> Result<Option<Placed>, Error> {
+    vfs::stat_link(from)?;
+    if to.starts_with(from) && to != from {
+        return Err(Error::InvalidPath(format!(
+            "cannot copy {} into itself",
+            from.display()
+        )));
+    }
+
+    // a copy in the folder of the entry is a duplicate
+    let conflict = if to == from {
+        ConflictPolicy::KeepBoth
+    } else {
+        conflict
+    };
+    let placed = match resolve_conflict(to, conflict, trash)? {
+        Some(placed) => placed,
+        None => return Ok(None),
+    };
+    ignore_own_operation(&placed.path);
+    if let Err(err) = copy_recursive(from, &placed.path) {
+        let _ = remove_entry(&placed.path);
+        return Err(err.into());
+    }
+    Ok(Some(placed))
+}
+
+// remove the entry for good, use the trash for the entries of the user
+pub fn remove_entry(path: &Path) -> Result<(), Error> {
+    ignore_own_operation(path);
+    if vfs::stat_link(path)?.is_dir {
+        vfs::remove_dir_all(path)?;
+    } else {
+        vfs::remove_file(path)?;
+    }
+    Ok(())
+}
+
+fn copy_recursive(from: &Path, to: &Path) -> std::io::Result<()> {
+    let metadata = vfs::stat_link(from)?;
+    if metadata.is_symlink {
+        copy_symlink(from, to)
+    } else if metadata.is_dir {
+        vfs::create_dir(to)?;
+        for path in vfs::list(from)? {
+            if let Some(name) = path.file_name() {
+                copy_recursive(&path, &to.join(name))?;
+            }
+        }
+        Ok(())
+    } else {
+        vfs::copy_file(from, to)
+    }
+}
+
+// the link is copied, not its target
+#[cfg(unix)]
+fn copy_symlink(from: &Path, to: &Path) -> std::io::Result<()> {
+    vfs::symlink(&vfs::read_link(from)?, to)
+}
+
+#[cfg(not(unix))]
+fn copy_symlink(from: &Path, to: &Path) -> std::io::Result<()> {
+    if vfs::is_dir(from) {
+        vfs::create_dir(to)
+    } else {
+        vfs::copy_file(from, to)
+    }
+}
+
+// the free path for the entry, None when it is skipped
+fn resolve_conflict(
+    path: &Path,
+    conflict: ConflictPolicy,
+    trash: &Trash,
+) -> Result<Option<Placed>, Error> {
+    let placed = |path: PathBuf, overwritten| Some(Placed { path, overwritten });
+    if vfs::stat_link(path).is_err() {
+        return Ok(placed(path.to_path_buf(), None));
+    }
+    match conflict {
+        ConflictPolicy::Fail => Err(Error::AlreadyExists(path.display().to_string())),
+        ConflictPolicy::Skip => Ok(None),
+        ConflictPolicy::Overwrite => Ok(placed(path.to_path_buf(), Some(trash.put(path)?))),
+        ConflictPolicy::KeepBoth => Ok(placed(free_path(path), None)),
+    }
+}
+
+// "name (1).ext", "name (2).ext", ...
+fn free_path(path: &Path) -> PathBuf {
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let extension = path
+        .extension()
+        .map(|extension| format!(".{}", extension.to_string_lossy()))
+        .unwrap_or_default();
+    (1..)
+        .map(|index| path.with_file_name(format!("{} ({}){}", stem, index, extension)))
+        .find(|path| vfs::stat_link(path).is_err())
+        .unwrap()
+}
+
+fn renamed_path(path: &Path, name: &str) -> Result<PathBuf, Error> {
+    if name.is_empty() || name == "." || name == ".." || name.contains(['/', '\\']) {
+        return Err(Error::InvalidPath(format!("invalid name: {}", name)));
+    }
+    Ok(path.with_file_name(name))
+}
+
+fn path_in_folder(path: &Path, folder: &Path) -> Result<PathBuf, Error> {
+    if !vfs::is_dir(folder) {
+        return Err(Error::InvalidPath(format!(
+            "{} is not a folder",
+            folder.display()
+        )));
+    }
+    let name = path
+        .file_name()
+        .ok_or_else(|| Error::InvalidPath(path.display().to_string()))?;
+    Ok(folder.join(name))
+}
+
+// endregion
+
+// region: ---Trash
+
+/// # TrashItem
+/// {
+///     id: string,
+///     name: string,
+///     originalPath: string,
+///     deletedAt: number, // milliseconds since the unix epoch
+/// }
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrashItem {
+    pub id: String,
+    pub name: String,
+    pub original_path: String,
+    pub deleted_at: u64,
+}
+
+// the entry is kept in .trash/<id>/<name>, its item in .trash/<id>.json
+pub struct Trash {
+    folder: PathBuf,
+}
+
+impl Trash {
+    pub fn new(root: &Path) -> Trash {
+        Trash {
+            folder: root.join(TRASH_FOLDER_NAME),
+        }
+    }
+
+    fn of_workspace(workspace: &Workspace) -> Result<Trash, Error> {
+        let root = workspace
+            .root()
+            .ok_or_else(|| Error::InvalidPath(String::from("no workspace is open")))?;
+        Ok(Trash::new(&root))
+    }
+
+    // only the entries of the workspace are put in the trash, `get` refuses the others
+    pub fn put(&self, path: &Path) -> Result<TrashItem, Error> {
+        if !path.starts_with(self.root())
+            || path.starts_with(&self.folder)
+            || self.folder.starts_with(path)
+        {
+            return Err(Error::InvalidPath(format!(
+                "cannot move {} to the trash",
+                path.display()
+            )));
+        }
+        let name = path
+            .file_name()
+            .ok_or_else(|| Error::InvalidPath(path.display().to_string()))?
+            .to_string_lossy()
+            .to_string();
+        let deleted_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_millis() as u64);
+
+        // the id is unique in the trash
+        let id = (0..)
+            .map(|index| format!("{}-{}", deleted_at, index))
+            .find(|id| !vfs::exists(&self.folder.join(id)))
+            .unwrap();
+        let item_folder = self.folder.join(&id);
+        vfs::create_dir_all(&item_folder)?;
+
+        let item = TrashItem {
+            id,
+            name: name.clone(),
+            original_path: path.to_string_lossy().to_string(),
+            deleted_at,
+        };
+        let result = move_entry(path, &item_folder.join(&name), ConflictPolicy::Fail, self)
+            .and_then(|_| self.write_item(&item));
+        if let Err(err) = result {
+            let _ = vfs::remove_dir_all(&item_folder);
+            let _ = vfs::remove_file(&self.item_path(&item.id));
+            return Err(err);
+        }
+        Ok(item)
+    }
+
+    // most recently deleted first
+    pub fn list(&self) -> Result<Vec<TrashItem>, Error> {
+        let paths = match vfs::list(&self.folder) {
+            Ok(paths) => paths,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(err.into()),
+        };
+
+        let mut items: Vec<TrashItem> = paths
+            .into_iter()
+            .filter(|path| {
+                path.extension()
+                    .is_some_and(|extension| extension == "json")
+            })
+            .filter_map(|path| {
+                let id = path.file_stem()?.to_string_lossy().to_string();
+                self.get(&id).ok()
+            })
+            .collect();
+        items.sort_by_key(|item| std::cmp::Reverse(item.deleted_at));
+        Ok(items)
+    }
+
+    // the item of the id, refused when its entry or its original path is not where the trash
+    // puts them (an edited item would move entries from or to anywhere)
+    pub fn get(&self, id: &str) -> Result<TrashItem, Error> {
+        let invalid = || Error::InvalidPath(format!("{} is not an item of the trash", id));
+        if !is_trash_id(id) {
+            return Err(invalid());
+        }
+        let bytes = vfs::read(&self.item_path(id))?;
+        let item: TrashItem =
+            serde_json::from_slice(&bytes).map_err(|err| Error::Io(err.to_string()))?;
+
+        let mut name = Path::new(&item.name).components();
+        let is_name = matches!(
+            (name.next(), name.next()),
+            (Some(Component::Normal(_)), None)
+        );
+        let entry_path = self.entry_path(&item);
+        let original_path = Path::new(&item.original_path);
+        let is_in_root = original_path.starts_with(self.root())
+            && !original_path.starts_with(&self.folder)
+            && original_path
+                .components()
+                .all(|component| component != Component::ParentDir);
+        if item.id != id || !is_name || !entry_path.starts_with(&self.folder) || !is_in_root {
+            return Err(invalid());
+        }
+        Ok(item)
+    }
+
+    // the restored path, None when it was skipped
+    pub fn restore(&self, id: &str, conflict: ConflictPolicy) -> Result<Option<Placed>, Error> {
+        let item = self.get(id)?;
+        let original_path = PathBuf::from(&item.original_path);
+        if let Some(parent) = original_path.parent() {
+            vfs::create_dir_all(parent)?;
+        }
+
+        let restored = move_entry(&self.entry_path(&item), &original_path, conflict, self)?;
+        if restored.is_some() {
+            let _ = vfs::remove_dir_all(&self.folder.join(&item.id));
+            let _ = vfs::remove_file(&self.item_path(&item.id));
+        }
+        Ok(restored)
+    }
+
+    fn write_item(&self, item: &TrashItem) -> Result<(), Error> {
+        let json = serde_json::to_vec_pretty(item).map_err(|err| Error::Io(err.to_string()))?;
+        ignore_own_operation(&self.item_path(&item.id));
+        vfs::write(&self.item_path(&item.id), &json)?;
+        Ok(())
+    }
+
+    fn root(&self) -> &Path {
+        self.folder.parent().unwrap_or(&self.folder)
+    }
+
+    fn item_path(&self, id: &str) -> PathBuf {
+        self.folder.join(format!("{}.json", id))
+    }
+
+    fn entry_path(&self, item: &TrashItem) -> PathBuf {
+        self.folder.join(&item.id).join(&item.name)
+    }
+}
+
+// <deleted at>-<index>, as given by `put`
+fn is_trash_id(id: &str) -> bool {
+    let is_number = |part: &str| !part.is_empty() && part.bytes().all(|byte| byte.is_ascii_digit());
+    id.split_once('-')
+        .is_some_and(|(time, index)| is_number(time) && is_number(index))
+}
+
+// endregion
+
+// region: ---Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::fs::vfs::MemoryVfs;
+    use std::sync::Arc;
+
+    // trash of a workspace in memory with a file a.txt
+    fn trash_in_memory(root: &Path) -> Trash {
+        vfs::mount(root, Arc::new(MemoryVfs::new()));
+        vfs::create_dir_all(root).unwrap();
+        vfs::write(&root.join("a.txt"), b"a").unwrap();
+        Trash::new(root)
+    }
+
+    #[test]
+    fn conflicts_are_resolved_by_the_policy() {
+        let root = Path::new("/operations-conflict");
+        let trash = trash_in_memory(root);
+        let (from, to) = (root.join("b.txt"), root.join("a.txt"));
+        vfs::write(&from, b"b").unwrap();
+
+        assert!(matches!(
+            copy_entry(&from, &to, ConflictPolicy::Fail, &trash),
+            Err(Error::AlreadyExists(_))
+        ));
+        assert_eq!(
+            copy_entry(&from, &to, ConflictPolicy::Skip, &trash).unwrap(),
+            None
+        );
+        let kept = copy_entry(&from, &to, ConflictPolicy::KeepBoth, &trash).unwrap();
+        assert_eq!(kept.unwrap().path, root.join("a (1).txt"));
+
+        // the overwritten entry goes to the trash
+        let placed = move_entry(&from, &to, ConflictPolicy::Overwrite, &trash)
+            .unwrap()
+            .unwrap();
+        assert_eq!(placed.path, to);
+        assert_eq!(vfs::read(&to).unwrap(), b"b");
+        assert!(!vfs::exists(&from));
+        let overwritten = placed.overwritten.unwrap();
+        assert_eq!(vfs::read(&trash.entry_path(&overwritten)).unwrap(), b"a");
+        assert_eq!(trash.list().unwrap(), [overwritten]);
+        vfs::unmount(root);
+    }
+
+    #[test]
+    fn folders_are_copied_with_their_children() {
+        let root = Path::new("/operations-copy");
+        let trash = trash_in_memory(root);
+        create_entry(&root.join("docs"), true).unwrap();
+        vfs::write(&root.join("docs/x.md"), b"x").unwrap();
+
+        let copied = copy_entry(
+            &root.join("docs"),
+            &root.join("docs"),
+            ConflictPolicy::Fail,
+            &trash,
+        );
+        assert_eq!(copied.unwrap().unwrap().path, root.join("docs (1)"));
+        assert_eq!(vfs::read(&root.join("docs (1)/x.md")).unwrap(), b"x");
+        assert!(move_entry(
+            &root.join("docs"),
+            &root.join("docs/a"),
+            ConflictPolicy::Fail,
+            &trash
+        )
+        .is_err());
+        assert!(renamed_path(&root.join("docs"), "../a").is_err());
+        vfs::unmount(root);
+    }
+
+    #[test]
+    fn trash_ids_are_a_time_and_an_index() {
+        assert!(is_trash_id("1700000000000-0"));
+        assert!(is_trash_id("1-12"));
+        assert!(!is_trash_id("1700000000000"));
+        assert!(!is_trash_id("-0"));
+        assert!(!is_trash_id("1-"));
+        assert!(!is_trash_id("1-0-2"));
+        assert!(!is_trash_id("../1-0"));
+        assert!(!is_trash_id("1-0/../.."));
+    }
+
+    #[test]
+    fn entries_are_put_and_restored() {
+        let trash = trash_in_memory(Path::new("/trash-put"));
+        let item = trash.put(Path::new("/trash-put/a.txt")).unwrap();
+        assert!(!vfs::exists(Path::new("/trash-put/a.txt")));
+        assert_eq!(trash.list().unwrap().len(), 1);
+
+        let restored = trash.restore(&item.id, ConflictPolicy::Fail).unwrap();
+        assert_eq!(restored.unwrap().path, PathBuf::from("/trash-put/a.txt"));
+        assert_eq!(vfs::read(Path::new("/trash-put/a.txt")).unwrap(), b"a");
+        assert!(trash.list().unwrap().is_empty());
+        vfs::unmount(Path::new("/trash-put"));
+    }
+
+    #[test]
+    fn entries_outside_of_the_workspace_are_not_put() {
+        let trash = trash_in_memory(Path::new("/trash-outside"));
+        assert!(trash.put(Path::new("/elsewhere/a.txt")).is_err());
+        assert!(trash.put(Path::new("/trash-outside/.trash")).is_err());
+        assert!(trash.put(Path::new("/trash-outside")).is_err());
+        vfs::unmount(Path::new("/trash-outside"));
+    }
+
+    #[test]
+    fn edited_items_are_refused() {
+        let trash = trash_in_memory(Path::new("/trash-edited"));
+        let item = trash.put(Path::new("/trash-edited/a.txt")).unwrap();
+        let edits = [
+            TrashItem {
+                id: String::from("1-0"),
+                ..item.clone()
+            },
+            TrashItem {
+                name: String::from("../../a.txt"),
+                ..item.clone()
+            },
+            TrashItem {
+                original_path: String::from("/etc/a.txt"),
+                ..item.clone()
+            },
+            TrashItem {
+                original_path: String::from("/trash-edited/../etc/a.txt"),
+                ..item.clone()
+            },
+            TrashItem {
+                original_path: String::from("/trash-edited/.trash/a.txt"),
+                ..item.clone()
+            },
+        ];
+        for edited in edits {
+            let json = serde_json::to_vec(&edited).unwrap();
+            vfs::write(&trash.item_path(&item.id), &json).unwrap();
+            assert!(trash.get(&item.id).is_err(), "{:?}", edited);
+            assert!(trash.restore(&item.id, ConflictPolicy::Fail).is_err());
+        }
+        assert!(trash.get("../a").is_err());
+        vfs::unmount(Path::new("/trash-edited"));
+    }
+}
+
+// endregion
@@ -0,0 +1,152 @@
+use crate::api::fs::archive::EXTRACTED_FOLDER_NAME;
+use crate::api::fs::thumbnail::THUMBNAILS_FOLDER_NAME;
+use crate::api::fs::vfs;
+use crate::error::Error;
+use crate::APP_DATA_FOLDER_NAME;
+use std::ffi::OsString;
+use std::path::{Component, Path, PathBuf};
+use tauri::api::path::data_dir;
+
+// the caches of the app data folder read by the viewers, the rest of it is the state of the app
+const CACHE_FOLDER_NAMES: [&str; 2] = [THUMBNAILS_FOLDER_NAME, EXTRACTED_FOLDER_NAME];
+
+// region: ---Sandbox
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Access {
+    Read,
+    Write,
+}
+
+// the path given by the webview, relative paths are from the workspace root
+// (the path and the targets of its symlinks are in the workspace or in an app data cache)
+pub fn resolve(root: Option<&Path>, path: &Path, access: Access) -> Result<PathBuf, Error> {
+    resolve_in(root, app_data_folder().as_deref(), path, access)
+}
+
+fn resolve_in(
+    root: Option<&Path>,
+    app_data: Option<&Path>,
+    path: &Path,
+    access: Access,
+) -> Result<PathBuf, Error> {
+    if path
+        .components()
+        .any(|component| component == Component::ParentDir)
+    {
+        return Err(Error::OutsideWorkspace(path.display().to_string()));
+    }
+    let path = match (path.is_absolute(), root) {
+        (true, _) => path.to_path_buf(),
+        (false, Some(root)) => root.join(path),
+        (false, None) => return Err(Error::InvalidPath(String::from("no workspace is open"))),
+    };
+
+    if !allowed(root, app_data, &canonical(&path)?, access) {
+        return Err(Error::OutsideWorkspace(path.display().to_string()));
+    }
+    Ok(path)
+}
+
+// the canonical path can be read by the webview
+pub fn is_allowed(root: Option<&Path>, canonical_path: &Path) -> bool {
+    allowed(
+        root,
+        app_data_folder().as_deref(),
+        canonical_path,
+        Access::Read,
+    )
+}
+
+// the target of the symlink is outside of the workspace
+pub fn is_outside(root: &Path, path: &Path) -> bool {
+    vfs::canonicalize(path).is_ok_and(|target| !is_allowed(Some(root), &target))
+}
+
+// the app data folder is never written, even in a workspace containing it, and only its caches
+// are read
+fn allowed(
+    root: Option<&Path>,
+    app_data: Option<&Path>,
+    canonical_path: &Path,
+    access: Access,
+) -> bool {
+    match app_data.filter(|folder| canonical_path.starts_with(folder)) {
+        Some(app_data) => {
+            access == Access::Read
+                && CACHE_FOLDER_NAMES
+                    .iter()
+                    .any(|name| canonical_path.starts_with(app_data.join(name)))
+        }
+        None => root.is_some_and(|root| canonical_path.starts_with(root)),
+    }
+}
+
+fn app_data_folder() -> Option<PathBuf> {
+    data_dir()
+        .map(|folder| folder.join(APP_DATA_FOLDER_NAME))
+        .map(|folder| vfs::canonicalize(&folder).unwrap_or(folder))
+}
+
+// the canonical path of the deepest existing ancestor, followed by the missing names
+fn canonical(path: &Path) -> Result<PathBuf, Error> {
+    let mut missing: Vec<OsString> = Vec::new();
+    let mut existing = path;
+    loop {
+        if let Ok(canonical) = vfs::canonicalize(existing) {
+            return Ok(missing
+                .iter()
+                .rev()
+                .fold(canonical, |path, name| path.join(name)));
+        }
+        match (existing.parent(), existing.file_name()) {
+            (Some(parent), Some(name)) => {
+                missing.push(name.to_os_string());
+                existing = parent;
+            }
+            _ => return Err(Error::NotFound(path.display().to_string())),
+        }
+    }
+}
+
+// endregion
+
+// region: ---Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::fs::vfs::MemoryVfs;
+    use std::sync::Arc;
+
+    #[test]
+    fn the_state_of_the_app_is_never_written() {
+        // a workspace containing the app data folder
+        let root = Path::new("/sandbox-state");
+        let app_data = root.join(APP_DATA_FOLDER_NAME);
+        vfs::mount(root, Arc::new(MemoryVfs::new()));
+        vfs::create_dir_all(&app_data.join(THUMBNAILS_FOLDER_NAME)).unwrap();
+        vfs::write(&app_data.join("workspaces.json"), b"[]").unwrap();
+        let resolve =
+            |path: &str, access| resolve_in(Some(root), Some(&app_data), Path::new(path), access);
+
+        let recent = format!("{}/workspaces.json", APP_DATA_FOLDER_NAME);
+        assert!(matches!(
+            resolve(&recent, Access::Write),
+            Err(Error::OutsideWorkspace(_))
+        ));
+        assert!(resolve(&recent, Access::Read).is_err());
+
+        // the caches are only read
+        let thumbnail = format!("{}/{}/a.png", APP_DATA_FOLDER_NAME, THUMBNAILS_FOLDER_NAME);
+        assert!(resolve(&thumbnail, Access::Read).is_ok());
+        assert!(resolve(&thumbnail, Access::Write).is_err());
+
+        assert!(resolve("notes/a.md", Access::Write).is_ok());
+        assert!(resolve("/elsewhere/a.md", Access::Read).is_err());
+        assert!(resolve("notes/../../a.md", Access::Read).is_err());
+        vfs::unmount(root);
+    }
+}
+
+// endregion
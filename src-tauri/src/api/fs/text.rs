@@ -0,0 +1,343 @@
+use crate::api::fs::archive::{split_archive_path, Archive};
+use crate::api::fs::history::History;
+use crate::api::fs::vfs;
+use crate::api::fs::watcher::ignore_own_write;
+use crate::api::workspace::Workspace;
+use crate::error::Error;
+use std::path::{Path, PathBuf};
+use tauri::State;
+
+// region: ---Tauri Command
+
+// text of the file with \n line endings, binary files are refused unless `force`
+// (the files of a .zip archive are read from it)
+#[tauri::command]
+pub async fn read_text_file(
+    path: &str,
+    force: Option<bool>,
+    workspace: State<'_, Workspace>,
+) -> Result<TextFile, Error> {
+    let path = workspace.resolve(path)?;
+    let force = force.unwrap_or(false);
+    if !vfs::exists(&path) {
+        if let Some((archive_path, name)) = split_archive_path(&path) {
+            let bytes = Archive::open(&archive_path)?.read(&name, None)?;
+            return decode_text(&path, &bytes, force);
+        }
+    }
+    read_text(path, force)
+}
+
+// encoding and line ending of the file on disk when not given (utf-8 and \n for a new file)
+// (the text on disk and the saved text are kept in the history of the file)
+#[tauri::command]
+pub async fn write_text_file(
+    path: &str,
+    text: &str,
+    encoding: Option<Encoding>,
+    line_ending: Option<LineEnding>,
+    workspace: State<'_, Workspace>,
+    history: State<'_, History>,
+) -> Result<(), Error> {
+    let path = workspace.resolve_writable(path)?;
+    history.record(&path);
+    write_text(&path, text, encoding, line_ending)?;
+    history.record(&path);
+    Ok(())
+}
+
+// endregion
+
+// region: ---Encoding
+
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum Encoding {
+    Utf8,
+    Utf8Bom,
+    Utf16Le, // with byte order mark
+    Utf16Be, // with byte order mark
+    Latin1,
+}
+
+const UTF8_BOM: &[u8] = &[0xEF, 0xBB, 0xBF];
+const UTF16_LE_BOM: &[u8] = &[0xFF, 0xFE];
+const UTF16_BE_BOM: &[u8] = &[0xFE, 0xFF];
+
+// bytes checked for a NUL byte to detect binary files
+pub const BINARY_SNIFF_LEN: usize = 8000;
+
+impl Encoding {
+    // encoding of the bytes, None for binary data
+    pub fn detect(bytes: &[u8]) -> Option<Encoding> {
+        if bytes.starts_with(UTF8_BOM) {
+            Some(Encoding::Utf8Bom)
+        } else if bytes.starts_with(UTF16_LE_BOM) {
+            Some(Encoding::Utf16Le)
+        } else if bytes.starts_with(UTF16_BE_BOM) {
+            Some(Encoding::Utf16Be)
+        } else if bytes[..bytes.len().min(BINARY_SNIFF_LEN)].contains(&0) {
+            None
+        } else if std::str::from_utf8(bytes).is_ok() {
+            Some(Encoding::Utf8)
+        } else {
+            Some(Encoding::Latin1)
+        }
+    }
+
+    pub fn decode(&self, bytes: &[u8]) -> Result<String, Error> {
+        match self {
+            Encoding::Utf8 => {
+                String::from_utf8(bytes.to_vec()).map_err(|err| Error::Encoding(err.to_string()))
+            }
+            Encoding::Utf8Bom => {
+                Encoding::Utf8.decode(bytes.strip_prefix(UTF8_BOM).unwrap_or(bytes))
+            }
+            Encoding::Utf16Le | Encoding::Utf16Be => {
+                let bytes = bytes
+                    .strip_prefix(UTF16_LE_BOM)
+                    .or(bytes.strip_prefix(UTF16_BE_BOM))
+                    .unwrap_or(bytes);
+                let units: Vec<u16> = bytes
+                    .chunks_exact(2)
+                    .map(|unit| match self {
+                        Encoding::Utf16Le => u16::from_le_bytes([unit[0], unit[1]]),
+                        _ => u16::from_be_bytes([unit[0], unit[1]]),
+                    })
+                    .collect();
+                String::from_utf16(&units).map_err(|err| Error::Encoding(err.to_string()))
+            }
+            Encoding::Latin1 => Ok(bytes.iter().map(|byte| *byte as char).collect()),
+        }
+    }
+
+    pub fn encode(&self, text: &str) -> Result<Vec<u8>, Error> {
+        match self {
+            Encoding::Utf8 => Ok(text.as_bytes().to_vec()),
+            Encoding::Utf8Bom => Ok([UTF8_BOM, text.as_bytes()].concat()),
+            Encoding::Utf16Le => Ok(UTF16_LE_BOM
+                .iter()
+                .copied()
+                .chain(text.encode_utf16().flat_map(u16::to_le_bytes))
+                .collect()),
+            Encoding::Utf16Be => Ok(UTF16_BE_BOM
+                .iter()
+                .copied()
+                .chain(text.encode_utf16().flat_map(u16::to_be_bytes))
+                .collect()),
+            Encoding::Latin1 => text
+                .chars()
+                .map(|char| {
+                    u8::try_from(char as u32).map_err(|_| {
+                        Error::Encoding(format!("{:?} can not be written in Latin-1", char))
+                    })
+                })
+                .collect(),
+        }
+    }
+}
+
+// endregion
+
+// region: ---Line Ending
+
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum LineEnding {
+    Lf,
+    Crlf,
+}
+
+impl LineEnding {
+    // most used line ending of the text, \n if there is no line
+    pub fn detect(text: &str) -> LineEnding {
+        let lines = text.matches('\n').count();
+        let crlf_lines = text.matches("\r\n").count();
+        if crlf_lines > 0 && crlf_lines * 2 >= lines {
+            LineEnding::Crlf
+        } else {
+            LineEnding::Lf
+        }
+    }
+
+    pub fn apply(&self, text: &str) -> String {
+        match self {
+            LineEnding::Lf => text.to_string(),
+            LineEnding::Crlf => text.replace('\n', "\r\n"),
+        }
+    }
+}
+
+// endregion
+
+// region: ---Text File
+
+#[derive(Debug, serde::Serialize)]
+pub struct TextFile {
+    pub text: String, // with \n line endings
+    pub encoding: Encoding,
+    pub line_ending: LineEnding,
+    pub binary: bool, // only read with `force`
+}
+
+pub fn read_text<P: AsRef<Path>>(path: P, force: bool) -> Result<TextFile, Error> {
+    let path = path.as_ref();
+    decode_text(path, &vfs::read(path)?, force)
+}
+
+// the bytes of the file at the path
+pub fn decode_text(path: &Path, bytes: &[u8], force: bool) -> Result<TextFile, Error> {
+    let (text, encoding, binary) = match Encoding::detect(bytes) {
+        Some(encoding) => (encoding.decode(bytes)?, encoding, false),
+        None if force => (
+            String::from_utf8_lossy(bytes).to_string(),
+            Encoding::Utf8,
+            true,
+        ),
+        None => return Err(Error::BinaryFile(path.display().to_string())),
+    };
+
+    let line_ending = LineEnding::detect(&text);
+    Ok(TextFile {
+        text: text.replace("\r\n", "\n"),
+        encoding,
+        line_ending,
+        binary,
+    })
+}
+
+pub fn write_text<P: AsRef<Path>>(
+    path: P,
+    text: &str,
+    encoding: Option<Encoding>,
+    line_ending: Option<LineEnding>,
+) -> Result<(), Error> {
+    let path = path.as_ref();
+
+    // keep the format of the file on disk
+    let current = match (encoding, line_ending) {
+        (Some(_), Some(_)) => None,
+        _ => read_text(path, false).ok(),
+    };
+    let encoding = encoding
+        .or(current.as_ref().map(|file| file.encoding))
+        .unwrap_or(Encoding::Utf8);
+    let line_ending = line_ending
+        .or(current.as_ref().map(|file| file.line_ending))
+        .unwrap_or(LineEnding::Lf);
+
+    let text = line_ending.apply(&text.replace("\r\n", "\n"));
+    write_atomic(path, &encoding.encode(&text)?)
+}
+
+// write in a temporary file renamed over the file, a crash never leaves a truncated file
+pub fn write_atomic(path: &Path, bytes: &[u8]) -> Result<(), Error> {
+    let temp_path = stage_write(path, bytes)?;
+    let result = vfs::rename(&temp_path, path);
+    if result.is_err() {
+        let _ = vfs::remove_file(&temp_path);
+    }
+    Ok(result?)
+}
+
+// the bytes in a temporary file next to the file, to be renamed over it
+pub fn stage_write(path: &Path, bytes: &[u8]) -> Result<PathBuf, Error> {
+    let temp_path = temporary_path(path)?;
+    ignore_own_write(&temp_path);
+    ignore_own_write(path);
+
+    let result = vfs::write(&temp_path, bytes).and_then(|_| match vfs::exists(path) {
+        true => vfs::copy_permissions(path, &temp_path),
+        false => Ok(()),
+    });
+
+    if result.is_err() {
+        let _ = vfs::remove_file(&temp_path);
+    }
+    result?;
+    Ok(temp_path)
+}
+
+fn temporary_path(path: &Path) -> Result<PathBuf, Error> {
+    let name = path
+        .file_name()
+        .ok_or_else(|| Error::InvalidPath(path.display().to_string()))?;
+    Ok(path.with_file_name(format!(
+        ".{}.{}.tmp",
+        name.to_string_lossy(),
+        std::process::id()
+    )))
+}
+
+// endregion
+
+// region: ---Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encoding_is_detected_from_the_bom_then_the_bytes() {
+        assert_eq!(Encoding::detect(b"\xEF\xBB\xBFa"), Some(Encoding::Utf8Bom));
+        assert_eq!(Encoding::detect(b"\xFF\xFEa\0"), Some(Encoding::Utf16Le));
+        assert_eq!(Encoding::detect(b"\xFE\xFF\0a"), Some(Encoding::Utf16Be));
+        assert_eq!(Encoding::detect("é".as_bytes()), Some(Encoding::Utf8));
+        assert_eq!(Encoding::detect(b"caf\xE9"), Some(Encoding::Latin1));
+        assert_eq!(Encoding::detect(b""), Some(Encoding::Utf8));
+        assert_eq!(Encoding::detect(b"a\0b"), None);
+    }
+
+    #[test]
+    fn text_survives_every_encoding() {
+        let encodings = [
+            Encoding::Utf8,
+            Encoding::Utf8Bom,
+            Encoding::Utf16Le,
+            Encoding::Utf16Be,
+            Encoding::Latin1,
+        ];
+        for encoding in encodings {
+            let bytes = encoding.encode("café\n").unwrap();
+            assert_eq!(Encoding::detect(&bytes), Some(encoding));
+            assert_eq!(encoding.decode(&bytes).unwrap(), "café\n");
+        }
+    }
+
+    #[test]
+    fn latin1_refuses_other_characters() {
+        assert!(matches!(
+            Encoding::Latin1.encode("€"),
+            Err(Error::Encoding(_))
+        ));
+    }
+
+    #[test]
+    fn line_ending_is_the_most_used_one() {
+        assert_eq!(LineEnding::detect("a\r\nb\r\nc\n"), LineEnding::Crlf);
+        assert_eq!(LineEnding::detect("a\r\nb\n"), LineEnding::Crlf);
+        assert_eq!(LineEnding::detect("a\r\nb\nc\nd\n"), LineEnding::Lf);
+        assert_eq!(LineEnding::detect("no line"), LineEnding::Lf);
+        assert_eq!(LineEnding::Crlf.apply("a\nb\n"), "a\r\nb\r\n");
+    }
+
+    #[test]
+    fn decoded_text_has_lf_line_endings() {
+        let file = decode_text(Path::new("a.txt"), b"a\r\nb\r\n", false).unwrap();
+        assert_eq!(file.text, "a\nb\n");
+        assert_eq!(file.line_ending, LineEnding::Crlf);
+        assert!(!file.binary);
+    }
+
+    #[test]
+    fn binary_files_are_only_decoded_with_force() {
+        let path = Path::new("a.bin");
+        assert!(matches!(
+            decode_text(path, b"a\0b", false),
+            Err(Error::BinaryFile(_))
+        ));
+        let file = decode_text(path, b"a\0b", true).unwrap();
+        assert!(file.binary);
+        assert_eq!(file.text, "a\0b");
+    }
+}
+
+// endregion
@@ -0,0 +1,249 @@
+use crate::api::fs::kind::{FileKind, FileType};
+use crate::api::fs::text::write_atomic;
+use crate::api::fs::vfs;
+use crate::api::workspace::Workspace;
+use crate::error::Error;
+use crate::APP_DATA_FOLDER_NAME;
+use image::io::Reader as ImageReader;
+use image::{ImageError, ImageOutputFormat};
+use std::io::{BufReader, Cursor};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::api::path::data_dir;
+use tauri::State;
+use xxhash_rust::xxh3::Xxh3;
+
+pub(crate) const THUMBNAILS_FOLDER_NAME: &str = "thumbnails";
+
+// largest side of a thumbnail, in pixels
+const DEFAULT_THUMBNAIL_SIZE: u32 = 256;
+const MAX_THUMBNAIL_SIZE: u32 = 1024;
+
+// larger images are not decoded
+const MAX_IMAGE_FILE_SIZE: u64 = 64 * 1024 * 1024;
+
+// oldest thumbnails are removed past this
+const MAX_CACHED_THUMBNAILS: usize = 2000;
+
+// region: ---Tauri Command
+
+// kind and mime type from the content, with the dimensions of the images
+#[tauri::command]
+pub async fn get_file_type(path: &str, workspace: State<'_, Workspace>) -> Result<FileInfo, Error> {
+    let path = workspace.resolve(path)?;
+    let path = path.as_path();
+    if !vfs::is_file(path) {
+        return Err(Error::NotFound(path.display().to_string()));
+    }
+    let file_type = FileType::read(path);
+    let dimensions = match file_type.kind {
+        FileKind::Image => image_dimensions(path).ok(),
+        _ => None,
+    };
+    Ok(FileInfo {
+        file_type,
+        dimensions,
+    })
+}
+
+// png thumbnail of the image, generated once and cached in the app data folder
+#[tauri::command]
+pub async fn get_thumbnail(
+    path: &str,
+    size: Option<u32>,
+    workspace: State<'_, Workspace>,
+) -> Result<Thumbnail, Error> {
+    let size = size
+        .unwrap_or(DEFAULT_THUMBNAIL_SIZE)
+        .clamp(1, MAX_THUMBNAIL_SIZE);
+    thumbnail(&workspace.resolve(path)?, &thumbnails_folder(), size)
+}
+
+// endregion
+
+// region: ---Thumbnail
+
+#[derive(Debug, Clone, Copy, serde::Serialize)]
+pub struct Dimensions {
+    pub width: u32,
+    pub height: u32,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct FileInfo {
+    #[serde(flatten)]
+    pub file_type: FileType,
+    pub dimensions: Option<Dimensions>, // of the images with a decoder
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct Thumbnail {
+    pub path: String, // png file in the cache, in the scope of the asset protocol
+    pub width: u32,
+    pub height: u32,
+    pub image: Dimensions, // of the original image
+}
+
+// $DATA/Workspace/thumbnails is the scope of the asset protocol in tauri.conf.json
+fn thumbnails_folder() -> PathBuf {
+    data_dir()
+        .unwrap_or_default()
+        .join(APP_DATA_FOLDER_NAME)
+        .join(THUMBNAILS_FOLDER_NAME)
+}
+
+// read from the header of the image
+pub fn image_dimensions(path: &Path) -> Result<Dimensions, Error> {
+    let (width, height) = ImageReader::new(BufReader::new(vfs::open(path)?))
+        .with_guessed_format()?
+        .into_dimensions()
+        .map_err(|err| image_error(path, err))?;
+    Ok(Dimensions { width, height })
+}
+
+// the thumbnail fits in a square of `size` pixels, smaller images keep their size
+pub fn thumbnail(path: &Path, folder: &Path, size: u32) -> Result<Thumbnail, Error> {
+    let metadata = vfs::stat(path)?;
+    if FileType::read(path).kind != FileKind::Image {
+        return Err(Error::Unsupported(format!(
+            "{} is not an image",
+            path.display()
+        )));
+    }
+    if metadata.len > MAX_IMAGE_FILE_SIZE {
+        return Err(Error::Unsupported(format!(
+            "{} is too large",
+            path.display()
+        )));
+    }
+
+    // a new thumbnail is made when the image changes
+    let modified = metadata
+        .modified
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |duration| duration.as_nanos());
+    let mut hasher = Xxh3::new();
+    hasher.update(path.as_os_str().as_encoded_bytes());
+    hasher.update(&size.to_le_bytes());
+    hasher.update(&metadata.len.to_le_bytes());
+    hasher.update(&modified.to_le_bytes());
+    let thumbnail_path = folder.join(format!("{:016x}.png", hasher.digest()));
+
+    let image = image_dimensions(path)?;
+    if let Ok(Dimensions { width, height }) = image_dimensions(&thumbnail_path) {
+        return Ok(Thumbnail {
+            path: thumbnail_path.to_string_lossy().to_string(),
+            width,
+            height,
+            image,
+        });
+    }
+
+    let decoded = ImageReader::new(BufReader::new(vfs::open(path)?))
+        .with_guessed_format()?
+        .decode()
+        .map_err(|err| image_error(path, err))?;
+    let resized = match decoded.width() > size || decoded.height() > size {
+        true => decoded.thumbnail(size, size),
+        false => decoded,
+    };
+    let mut png = Cursor::new(Vec::new());
+    resized
+        .write_to(&mut png, ImageOutputFormat::Png)
+        .map_err(|err| image_error(path, err))?;
+
+    vfs::create_dir_all(folder)?;
+    write_atomic(&thumbnail_path, png.get_ref())?;
+    prune(folder);
+
+    Ok(Thumbnail {
+        path: thumbnail_path.to_string_lossy().to_string(),
+        width: resized.width(),
+        height: resized.height(),
+        image,
+    })
+}
+
+// the formats without a decoder (svg) are unsupported
+fn image_error(path: &Path, err: ImageError) -> Error {
+    let message = format!("{}: {}", path.display(), err);
+    match err {
+        ImageError::IoError(err) => err.into(),
+        ImageError::Unsupported(_) => Error::Unsupported(message),
+        _ => Error::Encoding(message),
+    }
+}
+
+// keep the most recent thumbnails
+fn prune(folder: &Path) {
+    let paths = match vfs::list(folder) {
+        Ok(paths) => paths,
+        Err(_) => return,
+    };
+    let mut thumbnails: Vec<(PathBuf, Option<SystemTime>)> = paths
+        .into_iter()
+        .map(|path| {
+            let modified = vfs::stat(&path).ok().and_then(|metadata| metadata.modified);
+            (path, modified)
+        })
+        .collect();
+    if thumbnails.len() <= MAX_CACHED_THUMBNAILS {
+        return;
+    }
+    thumbnails.sort_by_key(|(_, modified)| *modified);
+    let excess = thumbnails.len() - MAX_CACHED_THUMBNAILS;
+    for (path, _) in thumbnails.into_iter().take(excess) {
+        let _ = vfs::remove_file(&path);
+    }
+}
+
+// endregion
+
+// region: ---Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::fs::vfs::MemoryVfs;
+    use image::{DynamicImage, RgbImage};
+    use std::sync::Arc;
+
+    #[test]
+    fn thumbnails_are_resized_and_cached() {
+        let folder = Path::new("/thumbnail-cache");
+        vfs::mount(folder, Arc::new(MemoryVfs::new()));
+        vfs::create_dir_all(folder).unwrap();
+        let mut png = Cursor::new(Vec::new());
+        DynamicImage::ImageRgb8(RgbImage::new(600, 300))
+            .write_to(&mut png, ImageOutputFormat::Png)
+            .unwrap();
+        let path = folder.join("a.png");
+        vfs::write(&path, png.get_ref()).unwrap();
+
+        let cache = folder.join("cache");
+        let first = thumbnail(&path, &cache, 256).unwrap();
+        assert_eq!((first.width, first.height), (256, 128));
+        assert_eq!((first.image.width, first.image.height), (600, 300));
+        assert!(Path::new(&first.path).starts_with(&cache));
+
+        let cached = thumbnail(&path, &cache, 256).unwrap();
+        assert_eq!(cached.path, first.path);
+        let other_size = thumbnail(&path, &cache, 64).unwrap();
+        assert_ne!(other_size.path, first.path);
+        vfs::unmount(folder);
+    }
+
+    #[test]
+    fn text_files_have_no_thumbnail() {
+        let folder = Path::new("/thumbnail-text");
+        vfs::mount(folder, Arc::new(MemoryVfs::new()));
+        vfs::create_dir_all(folder).unwrap();
+        let path = folder.join("a.txt");
+        vfs::write(&path, b"text").unwrap();
+        let result = thumbnail(&path, &folder.join("cache"), 256);
+        assert!(matches!(result, Err(Error::Unsupported(_))));
+        vfs::unmount(folder);
+    }
+}
+
+// endregion
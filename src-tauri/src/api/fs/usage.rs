@@ -0,0 +1,614 @@
+use crate::api::fs::operations::TRASH_FOLDER_NAME;
+use crate::api::fs::vfs;
+use crate::api::workspace::{Workspace, WorkspaceListener};
+use crate::error::Error;
+use crate::event::FsChange;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use tauri::State;
+
+const DEFAULT_DEPTH: usize = 1;
+
+// region: ---Tauri Command
+
+// cumulative size and file counts of the folder and of its subfolders down to `depth`,
+// the workspace root when no path is given
+// (the folders of the workspace are read from the cache, the others are measured now)
+#[tauri::command]
+pub async fn get_disk_usage(
+    path: Option<&str>,
+    depth: Option<usize>,
+    workspace: State<'_, Workspace>,
+    disk_usage: State<'_, DiskUsage>,
+) -> Result<FolderUsage, Error> {
+    let path = match path {
+        Some(path) => workspace.resolve(path)?,
+        None => workspace
+            .root()
+            .ok_or_else(|| Error::InvalidPath(String::from("no workspace is open")))?,
+    };
+    if !vfs::is_dir(&path) {
+        return Err(Error::InvalidPath(format!(
+            "{} is not a folder",
+            path.display()
+        )));
+    }
+    let depth = depth.unwrap_or(DEFAULT_DEPTH);
+
+    if let Some(usage) = disk_usage.get(&path, depth) {
+        return Ok(usage);
+    }
+    let mut tree = UsageTree::new(&path);
+    measure(&path, &path, |entries| {
+        tree.apply(entries);
+        true
+    });
+    tree.complete = true;
+    tree.usage("", depth)
+        .ok_or_else(|| Error::NotFound(path.display().to_string()))
+}
+
+// endregion
+
+// region: ---Folder Usage
+
+// cumulative size and counts of a tree of files
+#[derive(Debug, Clone, Copy, Default, PartialEq, serde::Serialize)]
+pub struct Usage {
+    pub size: u64,      // bytes of the files
+    pub files: usize,   // files in the folder and its subfolders
+    pub folders: usize, // subfolders at any depth
+}
+
+impl Usage {
+    pub fn add(&mut self, other: Usage) {
+        self.size += other.size;
+        self.files += other.files;
+        self.folders += other.folders;
+    }
+}
+
+/// # FolderUsage
+/// {
+///     path: string,
+///     size: number,
+///     files: number,
+///     folders: number,
+///     types: [{ extension, size, files }, ...], // largest first, "" for the files without one
+///     children: [FolderUsage, ...],             // largest first, empty past the depth
+///     complete: bool,                           // false while the workspace is being measured
+/// }
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct FolderUsage {
+    pub path: String,
+    #[serde(flatten)]
+    pub usage: Usage,
+    pub types: Vec<TypeUsage>,
+    pub children: Vec<FolderUsage>,
+    pub complete: bool,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct TypeUsage {
+    pub extension: String, // lowercase, without the dot
+    pub size: u64,
+    pub files: usize,
+}
+
+// endregion
+
+// region: ---Disk Usage
+
+// usage of the folders of the open workspace, kept up to date by the workspace watcher
+#[derive(Clone, Default)]
+pub struct DiskUsage {
+    tree: Arc<Mutex<Option<UsageTree>>>,
+    generation: Arc<AtomicUsize>, // a measure stops when the workspace is opened again
+}
+
+impl DiskUsage {
+    fn open(&self, root: &Path) {
+        let generation = self.generation.fetch_add(1, Ordering::Relaxed) + 1;
+        *self.tree.lock().unwrap() = Some(UsageTree::new(root));
+
+        // the workspace is measured on a background thread, the tree fills up meanwhile
+        let disk_usage = self.clone();
+        let root = root.to_path_buf();
+        thread::spawn(move || {
+            let is_current = || disk_usage.generation.load(Ordering::Relaxed) == generation;
+            measure(&root, &root, |entries| {
+                if !is_current() {
+                    return false;
+                }
+                if let Some(tree) = disk_usage.tree.lock().unwrap().as_mut() {
+                    tree.apply(entries);
+                }
+                true
+            });
+            if is_current() {
+                if let Some(tree) = disk_usage.tree.lock().unwrap().as_mut() {
+                    tree.complete = true;
+                }
+            }
+        });
+    }
+
+    // None when the folder is not in the measured workspace
+    fn get(&self, path: &Path, depth: usize) -> Option<FolderUsage> {
+        let tree = self.tree.lock().unwrap();
+        let tree = tree.as_ref()?;
+        tree.usage(&tree.relative(path)?, depth)
+    }
+
+    fn update(&self, root: &Path, changes: &[FsChange]) {
+        let mut tree = self.tree.lock().unwrap();
+        let tree = match tree.as_mut() {
+            Some(tree) if tree.root == root => tree,
+            _ => return,
+        };
+
+        for change in changes {
+            let (removed, added) = match change {
+                FsChange::Created { path } | FsChange::Modified { path } => (None, Some(path)),
+                FsChange::Removed { path } => (Some(path), None),
+                FsChange::Renamed { from, to } => (Some(from), Some(to)),
+            };
+            if let Some(relative) = removed.and_then(|path| tree.relative(Path::new(path))) {
+                tree.remove(&relative);
+            }
+            if let Some(path) = added {
+                tree.refresh(Path::new(path));
+            }
+        }
+    }
+}
+
+impl WorkspaceListener for DiskUsage {
+    fn opened(&self, root: &Path) {
+        self.open(root);
+    }
+
+    fn changed(&self, root: &Path, changes: &[FsChange]) {
+        self.update(root, changes);
+    }
+}
+
+enum Measured {
+    Folder(PathBuf),
+    File(PathBuf, u64),
+}
+
+// the path and its descendants, given folder by folder until `on_entries` returns false
+// (the symlinks are not followed and the trash of the workspace is left out)
+fn measure<F: FnMut(Vec<Measured>) -> bool>(root: &Path, path: &Path, mut on_entries: F) {
+    let trash = root.join(TRASH_FOLDER_NAME);
+    let mut folders = vec![path.to_path_buf()];
+    let mut entries = Vec::new();
+    match vfs::stat_link(path) {
+        Ok(stat) if stat.is_dir && path != trash => entries.push(Measured::Folder(path.into())),
+        Ok(stat) if stat.is_file() => {
+            on_entries(vec![Measured::File(path.into(), stat.len)]);
+            return;
+        }
+        _ => return,
+    }
+
+    while let Some(folder) = folders.pop() {
+        for child in vfs::list(&folder).unwrap_or_default() {
+            match vfs::stat_link(&child) {
+                Ok(stat) if stat.is_dir && child != trash => {
+                    entries.push(Measured::Folder(child.clone()));
+                    folders.push(child);
+                }
+                Ok(stat) if stat.is_file() => entries.push(Measured::File(child, stat.len)),
+                _ => {}
+            }
+        }
+        if !on_entries(std::mem::take(&mut entries)) {
+            return;
+        }
+    }
+}
+
+// endregion
+
+// region: ---Usage Tree
+
+#[derive(Default)]
+struct Totals {
+    usage: Usage,
+    types: HashMap<String, Usage>, // by extension, without the folders
+}
+
+// the measured files and the cumulative totals of the folders, by relative path
+struct UsageTree {
+    root: PathBuf,
+    files: HashMap<String, (u64, String)>, // size and extension
+    folders: HashMap<String, Totals>,      // "" for the root
+    complete: bool,
+}
+
+impl UsageTree {
+    fn new(root: &Path) -> UsageTree {
+        UsageTree {
+            root: root.to_path_buf(),
+            files: HashMap::new(),
+            folders: HashMap::from([(String::new(), Totals::default())]),
+            complete: false,
+        }
+    }
+
+    // with / separators, None outside of the root and in its trash
+    fn relative(&self, path: &Path) -> Option<String> {
+        if path.starts_with(self.root.join(TRASH_FOLDER_NAME)) {
+            return None;
+        }
+        path.strip_prefix(&self.root)
+            .ok()
+            .map(|relative| relative.to_string_lossy().replace('\\', "/"))
+    }
+
+    fn apply(&mut self, entries: Vec<Measured>) {
+        for entry in entries {
+            match entry {
+                Measured::Folder(path) => {
+                    if let Some(relative) = self.relative(&path) {
+                        self.add_folder(&relative);
+                    }
+                }
+                Measured::File(path, size) => {
+                    if let Some(relative) = self.relative(&path) {
+                        let extension = path
+                            .extension()
+                            .map(|extension| extension.to_string_lossy().to_lowercase())
+                            .unwrap_or_default();
+                        self.add_file(&relative, size, extension);
+                    }
+                }
+            }
+        }
+    }
+
+    // the path is measured again, a modified folder keeps its entries
+    fn refresh(&mut self, path: &Path) {
+        let relative = match self.relative(path) {
+            Some(relative) => relative,
+            None => return,
+        };
+        if self.folders.contains_key(&relative) && vfs::is_dir(path) {
+            return;
+        }
+        self.remove(&relative);
+        let root = self.root.clone();
+        measure(&root, path, |entries| {
+            self.apply(entries);
+            true
+        });
+    }
+
+    fn add_folder(&mut self, relative: &str) {
+        if self.folders.contains_key(relative) {
+            return;
+        }
+        if let Some(parent) = parent(relative) {
+            self.add_folder(parent);
+        }
+        for ancestor in ancestors(relative) {
+            if let Some(totals) = self.folders.get_mut(ancestor) {
+                totals.usage.folders += 1;
+            }
+        }
+        self.folders.insert(relative.to_string(), Totals::default());
+    }
+
+    fn add_file(&mut self, relative: &str, size: u64, extension: String) {
+        self.remove_file(relative);
+        if let Some(parent) = parent(relative) {
+            self.add_folder(parent);
+        }
+        let file = Usage {
+            size,
+            files: 1,
+            folders: 0,
+        };
+        for ancestor in ancestors(relative) {
+            if let Some(totals) = self.folders.get_mut(ancestor) {
+                totals.usage.add(file);
+                totals.types.entry(extension.clone()).or_default().add(file);
+            }
+        }
+        self.files.insert(relative.to_string(), (size, extension));
+    }
+
+    // the file or the folder with its descendants
+    fn remove(&mut self, relative: &str) {
+        if self.remove_file(relative) || relative.is_empty() {
+            return;
+        }
+        let prefix = format!("{}/", relative);
+        let files: Vec<String> = self
+            .files
+            .keys()
+            .filter(|path| path.starts_with(&prefix))
+            .cloned()
+            .collect();
+        for file in files {
+            self.remove_file(&file);
+        }
+
+        // the deepest folders first, their ancestors are counted down one by one
+        let mut folders: Vec<String> = self
+            .folders
+            .keys()
+            .filter(|path| *path == relative || path.starts_with(&prefix))
+            .cloned()
+            .collect();
+        folders.sort_by_key(|path| std::cmp::Reverse(path.len()));
+        for folder in folders {
+            self.folders.remove(&folder);
+            for ancestor in ancestors(&folder) {
+                if let Some(totals) = self.folders.get_mut(ancestor) {
+                    totals.usage.folders = totals.usage.folders.saturating_sub(1);
+                }
+            }
+        }
+    }
+
+    fn remove_file(&mut self, relative: &str) -> bool {
+        let (size, extension) = match self.files.remove(relative) {
+            Some(file) => file,
+            None => return false,
+        };
+        for ancestor in ancestors(relative) {
+            if let Some(totals) = self.folders.get_mut(ancestor) {
+                totals.usage.size = totals.usage.size.saturating_sub(size);
+                totals.usage.files = totals.usage.files.saturating_sub(1);
+                if let Some(usage) = totals.types.get_mut(&extension) {
+                    usage.size = usage.size.saturating_sub(size);
+                    usage.files = usage.files.saturating_sub(1);
+                    if usage.files == 0 {
+                        totals.types.remove(&extension);
+                    }
+                }
+            }
+        }
+        true
+    }
+
+    fn usage(&self, relative: &str, depth: usize) -> Option<FolderUsage> {
+        self.folders.get(relative)?;
+
+        let mut children: HashMap<&str, Vec<&str>> = HashMap::new();
+        if depth > 0 {
+            for folder in self.folders.keys() {
+                if let Some(parent) = parent(folder) {
+                    children.entry(parent).or_default().push(folder);
+                }
+            }
+        }
+        Some(self.folder_usage(relative, depth, &children))
+    }
+
+    fn folder_usage(
+        &self,
+        relative: &str,
+        depth: usize,
+        children: &HashMap<&str, Vec<&str>>,
+    ) -> FolderUsage {
+        let totals = &self.folders[relative];
+
+        let mut types: Vec<TypeUsage> = totals
+            .types
+            .iter()
+            .map(|(extension, usage)| TypeUsage {
+                extension: extension.clone(),
+                size: usage.size,
+                files: usage.files,
+            })
+            .collect();
+        types.sort_by(|left, right| {
+            right
+                .size
+                .cmp(&left.size)
+                .then_with(|| left.extension.cmp(&right.extension))
+        });
+
+        let mut subfolders: Vec<FolderUsage> = match depth {
+            0 => Vec::new(),
+            _ => children
+                .get(relative)
+                .map(|folders| {
+                    folders
+                        .iter()
+                        .map(|folder| self.folder_usage(folder, depth - 1, children))
+                        .collect()
+                })
+                .unwrap_or_default(),
+        };
+        subfolders.sort_by(|left, right| {
+            right
+                .usage
+                .size
+                .cmp(&left.usage.size)
+                .then_with(|| left.path.cmp(&right.path))
+        });
+
+        FolderUsage {
+            path: match relative.is_empty() {
+                true => self.root.to_string_lossy().to_string(),
+                false => self.root.join(relative).to_string_lossy().to_string(),
+            },
+            usage: totals.usage,
+            types,
+            children: subfolders,
+            complete: self.complete,
+        }
+    }
+}
+
+// None for the root
+fn parent(relative: &str) -> Option<&str> {
+    match relative.rfind('/') {
+        Some(slash) => Some(&relative[..slash]),
+        None if relative.is_empty() => None,
+        None => Some(""),
+    }
+}
+
+// the folders containing the path, up to the root
+fn ancestors(relative: &str) -> Vec<&str> {
+    let mut ancestors = Vec::new();
+    let mut path = relative;
+    while let Some(parent) = parent(path) {
+        ancestors.push(parent);
+        path = parent;
+    }
+    ancestors
+}
+
+// endregion
+
+// region: ---Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::fs::vfs::MemoryVfs;
+
+    fn usage(size: u64, files: usize, folders: usize) -> Usage {
+        Usage {
+            size,
+            files,
+            folders,
+        }
+    }
+
+    // a tree measured from the files of a memory folder
+    fn measured_tree(root: &Path, files: &[(&str, usize)]) -> UsageTree {
+        vfs::mount(root, Arc::new(MemoryVfs::new()));
+        for (path, size) in files {
+            let path = root.join(path);
+            vfs::create_dir_all(path.parent().unwrap()).unwrap();
+            vfs::write(&path, &vec![0; *size]).unwrap();
+        }
+        let mut tree = UsageTree::new(root);
+        measure(root, root, |entries| {
+            tree.apply(entries);
+            true
+        });
+        tree
+    }
+
+    #[test]
+    fn folders_add_up_their_descendants() {
+        let mut tree = UsageTree::new(Path::new("/root"));
+        tree.add_file("a.md", 10, String::from("md"));
+        tree.add_file("b/c.md", 20, String::from("md"));
+        tree.add_file("b/d/e.png", 5, String::from("png"));
+        tree.add_folder("f");
+
+        assert_eq!(tree.folders[""].usage, usage(35, 3, 3));
+        assert_eq!(tree.folders["b"].usage, usage(25, 2, 1));
+        assert_eq!(tree.folders["b/d"].usage, usage(5, 1, 0));
+        assert_eq!(tree.folders[""].types["md"], usage(30, 2, 0));
+
+        // a file measured again replaces its size
+        tree.add_file("b/c.md", 2, String::from("md"));
+        assert_eq!(tree.folders[""].usage, usage(17, 3, 3));
+    }
+
+    #[test]
+    fn removed_folders_are_taken_out_of_their_ancestors() {
+        let mut tree = UsageTree::new(Path::new("/root"));
+        tree.add_file("a.md", 10, String::from("md"));
+        tree.add_file("b/c.md", 20, String::from("md"));
+        tree.add_file("b/d/e.png", 5, String::from("png"));
+
+        tree.remove("b");
+        assert_eq!(tree.folders[""].usage, usage(10, 1, 0));
+        assert!(!tree.folders[""].types.contains_key("png"));
+        assert!(!tree.folders.contains_key("b/d"));
+
+        // the root is never removed
+        tree.remove("");
+        assert!(tree.folders.contains_key(""));
+    }
+
+    #[test]
+    fn usage_is_sorted_and_cut_at_the_depth() {
+        let mut tree = UsageTree::new(Path::new("/root"));
+        tree.add_file("small/a.txt", 1, String::from("txt"));
+        tree.add_file("large/b.txt", 100, String::from("txt"));
+        tree.add_file("large/deep/c", 50, String::new());
+
+        let root = tree.usage("", 1).unwrap();
+        assert_eq!(root.path, "/root");
+        let children: Vec<&str> = root
+            .children
+            .iter()
+            .map(|child| child.path.as_str())
+            .collect();
+        assert_eq!(children, ["/root/large", "/root/small"]);
+        assert!(root.children[0].children.is_empty());
+        let types: Vec<&str> = root
+            .types
+            .iter()
+            .map(|usage| usage.extension.as_str())
+            .collect();
+        assert_eq!(types, ["txt", ""]);
+
+        assert_eq!(tree.usage("large", 1).unwrap().children.len(), 1);
+        assert!(tree.usage("missing", 1).is_none());
+    }
+
+    #[test]
+    fn the_trash_is_not_measured() {
+        let root = Path::new("/usage-trash");
+        let tree = measured_tree(
+            root,
+            &[
+                ("a.md", 3),
+                ("b/c.MD", 4),
+                (&format!("{}/d.md", TRASH_FOLDER_NAME), 100),
+            ],
+        );
+        assert_eq!(tree.folders[""].usage, usage(7, 2, 1));
+        assert_eq!(tree.folders[""].types["md"], usage(7, 2, 0));
+        assert!(tree.relative(&root.join(TRASH_FOLDER_NAME)).is_none());
+        vfs::unmount(root);
+    }
+
+    #[test]
+    fn changes_of_the_workspace_update_the_tree() {
+        let root = Path::new("/usage-changes");
+        let tree = measured_tree(root, &[("a.md", 3), ("b/c.md", 4)]);
+        let disk_usage = DiskUsage::default();
+        *disk_usage.tree.lock().unwrap() = Some(tree);
+
+        vfs::write(&root.join("b/c.md"), &[0; 10]).unwrap();
+        vfs::rename(&root.join("a.md"), &root.join("b/a.md")).unwrap();
+        let path = |relative: &str| root.join(relative).to_string_lossy().to_string();
+        disk_usage.update(
+            root,
+            &[
+                FsChange::Modified {
+                    path: path("b/c.md"),
+                },
+                FsChange::Renamed {
+                    from: path("a.md"),
+                    to: path("b/a.md"),
+                },
+            ],
+        );
+
+        let usage_of = |relative: &str| disk_usage.get(&root.join(relative), 0).unwrap().usage;
+        assert_eq!(usage_of(""), usage(13, 2, 1));
+        assert_eq!(usage_of("b"), usage(13, 2, 0));
+        vfs::unmount(root);
+    }
+}
+
+// endregion
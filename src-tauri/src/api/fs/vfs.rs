@@ -0,0 +1,779 @@
+use crate::api::fs::watcher::Watcher;
+use crate::error::Error;
+use crate::event::FsChange;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, Cursor, ErrorKind, Read, Seek, Write};
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock, RwLock, Weak};
+use std::time::SystemTime;
+
+// region: ---Vfs
+
+// metadata of an entry, the times are None when the file system has none
+#[derive(Debug, Clone)]
+pub struct Stat {
+    pub is_dir: bool,
+    pub is_symlink: bool, // only from `stat_link`
+    pub len: u64,
+    pub created: Option<SystemTime>,
+    pub modified: Option<SystemTime>,
+    pub accessed: Option<SystemTime>,
+    pub readonly: bool,
+    pub hidden: bool, // hidden attribute of the file system, the dot files are not included
+}
+
+impl Stat {
+    pub fn is_file(&self) -> bool {
+        !self.is_dir && !self.is_symlink
+    }
+}
+
+pub trait VfsFile: Read + Seek + Send {}
+
+impl<T: Read + Seek + Send> VfsFile for T {}
+
+pub type OnChange = Box<dyn Fn(Vec<FsChange>) + Send>;
+
+// the changes are watched until it is dropped
+pub type WatchGuard = Box<dyn Send>;
+
+// a file system mounted at a folder, it is given the absolute paths under it
+pub trait Vfs: Send + Sync {
+    // paths of the children of the folder, in no order
+    fn list(&self, folder: &Path) -> io::Result<Vec<PathBuf>>;
+
+    // of the target of a symlink
+    fn stat(&self, path: &Path) -> io::Result<Stat>;
+
+    // of the symlink itself
+    fn stat_link(&self, path: &Path) -> io::Result<Stat>;
+
+    fn read_link(&self, path: &Path) -> io::Result<PathBuf>;
+
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf>;
+
+    fn open(&self, path: &Path) -> io::Result<Box<dyn VfsFile>>;
+
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        let mut bytes = Vec::new();
+        self.open(path)?.read_to_end(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    // creates or truncates the file, `write_atomic` replaces it safely
+    fn write(&self, path: &Path, bytes: &[u8]) -> io::Result<()>;
+
+    // creates the file when it does not exist, the bytes are on disk when it returns
+    fn append(&self, path: &Path, bytes: &[u8]) -> io::Result<()>;
+
+    // fails when the entry exists
+    fn create_file(&self, path: &Path) -> io::Result<()>;
+
+    fn create_dir(&self, path: &Path) -> io::Result<()>;
+
+    fn create_dir_all(&self, path: &Path) -> io::Result<()>;
+
+    fn copy_file(&self, from: &Path, to: &Path) -> io::Result<()>;
+
+    fn symlink(&self, target: &Path, path: &Path) -> io::Result<()>;
+
+    // a file at `to` is replaced
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()>;
+
+    fn remove_file(&self, path: &Path) -> io::Result<()>;
+
+    fn remove_dir_all(&self, path: &Path) -> io::Result<()>;
+
+    fn set_readonly(&self, path: &Path, readonly: bool) -> io::Result<()>;
+
+    // `to` gets the permissions of `from`
+    fn copy_permissions(&self, from: &Path, to: &Path) -> io::Result<()>;
+
+    // the changes of the folder and its subfolders
+    fn watch(&self, root: &Path, on_change: OnChange) -> Result<WatchGuard, Error>;
+}
+
+// endregion
+
+// region: ---Mounts
+
+// (folder, file system) of the mounted file systems, the other paths are on the local disk
+static MOUNTS: RwLock<Vec<(PathBuf, Arc<dyn Vfs>)>> = RwLock::new(Vec::new());
+
+static LOCAL: OnceLock<Arc<dyn Vfs>> = OnceLock::new();
+
+// the paths in the folder are read and written in the file system, until it is unmounted
+pub fn mount(folder: &Path, vfs: Arc<dyn Vfs>) {
+    let mut mounts = MOUNTS.write().unwrap();
+    mounts.retain(|(mounted, _)| mounted != folder);
+    mounts.push((folder.to_path_buf(), vfs));
+}
+
+pub fn unmount(folder: &Path) {
+    MOUNTS
+        .write()
+        .unwrap()
+        .retain(|(mounted, _)| mounted != folder);
+}
+
+// the file system of the deepest mount containing the path
+pub fn resolve(path: &Path) -> Arc<dyn Vfs> {
+    let mounts = MOUNTS.read().unwrap();
+    mounts
+        .iter()
+        .filter(|(folder, _)| path.starts_with(folder))
+        .max_by_key(|(folder, _)| folder.components().count())
+        .map(|(_, vfs)| vfs.clone())
+        .unwrap_or_else(|| LOCAL.get_or_init(|| Arc::new(LocalVfs)).clone())
+}
+
+pub fn list(folder: &Path) -> io::Result<Vec<PathBuf>> {
+    resolve(folder).list(folder)
+}
+
+pub fn stat(path: &Path) -> io::Result<Stat> {
+    resolve(path).stat(path)
+}
+
+pub fn stat_link(path: &Path) -> io::Result<Stat> {
+    resolve(path).stat_link(path)
+}
+
+pub fn read_link(path: &Path) -> io::Result<PathBuf> {
+    resolve(path).read_link(path)
+}
+
+pub fn canonicalize(path: &Path) -> io::Result<PathBuf> {
+    resolve(path).canonicalize(path)
+}
+
+pub fn exists(path: &Path) -> bool {
+    stat(path).is_ok()
+}
+
+pub fn is_dir(path: &Path) -> bool {
+    stat(path).map_or(false, |stat| stat.is_dir)
+}
+
+pub fn is_file(path: &Path) -> bool {
+    stat(path).map_or(false, |stat| !stat.is_dir)
+}
+
+pub fn open(path: &Path) -> io::Result<Box<dyn VfsFile>> {
+    resolve(path).open(path)
+}
+
+pub fn read(path: &Path) -> io::Result<Vec<u8>> {
+    resolve(path).read(path)
+}
+
+pub fn write(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    resolve(path).write(path, bytes)
+}
+
+pub fn append(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    resolve(path).append(path, bytes)
+}
+
+pub fn create_file(path: &Path) -> io::Result<()> {
+    resolve(path).create_file(path)
+}
+
+pub fn create_dir(path: &Path) -> io::Result<()> {
+    resolve(path).create_dir(path)
+}
+
+pub fn create_dir_all(path: &Path) -> io::Result<()> {
+    resolve(path).create_dir_all(path)
+}
+
+// the bytes go through the app between two file systems
+pub fn copy_file(from: &Path, to: &Path) -> io::Result<()> {
+    let (from_vfs, to_vfs) = (resolve(from), resolve(to));
+    match Arc::ptr_eq(&from_vfs, &to_vfs) {
+        true => from_vfs.copy_file(from, to),
+        false => to_vfs.write(to, &from_vfs.read(from)?),
+    }
+}
+
+pub fn symlink(target: &Path, path: &Path) -> io::Result<()> {
+    resolve(path).symlink(target, path)
+}
+
+// fails between two file systems, like between two disks
+pub fn rename(from: &Path, to: &Path) -> io::Result<()> {
+    let (from_vfs, to_vfs) = (resolve(from), resolve(to));
+    if !Arc::ptr_eq(&from_vfs, &to_vfs) {
+        return Err(io::Error::other(format!(
+            "{} and {} are on different file systems",
+            from.display(),
+            to.display()
+        )));
+    }
+    from_vfs.rename(from, to)
+}
+
+pub fn remove_file(path: &Path) -> io::Result<()> {
+    resolve(path).remove_file(path)
+}
+
+pub fn remove_dir_all(path: &Path) -> io::Result<()> {
+    resolve(path).remove_dir_all(path)
+}
+
+pub fn set_readonly(path: &Path, readonly: bool) -> io::Result<()> {
+    resolve(path).set_readonly(path, readonly)
+}
+
+pub fn copy_permissions(from: &Path, to: &Path) -> io::Result<()> {
+    resolve(to).copy_permissions(from, to)
+}
+
+pub fn watch(root: &Path, on_change: OnChange) -> Result<WatchGuard, Error> {
+    resolve(root).watch(root, on_change)
+}
+
+// endregion
+
+// region: ---Local Vfs
+
+// the disk, through std::fs and the notify watcher
+pub struct LocalVfs;
+
+impl LocalVfs {
+    fn to_stat(metadata: fs::Metadata) -> Stat {
+        Stat {
+            is_dir: metadata.is_dir(),
+            is_symlink: metadata.file_type().is_symlink(),
+            len: metadata.len(),
+            created: metadata.created().ok(),
+            modified: metadata.modified().ok(),
+            accessed: metadata.accessed().ok(),
+            readonly: metadata.permissions().readonly(),
+            hidden: has_hidden_attribute(&metadata),
+        }
+    }
+}
+
+#[cfg(windows)]
+fn has_hidden_attribute(metadata: &fs::Metadata) -> bool {
+    use std::os::windows::fs::MetadataExt;
+    const FILE_ATTRIBUTE_HIDDEN: u32 = 0x2;
+    metadata.file_attributes() & FILE_ATTRIBUTE_HIDDEN != 0
+}
+
+#[cfg(not(windows))]
+fn has_hidden_attribute(_metadata: &fs::Metadata) -> bool {
+    false
+}
+
+impl Vfs for LocalVfs {
+    fn list(&self, folder: &Path) -> io::Result<Vec<PathBuf>> {
+        Ok(fs::read_dir(folder)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .collect())
+    }
+
+    fn stat(&self, path: &Path) -> io::Result<Stat> {
+        fs::metadata(path).map(LocalVfs::to_stat)
+    }
+
+    fn stat_link(&self, path: &Path) -> io::Result<Stat> {
+        fs::symlink_metadata(path).map(LocalVfs::to_stat)
+    }
+
+    fn read_link(&self, path: &Path) -> io::Result<PathBuf> {
+        fs::read_link(path)
+    }
+
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        fs::canonicalize(path)
+    }
+
+    fn open(&self, path: &Path) -> io::Result<Box<dyn VfsFile>> {
+        Ok(Box::new(fs::File::open(path)?))
+    }
+
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        fs::read(path)
+    }
+
+    fn write(&self, path: &Path, bytes: &[u8]) -> io::Result<()> {
+        let mut file = fs::File::create(path)?;
+        file.write_all(bytes)?;
+        file.sync_all()
+    }
+
+    fn append(&self, path: &Path, bytes: &[u8]) -> io::Result<()> {
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?;
+        file.write_all(bytes)?;
+        file.sync_data()
+    }
+
+    fn create_file(&self, path: &Path) -> io::Result<()> {
+        fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(path)
+            .map(|_| ())
+    }
+
+    fn create_dir(&self, path: &Path) -> io::Result<()> {
+        fs::create_dir(path)
+    }
+
+    fn create_dir_all(&self, path: &Path) -> io::Result<()> {
+        fs::create_dir_all(path)
+    }
+
+    fn copy_file(&self, from: &Path, to: &Path) -> io::Result<()> {
+        fs::copy(from, to).map(|_| ())
+    }
+
+    #[cfg(unix)]
+    fn symlink(&self, target: &Path, path: &Path) -> io::Result<()> {
+        std::os::unix::fs::symlink(target, path)
+    }
+
+    #[cfg(not(unix))]
+    fn symlink(&self, _target: &Path, path: &Path) -> io::Result<()> {
+        Err(unsupported(path, "symlinks"))
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        fs::rename(from, to)
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        fs::remove_file(path)
+    }
+
+    fn remove_dir_all(&self, path: &Path) -> io::Result<()> {
+        fs::remove_dir_all(path)
+    }
+
+    fn set_readonly(&self, path: &Path, readonly: bool) -> io::Result<()> {
+        let mut permissions = fs::metadata(path)?.permissions();
+        permissions.set_readonly(readonly);
+        fs::set_permissions(path, permissions)
+    }
+
+    fn copy_permissions(&self, from: &Path, to: &Path) -> io::Result<()> {
+        fs::set_permissions(to, fs::metadata(from)?.permissions())
+    }
+
+    fn watch(&self, root: &Path, on_change: OnChange) -> Result<WatchGuard, Error> {
+        Ok(Box::new(Watcher::start(root, on_change)?))
+    }
+}
+
+// endregion
+
+// region: ---Memory Vfs
+
+enum Node {
+    Folder,
+    File(Vec<u8>),
+}
+
+struct MemoryEntry {
+    node: Node,
+    created: SystemTime,
+    modified: SystemTime,
+    readonly: bool,
+}
+
+impl MemoryEntry {
+    fn new(node: Node) -> MemoryEntry {
+        let now = SystemTime::now();
+        MemoryEntry {
+            node,
+            created: now,
+            modified: now,
+            readonly: false,
+        }
+    }
+
+    fn stat(&self) -> Stat {
+        Stat {
+            is_dir: matches!(self.node, Node::Folder),
+            is_symlink: false,
+            len: match &self.node {
+                Node::Folder => 0,
+                Node::File(bytes) => bytes.len() as u64,
+            },
+            created: Some(self.created),
+            modified: Some(self.modified),
+            accessed: None,
+            readonly: self.readonly,
+            hidden: false,
+        }
+    }
+}
+
+// entries kept in memory, without symlinks, for the tests and the scratch workspaces
+// (the root folders of the paths always exist, the changes are sent without delay)
+#[derive(Default)]
+pub struct MemoryVfs {
+    entries: Mutex<BTreeMap<PathBuf, MemoryEntry>>,
+    watches: Mutex<Vec<(PathBuf, Weak<Mutex<OnChange>>)>>,
+}
+
+impl MemoryVfs {
+    pub fn new() -> MemoryVfs {
+        MemoryVfs::default()
+    }
+
+    // the entry at the path, with ".." and "." resolved
+    fn with_entry<T>(
+        &self,
+        path: &Path,
+        f: impl FnOnce(Option<&mut MemoryEntry>) -> io::Result<T>,
+    ) -> io::Result<T> {
+        let path = normalize(path);
+        let mut entries = self.entries.lock().unwrap();
+        f(entries.get_mut(&path))
+    }
+
+    fn stat_of(&self, path: &Path) -> io::Result<Stat> {
+        if is_root(path) {
+            return Ok(MemoryEntry::new(Node::Folder).stat());
+        }
+        self.with_entry(path, |entry| {
+            entry
+                .map(|entry| entry.stat())
+                .ok_or_else(|| not_found(path))
+        })
+    }
+
+    // the entry is added in an existing folder
+    fn insert(&self, path: &Path, entry: MemoryEntry, replace: bool) -> io::Result<()> {
+        let path = normalize(path);
+        let mut entries = self.entries.lock().unwrap();
+        check_parent(&entries, &path)?;
+        let existed = match entries.get(&path) {
+            Some(current) if !replace || matches!(current.node, Node::Folder) => {
+                return Err(io::Error::new(
+                    ErrorKind::AlreadyExists,
+                    path.display().to_string(),
+                ))
+            }
+            Some(current) if current.readonly => return Err(permission_denied(&path)),
+            Some(_) => true,
+            None => false,
+        };
+        entries.insert(path.clone(), entry);
+        drop(entries);
+
+        let path = path.to_string_lossy().to_string();
+        self.notify(vec![match existed {
+            true => FsChange::Modified { path },
+            false => FsChange::Created { path },
+        }]);
+        Ok(())
+    }
+
+    // the callbacks are called outside of the lock of the entries, they may read them
+    fn notify(&self, changes: Vec<FsChange>) {
+        let mut watches = self.watches.lock().unwrap();
+        watches.retain(|(_, on_change)| on_change.strong_count() > 0);
+        let watches: Vec<(PathBuf, Arc<Mutex<OnChange>>)> = watches
+            .iter()
+            .filter_map(|(root, on_change)| Some((root.clone(), on_change.upgrade()?)))
+            .collect();
+
+        for (root, on_change) in watches {
+            let changes: Vec<FsChange> = changes
+                .iter()
+                .filter(|change| {
+                    change
+                        .paths()
+                        .iter()
+                        .any(|path| Path::new(path).starts_with(&root))
+                })
+                .cloned()
+                .collect();
+            if !changes.is_empty() {
+                (on_change.lock().unwrap())(changes);
+            }
+        }
+    }
+}
+
+impl Vfs for MemoryVfs {
+    fn list(&self, folder: &Path) -> io::Result<Vec<PathBuf>> {
+        let folder = normalize(folder);
+        if !self.stat_of(&folder)?.is_dir {
+            return Err(io::Error::other(format!(
+                "{} is not a folder",
+                folder.display()
+            )));
+        }
+        let entries = self.entries.lock().unwrap();
+        Ok(entries
+            .range(folder.clone()..)
+            .map(|(path, _)| path)
+            .take_while(|path| path.starts_with(&folder))
+            .filter(|path| path.parent() == Some(folder.as_path()))
+            .cloned()
+            .collect())
+    }
+
+    fn stat(&self, path: &Path) -> io::Result<Stat> {
+        self.stat_of(path)
+    }
+
+    fn stat_link(&self, path: &Path) -> io::Result<Stat> {
+        self.stat_of(path)
+    }
+
+    fn read_link(&self, path: &Path) -> io::Result<PathBuf> {
+        self.stat_of(path)?;
+        Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            format!("{} is not a symlink", path.display()),
+        ))
+    }
+
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        self.stat_of(path)?;
+        Ok(normalize(path))
+    }
+
+    fn open(&self, path: &Path) -> io::Result<Box<dyn VfsFile>> {
+        let bytes = self.read(path)?;
+        Ok(Box::new(Cursor::new(bytes)))
+    }
+
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        self.with_entry(path, |entry| match entry.map(|entry| &entry.node) {
+            Some(Node::File(bytes)) => Ok(bytes.clone()),
+            Some(Node::Folder) => Err(io::Error::other(format!("{} is a folder", path.display()))),
+            None => Err(not_found(path)),
+        })
+    }
+
+    fn write(&self, path: &Path, bytes: &[u8]) -> io::Result<()> {
+        let created = self
+            .with_entry(path, |entry| Ok(entry.map(|entry| entry.created)))?
+            .unwrap_or_else(SystemTime::now);
+        let mut entry = MemoryEntry::new(Node::File(bytes.to_vec()));
+        entry.created = created;
+        self.insert(path, entry, true)
+    }
+
+    fn append(&self, path: &Path, bytes: &[u8]) -> io::Result<()> {
+        let mut appended = match self.read(path) {
+            Ok(current) => current,
+            Err(err) if err.kind() == ErrorKind::NotFound => Vec::new(),
+            Err(err) => return Err(err),
+        };
+        appended.extend_from_slice(bytes);
+        self.write(path, &appended)
+    }
+
+    fn create_file(&self, path: &Path) -> io::Result<()> {
+        self.insert(path, MemoryEntry::new(Node::File(Vec::new())), false)
+    }
+
+    fn create_dir(&self, path: &Path) -> io::Result<()> {
+        self.insert(path, MemoryEntry::new(Node::Folder), false)
+    }
+
+    fn create_dir_all(&self, path: &Path) -> io::Result<()> {
+        let path = normalize(path);
+        let mut folders: Vec<&Path> = path
+            .ancestors()
+            .take_while(|folder| !is_root(folder))
+            .collect();
+        folders.reverse();
+        for folder in folders {
+            match self.stat_of(folder) {
+                Ok(stat) if stat.is_dir => continue,
+                _ => self.create_dir(folder)?,
+            }
+        }
+        Ok(())
+    }
+
+    fn copy_file(&self, from: &Path, to: &Path) -> io::Result<()> {
+        let bytes = self.read(from)?;
+        self.write(to, &bytes)
+    }
+
+    fn symlink(&self, _target: &Path, path: &Path) -> io::Result<()> {
+        Err(unsupported(path, "symlinks"))
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        let (from, to) = (normalize(from), normalize(to));
+        let mut entries = self.entries.lock().unwrap();
+        let is_folder = match entries.get(&from) {
+            Some(entry) => matches!(entry.node, Node::Folder),
+            None => return Err(not_found(&from)),
+        };
+        check_parent(&entries, &to)?;
+        if to.starts_with(&from) && to != from {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("cannot move {} into itself", from.display()),
+            ));
+        }
+        if let Some(target) = entries.get(&to).filter(|_| to != from) {
+            // a file replaces a file, a folder an empty folder
+            let replaceable = match (&target.node, is_folder) {
+                (Node::File(_), false) => true,
+                (Node::Folder, true) => !entries
+                    .keys()
+                    .any(|path| path.parent() == Some(to.as_path())),
+                _ => false,
+            };
+            if !replaceable {
+                return Err(io::Error::new(
+                    ErrorKind::AlreadyExists,
+                    to.display().to_string(),
+                ));
+            }
+        }
+
+        let moved: Vec<PathBuf> = entries
+            .range(from.clone()..)
+            .map(|(path, _)| path)
+            .take_while(|path| path.starts_with(&from))
+            .cloned()
+            .collect();
+        for path in moved {
+            if let Some(entry) = entries.remove(&path) {
+                let moved_path = match path.strip_prefix(&from) {
+                    Ok(relative) if !relative.as_os_str().is_empty() => to.join(relative),
+                    _ => to.clone(),
+                };
+                entries.insert(moved_path, entry);
+            }
+        }
+        drop(entries);
+
+        self.notify(vec![FsChange::Renamed {
+            from: from.to_string_lossy().to_string(),
+            to: to.to_string_lossy().to_string(),
+        }]);
+        Ok(())
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        let path = normalize(path);
+        let mut entries = self.entries.lock().unwrap();
+        match entries.get(&path).map(|entry| &entry.node) {
+            Some(Node::File(_)) => entries.remove(&path),
+            Some(Node::Folder) => {
+                return Err(io::Error::other(format!("{} is a folder", path.display())))
+            }
+            None => return Err(not_found(&path)),
+        };
+        drop(entries);
+
+        self.notify(vec![FsChange::Removed {
+            path: path.to_string_lossy().to_string(),
+        }]);
+        Ok(())
+    }
+
+    fn remove_dir_all(&self, path: &Path) -> io::Result<()> {
+        let path = normalize(path);
+        let mut entries = self.entries.lock().unwrap();
+        if !entries.contains_key(&path) {
+            return Err(not_found(&path));
+        }
+        entries.retain(|entry_path, _| !entry_path.starts_with(&path));
+        drop(entries);
+
+        self.notify(vec![FsChange::Removed {
+            path: path.to_string_lossy().to_string(),
+        }]);
+        Ok(())
+    }
+
+    fn set_readonly(&self, path: &Path, readonly: bool) -> io::Result<()> {
+        self.with_entry(path, |entry| {
+            entry.ok_or_else(|| not_found(path))?.readonly = readonly;
+            Ok(())
+        })
+    }
+
+    fn copy_permissions(&self, from: &Path, to: &Path) -> io::Result<()> {
+        let readonly = self.stat_of(from)?.readonly;
+        self.set_readonly(to, readonly)
+    }
+
+    fn watch(&self, root: &Path, on_change: OnChange) -> Result<WatchGuard, Error> {
+        if !self.stat_of(root)?.is_dir {
+            return Err(Error::InvalidPath(format!(
+                "{} is not a folder",
+                root.display()
+            )));
+        }
+        let on_change = Arc::new(Mutex::new(on_change));
+        self.watches
+            .lock()
+            .unwrap()
+            .push((normalize(root), Arc::downgrade(&on_change)));
+        Ok(Box::new(on_change))
+    }
+}
+
+// "." and ".." are resolved without the file system
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !is_root(&normalized) {
+                    normalized.pop();
+                }
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+fn is_root(path: &Path) -> bool {
+    path.file_name().is_none()
+}
+
+fn check_parent(entries: &BTreeMap<PathBuf, MemoryEntry>, path: &Path) -> io::Result<()> {
+    let parent = match path.parent() {
+        Some(parent) if !is_root(parent) => parent,
+        _ => return Ok(()),
+    };
+    match entries.get(parent).map(|entry| &entry.node) {
+        Some(Node::Folder) => Ok(()),
+        Some(Node::File(_)) => Err(io::Error::other(format!(
+            "{} is not a folder",
+            parent.display()
+        ))),
+        None => Err(not_found(parent)),
+    }
+}
+
+fn not_found(path: &Path) -> io::Error {
+    io::Error::new(ErrorKind::NotFound, path.display().to_string())
+}
+
+fn permission_denied(path: &Path) -> io::Error {
+    io::Error::new(ErrorKind::PermissionDenied, path.display().to_string())
+}
+
+fn unsupported(path: &Path, what: &str) -> io::Error {
+    io::Error::new(
+        ErrorKind::Unsupported,
+        format!("{}: {} are not supported", path.display(), what),
+    )
+}
+
+// endregion
@@ -0,0 +1,465 @@
+use crate::error::Error;
+use crate::event::FsChange;
+use notify::event::{ModifyKind, RenameMode};
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher as _};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+// region: ---Watcher
+
+// quiet time before the changes are sent, and longest wait under a stream of changes
+const DEBOUNCE: Duration = Duration::from_millis(200);
+const MAX_DELAY: Duration = Duration::from_secs(1);
+
+// how long a write of the app hides the change events of its path
+const OWN_WRITE_DELAY: Duration = Duration::from_secs(2);
+
+// watches the folder recursively until dropped
+pub struct Watcher {
+    _watcher: RecommendedWatcher,
+}
+
+impl Watcher {
+    // `on_change` receives the coalesced changes from the debounce thread, with the own writes
+    pub fn start<F>(root: &Path, on_change: F) -> Result<Self, Error>
+    where
+        F: Fn(Vec<FsChange>) + Send + 'static,
+    {
+        let (sender, receiver) = channel();
+        let mut watcher = notify::recommended_watcher(move |event| {
+            let _ = sender.send(event);
+        })
+        .map_err(to_error)?;
+        watcher
+            .watch(root, RecursiveMode::Recursive)
+            .map_err(to_error)?;
+
+        // the thread ends when the watcher is dropped with the sender
+        thread::spawn(move || debounce(receiver, on_change));
+
+        Ok(Watcher { _watcher: watcher })
+    }
+}
+
+fn to_error(err: notify::Error) -> Error {
+    let paths = err
+        .paths
+        .iter()
+        .map(|path| path.display().to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+    match err.kind {
+        notify::ErrorKind::Io(err) => Error::from(err),
+        notify::ErrorKind::PathNotFound => Error::NotFound(paths),
+        kind => Error::Io(notify::Error::new(kind).to_string()),
+    }
+}
+
+fn debounce<F: Fn(Vec<FsChange>)>(receiver: Receiver<notify::Result<notify::Event>>, on_change: F) {
+    let mut batch = Batch::default();
+    while let Ok(event) = receiver.recv() {
+        let started = Instant::now();
+        batch.add_event(event);
+
+        let mut disconnected = false;
+        while started.elapsed() < MAX_DELAY {
+            match receiver.recv_timeout(DEBOUNCE) {
+                Ok(event) => batch.add_event(event),
+                Err(RecvTimeoutError::Timeout) => break,
+                Err(RecvTimeoutError::Disconnected) => {
+                    disconnected = true;
+                    break;
+                }
+            }
+        }
+
+        let changes = batch.take_changes();
+        if !changes.is_empty() {
+            on_change(changes);
+        }
+        if disconnected {
+            return;
+        }
+    }
+}
+
+// endregion
+
+// region: ---Coalescing
+
+#[derive(Debug, Clone)]
+enum Pending {
+    Created,
+    Modified,
+    Removed,
+    Renamed(PathBuf), // from
+}
+
+// changes by path, in the order of their first event
+// (the paths sent by the earlier batches are known to exist until their removal)
+#[derive(Default)]
+struct Batch {
+    order: Vec<PathBuf>,
+    ordered: HashSet<PathBuf>,
+    changes: HashMap<PathBuf, Pending>,
+    existing: HashSet<PathBuf>,
+    rename_from: Option<(Option<usize>, PathBuf)>, // (tracker, path) waiting for its destination
+}
+
+impl Batch {
+    fn add_event(&mut self, event: notify::Result<notify::Event>) {
+        let event = match event {
+            Ok(event) => event,
+            Err(err) => {
+                eprintln!("watcher error: {}", err);
+                return;
+            }
+        };
+        let tracker = event.attrs.tracker();
+        let mut paths = event.paths.into_iter();
+
+        match event.kind {
+            EventKind::Access(_) => {}
+            EventKind::Create(_) => paths.for_each(|path| self.add(path, Pending::Created)),
+            EventKind::Remove(_) => paths.for_each(|path| self.add(path, Pending::Removed)),
+            EventKind::Modify(ModifyKind::Name(RenameMode::Both)) => {
+                if let (Some(from), Some(to)) = (paths.next(), paths.next()) {
+                    self.rename(from, to);
+                }
+            }
+            EventKind::Modify(ModifyKind::Name(RenameMode::From)) => {
+                self.flush_rename_from();
+                self.rename_from = paths.next().map(|path| (tracker, path));
+            }
+            EventKind::Modify(ModifyKind::Name(RenameMode::To)) => {
+                let to = match paths.next() {
+                    Some(to) => to,
+                    None => return,
+                };
+                match self.rename_from.take() {
+                    Some((from_tracker, from)) if from_tracker == tracker => self.rename(from, to),
+                    rename_from => {
+                        self.rename_from = rename_from;
+                        self.add(to, Pending::Created);
+                    }
+                }
+            }
+            // a rename without its other path is seen as a removal or a creation
+            EventKind::Modify(ModifyKind::Name(_)) => paths.for_each(|path| {
+                let change = if path.exists() {
+                    Pending::Created
+                } else {
+                    Pending::Removed
+                };
+                self.add(path, change);
+            }),
+            _ => paths.for_each(|path| self.add(path, Pending::Modified)),
+        }
+    }
+
+    fn add(&mut self, path: PathBuf, change: Pending) {
+        if let (Some(Pending::Renamed(from)), Pending::Removed) = (self.changes.get(&path), &change)
+        {
+            // the entry is gone from where it was before the batch
+            let from = from.clone();
+            self.changes.remove(&path);
+            let removed = match self.changes.remove(&from) {
+                Some(Pending::Created) => Pending::Modified,
+                _ => Pending::Removed,
+            };
+            return self.add(from, removed);
+        }
+
+        let merged = match (self.changes.get(&path), change) {
+            (Some(Pending::Created), Pending::Modified) => Some(Pending::Created),
+            (Some(Pending::Created), Pending::Removed) => None,
+            (Some(Pending::Removed), Pending::Created) => Some(Pending::Modified),
+            (Some(Pending::Renamed(from)), Pending::Modified) => {
+                Some(Pending::Renamed(from.clone()))
+            }
+            (_, change) => Some(change),
+        };
+
+        match merged {
+            Some(change) => {
+                if self.ordered.insert(path.clone()) {
+                    self.order.push(path.clone());
+                }
+                self.changes.insert(path, change);
+            }
+            None => {
+                self.changes.remove(&path);
+            }
+        }
+    }
+
+    fn rename(&mut self, from: PathBuf, to: PathBuf) {
+        match self.changes.remove(&from) {
+            // a file created then renamed in the batch is only created at its destination, or
+            // modified when it replaces an existing file (atomic saves)
+            Some(Pending::Created) => {
+                let change = match self.existed(&to) {
+                    true => Pending::Modified,
+                    false => Pending::Created,
+                };
+                self.add(to, change)
+            }
+            Some(Pending::Renamed(origin)) => self.add(to, Pending::Renamed(origin)),
+            _ => self.add(to, Pending::Renamed(from)),
+        }
+    }
+
+    fn existed(&self, path: &Path) -> bool {
+        match self.changes.get(path) {
+            Some(Pending::Modified | Pending::Renamed(_)) => true,
+            Some(_) => false,
+            None => self.existing.contains(path),
+        }
+    }
+
+    fn forget(&mut self, path: &Path) {
+        self.existing.retain(|existing| !existing.starts_with(path));
+    }
+
+    fn flush_rename_from(&mut self) {
+        if let Some((_, from)) = self.rename_from.take() {
+            self.add(from, Pending::Removed);
+        }
+    }
+
+    fn take_changes(&mut self) -> Vec<FsChange> {
+        self.flush_rename_from();
+        let mut changes = std::mem::take(&mut self.changes);
+        self.ordered.clear();
+        std::mem::take(&mut self.order)
+            .into_iter()
+            .filter_map(|path| {
+                let change = changes.remove(&path)?;
+                match &change {
+                    Pending::Removed => self.forget(&path),
+                    Pending::Renamed(from) => {
+                        self.forget(from);
+                        self.existing.insert(path.clone());
+                    }
+                    _ => {
+                        self.existing.insert(path.clone());
+                    }
+                }
+                let path_string = path.to_string_lossy().to_string();
+                Some(match change {
+                    Pending::Created => FsChange::Created { path: path_string },
+                    Pending::Modified => FsChange::Modified { path: path_string },
+                    Pending::Removed => FsChange::Removed { path: path_string },
+                    Pending::Renamed(from) => FsChange::Renamed {
+                        from: from.to_string_lossy().to_string(),
+                        to: path_string,
+                    },
+                })
+            })
+            .collect()
+    }
+}
+
+// endregion
+
+// region: ---Own Writes
+
+// (path, time, whether its removal and the changes of its children are hidden too)
+static OWN_WRITES: Mutex<Vec<(PathBuf, Instant, bool)>> = Mutex::new(Vec::new());
+
+// the changes of the path in the next moments come from the app and are not emitted
+pub fn ignore_own_write(path: &Path) {
+    push_own_write(path, false);
+}
+
+// the entry is created, moved, copied or removed by the app with all its children
+pub fn ignore_own_operation(path: &Path) {
+    push_own_write(path, true);
+}
+
+fn push_own_write(path: &Path, operation: bool) {
+    let mut own_writes = OWN_WRITES.lock().unwrap();
+    own_writes.retain(|(_, time, _)| time.elapsed() < OWN_WRITE_DELAY);
+    own_writes.push((path.to_path_buf(), Instant::now(), operation));
+}
+
+// a removal never comes from a write
+pub fn is_own_write(change: &FsChange) -> bool {
+    let removed = matches!(change, FsChange::Removed { .. });
+    let mut own_writes = OWN_WRITES.lock().unwrap();
+    own_writes.retain(|(_, time, _)| time.elapsed() < OWN_WRITE_DELAY);
+    change.paths().iter().all(|path| {
+        let path = Path::new(path);
+        own_writes.iter().any(|(own_path, _, operation)| {
+            if *operation {
+                path.starts_with(own_path)
+            } else {
+                !removed && path == own_path
+            }
+        })
+    })
+}
+
+// endregion
+
+// region: ---Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use notify::event::{CreateKind, DataChange, RemoveKind};
+
+    fn event(kind: EventKind, paths: &[&str]) -> notify::Result<notify::Event> {
+        let event = paths.iter().fold(notify::Event::new(kind), |event, path| {
+            event.add_path(PathBuf::from(path))
+        });
+        Ok(event)
+    }
+
+    fn created(path: &str) -> notify::Result<notify::Event> {
+        event(EventKind::Create(CreateKind::File), &[path])
+    }
+
+    fn modified(path: &str) -> notify::Result<notify::Event> {
+        let kind = EventKind::Modify(ModifyKind::Data(DataChange::Content));
+        event(kind, &[path])
+    }
+
+    fn removed(path: &str) -> notify::Result<notify::Event> {
+        event(EventKind::Remove(RemoveKind::File), &[path])
+    }
+
+    fn renamed(mode: RenameMode, paths: &[&str], tracker: usize) -> notify::Result<notify::Event> {
+        let kind = EventKind::Modify(ModifyKind::Name(mode));
+        event(kind, paths).map(|event| event.set_tracker(tracker))
+    }
+
+    fn changes(events: Vec<notify::Result<notify::Event>>) -> Vec<FsChange> {
+        let mut batch = Batch::default();
+        events.into_iter().for_each(|event| batch.add_event(event));
+        batch.take_changes()
+    }
+
+    fn change(kind: &str, path: &str) -> FsChange {
+        let path = path.to_string();
+        match kind {
+            "created" => FsChange::Created { path },
+            "modified" => FsChange::Modified { path },
+            _ => FsChange::Removed { path },
+        }
+    }
+
+    #[test]
+    fn changes_of_a_path_are_merged() {
+        let changes = changes(vec![
+            created("/a"),
+            modified("/a"),
+            modified("/b"),
+            modified("/b"),
+            removed("/c"),
+            created("/c"),
+        ]);
+        assert_eq!(
+            changes,
+            [
+                change("created", "/a"),
+                change("modified", "/b"),
+                change("modified", "/c"),
+            ]
+        );
+    }
+
+    #[test]
+    fn file_created_then_removed_is_not_changed() {
+        assert!(changes(vec![created("/a"), modified("/a"), removed("/a")]).is_empty());
+    }
+
+    #[test]
+    fn rename_events_are_paired_by_tracker() {
+        let changes = changes(vec![
+            renamed(RenameMode::From, &["/a"], 1),
+            renamed(RenameMode::To, &["/b"], 1),
+            renamed(RenameMode::Both, &["/c", "/d"], 0),
+            modified("/d"),
+        ]);
+        let renamed = |from: &str, to: &str| FsChange::Renamed {
+            from: from.to_string(),
+            to: to.to_string(),
+        };
+        assert_eq!(changes, [renamed("/a", "/b"), renamed("/c", "/d")]);
+    }
+
+    #[test]
+    fn unpaired_rename_events_are_created_and_removed() {
+        let changes = changes(vec![
+            renamed(RenameMode::From, &["/a"], 1),
+            renamed(RenameMode::To, &["/b"], 2),
+        ]);
+        assert_eq!(changes, [change("created", "/b"), change("removed", "/a")]);
+    }
+
+    #[test]
+    fn renames_in_a_batch_are_followed() {
+        let changes = changes(vec![
+            created("/new"),
+            renamed(RenameMode::Both, &["/new", "/created"], 0),
+            renamed(RenameMode::Both, &["/x", "/y"], 0),
+            renamed(RenameMode::Both, &["/y", "/z"], 0),
+        ]);
+        let from_x = FsChange::Renamed {
+            from: String::from("/x"),
+            to: String::from("/z"),
+        };
+        assert_eq!(changes, [change("created", "/created"), from_x]);
+    }
+
+    #[test]
+    fn renamed_then_removed_is_removed_from_its_origin() {
+        let changes = changes(vec![
+            renamed(RenameMode::Both, &["/a", "/b"], 0),
+            modified("/b"),
+            removed("/b"),
+        ]);
+        assert_eq!(changes, [change("removed", "/a")]);
+    }
+
+    #[test]
+    fn atomic_saves_are_modified() {
+        let mut batch = Batch::default();
+        batch.add_event(modified("/a"));
+        assert_eq!(batch.take_changes(), [change("modified", "/a")]);
+
+        // a temporary file renamed over the saved one
+        batch.add_event(created("/a.tmp"));
+        batch.add_event(modified("/a.tmp"));
+        batch.add_event(renamed(RenameMode::Both, &["/a.tmp", "/a"], 0));
+        batch.add_event(created("/b.tmp"));
+        batch.add_event(renamed(RenameMode::Both, &["/b.tmp", "/b"], 0));
+        assert_eq!(
+            batch.take_changes(),
+            [change("modified", "/a"), change("created", "/b")]
+        );
+
+        batch.add_event(removed("/a"));
+        batch.take_changes();
+        batch.add_event(created("/a.tmp"));
+        batch.add_event(renamed(RenameMode::Both, &["/a.tmp", "/a"], 0));
+        assert_eq!(batch.take_changes(), [change("created", "/a")]);
+    }
+
+    #[test]
+    fn own_operations_hide_the_changes_of_their_children() {
+        ignore_own_write(Path::new("/own/file"));
+        ignore_own_operation(Path::new("/own/folder"));
+        assert!(is_own_write(&change("modified", "/own/file")));
+        assert!(!is_own_write(&change("removed", "/own/file")));
+        assert!(is_own_write(&change("removed", "/own/folder/child")));
+        assert!(!is_own_write(&change("modified", "/own/other")));
+    }
+}
+
+// endregion
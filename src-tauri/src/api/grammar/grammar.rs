@@ -2,12 +2,17 @@ use crate::api::grammar::fragment::Fragment;
 use crate::api::grammar::rules::{Context, Rule, RuleStatus};
 use crate::api::grammar::token::{TokenDefinition, Tokenizer};
 
+// (open, close) texts inserted by the editor
+pub type AutoClosingPairs = Vec<(Box<str>, Box<str>)>;
+
 #[derive(Debug)]
 pub struct Grammar {
     name: Box<str>,
     tokens_definition: Vec<TokenDefinition>,
     fragments: Vec<Fragment>,
     main_fragment_index: usize,
+    brackets: Vec<(usize, usize)>, // (open, close) token definitions
+    auto_closing_pairs: AutoClosingPairs,
 }
 
 impl Grammar {
@@ -22,20 +27,68 @@ impl Grammar {
             tokens_definition,
             fragments,
             main_fragment_index: main_fragment,
+            brackets: Vec::new(),
+            auto_closing_pairs: Vec::new(),
         }
     }
 
+    pub fn with_brackets(mut self, brackets: Vec<(usize, usize)>) -> Self {
+        self.brackets = brackets;
+        self
+    }
+
+    pub fn with_auto_closing_pairs(mut self, auto_closing_pairs: AutoClosingPairs) -> Self {
+        self.auto_closing_pairs = auto_closing_pairs;
+        self
+    }
+
+    // the context of the main fragment, None if the text does not match the whole grammar
     pub fn parse(&self, text: &str) -> Option<Context> {
         let mut tokenizer = Tokenizer::new(text, &self.tokens_definition);
         match self
             .get_rule_of_fragment(self.main_fragment_index)
             .parse(&mut tokenizer, self, 0)
         {
-            RuleStatus::Valid(context, _) => Some(context),
-            RuleStatus::Invalid => None,
+            RuleStatus::Valid(context, index_end) if tokenizer.is_end(index_end) => Some(context),
+            _ => None,
+        }
+    }
+
+    // tokens that could follow the text: (byte offset where they are expected,
+    // (definition, innermost fragment) of each token)
+    pub fn expected(&self, text: &str) -> (usize, Vec<(usize, Option<usize>)>) {
+        let mut tokenizer = Tokenizer::new(text, &self.tokens_definition);
+        let rule_status =
+            self.get_rule_of_fragment(self.main_fragment_index)
+                .parse(&mut tokenizer, self, 0);
+
+        let (expected_index, expected) = tokenizer.get_expected();
+        let expected = expected.clone();
+        let offset = tokenizer.get_offset(expected_index);
+
+        // a complete text only accepts the tokens expected after its end
+        match rule_status {
+            RuleStatus::Valid(_, index_end)
+                if tokenizer.is_end(index_end) && expected_index < index_end =>
+            {
+                (text.len(), Vec::new())
+            }
+            _ => (offset, expected),
         }
     }
 
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_brackets(&self) -> &Vec<(usize, usize)> {
+        &self.brackets
+    }
+
+    pub fn get_auto_closing_pairs(&self) -> &AutoClosingPairs {
+        &self.auto_closing_pairs
+    }
+
     pub fn get_tokens_definition(&self) -> &Vec<TokenDefinition> {
         &self.tokens_definition
     }
@@ -44,6 +97,18 @@ impl Grammar {
         &self.tokens_definition[index]
     }
 
+    pub fn get_token_definition_index(&self, name: &str) -> Option<usize> {
+        self.tokens_definition
+            .iter()
+            .position(|definition| definition.name() == name)
+    }
+
+    pub fn get_fragment_index(&self, name: &str) -> Option<usize> {
+        self.fragments
+            .iter()
+            .position(|fragment| fragment.get_name() == name)
+    }
+
     pub fn get_fragment(&self, index: usize) -> &Fragment {
         &self.fragments[index]
     }
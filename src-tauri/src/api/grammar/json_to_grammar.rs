@@ -1,5 +1,5 @@
 use crate::api::grammar::fragment::Fragment;
-use crate::api::grammar::grammar::Grammar;
+use crate::api::grammar::grammar::{AutoClosingPairs, Grammar};
 use crate::api::grammar::rules::{
     FragmentRule, LoopRule, OptionalRule, OrRule, Rule, SequenceRule, TokenRule,
 };
@@ -72,6 +72,8 @@ impl GrammarData {
 ///         fragmentName: Fragment,
 ///         fragmentName: Fragment,
 ///         ...},
+///     brackets?: [[openTokenName, closeTokenName], ...],
+///     autoClosingPairs?: [[openText, closeText], ...],
 /// }
 #[tauri::command]
 pub fn json_to_grammar(json: Value) -> Result<Grammar, Error> {
@@ -79,17 +81,72 @@ pub fn json_to_grammar(json: Value) -> Result<Grammar, Error> {
     let (token_definitions, tokens_index_map) =
         json_to_tokens_definition(json.get("tokenDefinitions").unwrap())?;
 
+    let brackets = json
+        .get("brackets")
+        .map(|brackets| json_to_brackets(brackets, &tokens_index_map))
+        .transpose()?
+        .unwrap_or_default();
+    let auto_closing_pairs = json
+        .get("autoClosingPairs")
+        .map(json_to_auto_closing_pairs)
+        .transpose()?
+        .unwrap_or_default();
+
     let (fragments, index_main_fragment) =
         json_to_fragments(json.get("fragments").unwrap(), tokens_index_map)?;
-    
+
     Ok(Grammar::new(
         grammar_name,
         token_definitions,
         fragments,
         index_main_fragment,
-    ))
+    )
+    .with_brackets(brackets)
+    .with_auto_closing_pairs(auto_closing_pairs))
+}
+
+// region ---Brackets
+
+fn json_to_brackets(
+    json: &Value,
+    tokens_index_map: &HashMap<String, usize>,
+) -> Result<Vec<(usize, usize)>, Error> {
+    let mut brackets = Vec::new();
+    for (open, close) in json_to_pairs(json)? {
+        let open = tokens_index_map
+            .get(open)
+            .ok_or(Error::UnknownTokenOrFragment)?;
+        let close = tokens_index_map
+            .get(close)
+            .ok_or(Error::UnknownTokenOrFragment)?;
+        brackets.push((*open, *close));
+    }
+    Ok(brackets)
+}
+
+fn json_to_auto_closing_pairs(json: &Value) -> Result<AutoClosingPairs, Error> {
+    Ok(json_to_pairs(json)?
+        .into_iter()
+        .map(|(open, close)| (Box::from(open), Box::from(close)))
+        .collect())
 }
 
+// [[string, string], ...]
+fn json_to_pairs(json: &Value) -> Result<Vec<(&str, &str)>, Error> {
+    let mut pairs = Vec::new();
+    for pair in json.as_array().ok_or(Error::Default)? {
+        match pair.as_array().map(|pair| pair.as_slice()) {
+            Some([Value::String(open), Value::String(close)]) => {
+                pairs.push((open.as_str(), close.as_str()))
+            }
+            _ => return Err(Error::Default),
+        }
+    }
+    Ok(pairs)
+}
+
+// endregion
+
 // region ---TokenDefinition
 fn json_to_tokens_definition(
     json: &Value,
@@ -110,26 +167,35 @@ fn json_to_tokens_definition(
 
 /// # TokenDefinition
 /// 1. string : if []+*? -> regex else -> keyword
-/// 1. { regex: string } -> regex,
-/// 1. { keyword: string } -> keyword,
+/// 1. { regex: string, skip?: bool } -> regex,
+/// 1. { keyword: string, skip?: bool } -> keyword,
+///
+/// skipped tokens (whitespace, comments, ...) are consumed between the other tokens
 fn json_to_token_definition(json: &Value, name: &str) -> Result<TokenDefinition, Error> {
     match json {
         Value::String(str) => Ok(TokenDefinition::new(name, string_to_pattern(str)?)),
         Value::Object(object) => {
             // Regex
-            if let Some(regex) = object.get("regex") {
+            let definition = if let Some(regex) = object.get("regex") {
                 let regex = regex.as_str().unwrap();
-                Ok(TokenDefinition::new_regex(
-                    name,
-                    format!("{}{}", "^", regex).as_str(),
-                ))
+                TokenDefinition::new(name, string_to_pattern(&regex.to_string())?)
             // Keyword
             } else if let Some(keyword) = object.get("keyword") {
                 let keyword = keyword.as_str().unwrap();
-                Ok(TokenDefinition::new_keyword(name, keyword))
+                TokenDefinition::new_keyword(name, keyword)
             // Undefined
             } else {
-                Err(Error::UndefinedToken)
+                return Err(Error::UndefinedToken);
+            };
+
+            let skip = object
+                .get("skip")
+                .map(|skip| skip.as_bool().unwrap_or(false))
+                .unwrap_or(false);
+            if skip {
+                Ok(definition.skipped())
+            } else {
+                Ok(definition)
             }
         }
         _ => Err(Error::UndefinedToken),
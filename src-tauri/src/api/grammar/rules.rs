@@ -32,6 +32,47 @@ impl Debug for Context {
     }
 }
 
+impl Context {
+    // direct children of the context, in text order
+    pub fn children(&self) -> Vec<&Context> {
+        match self {
+            Context::Token(_) => Vec::new(),
+            Context::Fragment(fragment_context) => vec![fragment_context.context()],
+            Context::Sequence(sequence_context) => sequence_context.0.iter().collect(),
+            Context::Or(or_context) => vec![or_context.1.as_ref()],
+            Context::Optional(optional_context) => optional_context.0.iter().collect(),
+            Context::Loop(loop_context) => loop_context.contexts(),
+        }
+    }
+
+    // all the tokens of the context, in text order
+    pub fn tokens(&self) -> Vec<&TokenContext> {
+        let mut tokens = Vec::new();
+        self.push_tokens(&mut tokens);
+        tokens
+    }
+
+    fn push_tokens<'a>(&'a self, tokens: &mut Vec<&'a TokenContext>) {
+        match self {
+            Context::Token(token_context) => tokens.push(token_context),
+            _ => {
+                for child in self.children() {
+                    child.push_tokens(tokens);
+                }
+            }
+        }
+    }
+
+    // (start, end) byte offsets of the context, None if it contains no token
+    pub fn span(&self) -> Option<(usize, usize)> {
+        let tokens = self.tokens();
+        match (tokens.first(), tokens.last()) {
+            (Some(first), Some(last)) => Some((first.span().0, last.span().1)),
+            _ => None,
+        }
+    }
+}
+
 // endregion
 
 // region: ---Rule Trait
@@ -48,12 +89,25 @@ where
 // region: ---Token
 #[derive(Debug)]
 pub struct TokenRule(pub usize); // index of the token definition in the grammar
-pub struct TokenContext(usize, String);
+pub struct TokenContext(usize, String, (usize, usize)); // index of the token definition, text and span of the token
 impl Debug for TokenContext {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(f, "{:?}", self.1)
     }
 }
+impl TokenContext {
+    pub fn definition_index(&self) -> usize {
+        self.0
+    }
+
+    pub fn text(&self) -> &str {
+        &self.1
+    }
+
+    pub fn span(&self) -> (usize, usize) {
+        self.2
+    }
+}
 impl Rule for TokenRule {
     fn parse(
         &self,
@@ -63,7 +117,11 @@ impl Rule for TokenRule {
     ) -> RuleStatus {
         let token = tokenizer.get_token_from_token_definition(index_start, self.0);
         if let Some(token) = token {
-            let context = Context::Token(TokenContext(self.0, token.slice().to_string()));
+            let context = Context::Token(TokenContext(
+                self.0,
+                token.slice().to_string(),
+                token.span(),
+            ));
             RuleStatus::Valid(context, index_start + 1)
         } else {
             RuleStatus::Invalid
@@ -83,6 +141,15 @@ impl Debug for FragmentContext {
         write!(f, "{:?}", self.1)
     }
 }
+impl FragmentContext {
+    pub fn fragment_index(&self) -> usize {
+        self.0
+    }
+
+    pub fn context(&self) -> &Context {
+        self.1.as_ref()
+    }
+}
 impl Rule for FragmentRule {
     fn parse(
         &self,
@@ -91,7 +158,9 @@ impl Rule for FragmentRule {
         index_start: usize,
     ) -> RuleStatus {
         let rule: &dyn Rule = grammar.get_rule_of_fragment(self.0);
+        tokenizer.enter_fragment(self.0);
         let rule_status = rule.parse(tokenizer, grammar, index_start);
+        tokenizer.exit_fragment();
         match rule_status {
             RuleStatus::Valid(context, index_end) => RuleStatus::Valid(
                 Context::Fragment(FragmentContext(self.0, Box::from(context))),
@@ -223,6 +292,29 @@ pub struct LoopContext(
     Option<Vec<Context>>, /*Separators*/
 );
 
+impl LoopContext {
+    pub fn values(&self) -> &Vec<Context> {
+        &self.0
+    }
+
+    // values and separators, in text order
+    fn contexts(&self) -> Vec<&Context> {
+        match &self.1 {
+            Some(separators) => {
+                let mut contexts = Vec::with_capacity(self.0.len() + separators.len());
+                for (index, value) in self.0.iter().enumerate() {
+                    contexts.push(value);
+                    if let Some(separator) = separators.get(index) {
+                        contexts.push(separator);
+                    }
+                }
+                contexts
+            }
+            None => self.0.iter().collect(),
+        }
+    }
+}
+
 impl LoopRule {
     pub fn new_min(rule: Box<dyn Rule>, min: usize, separator: Option<Box<dyn Rule>>) -> LoopRule {
         LoopRule {
@@ -6,15 +6,22 @@ use regex::Regex;
 pub struct Token<'a> {
     definition_index: usize,
     slice: &'a str,
-    position: (usize, usize),
+    position: (usize, usize), // (line, column)
+    span: (usize, usize),     // (start, end) byte offsets in the text
 }
 
 impl<'a> Token<'a> {
-    pub fn new(definition_index: usize, slice: &str, position: (usize, usize)) -> Token {
+    pub fn new(
+        definition_index: usize,
+        slice: &'a str,
+        position: (usize, usize),
+        span: (usize, usize),
+    ) -> Token<'a> {
         Token {
             definition_index,
             slice,
             position,
+            span,
         }
     }
 
@@ -30,6 +37,10 @@ impl<'a> Token<'a> {
         self.position
     }
 
+    pub fn span(&self) -> (usize, usize) {
+        self.span
+    }
+
     pub fn equal(&self, definition_index: usize) -> bool {
         self.definition_index == definition_index
     }
@@ -49,6 +60,7 @@ pub enum Pattern {
 pub struct TokenDefinition {
     name: Box<str>,
     pattern: Pattern,
+    skip: bool, // skipped tokens (whitespace, ...) are consumed but never given to the rules
 }
 
 impl TokenDefinition {
@@ -56,6 +68,7 @@ impl TokenDefinition {
         TokenDefinition {
             name: Box::from(name),
             pattern: Pattern::Regex(Regex::new(regex).unwrap()),
+            skip: false,
         }
     }
 
@@ -63,6 +76,7 @@ impl TokenDefinition {
         TokenDefinition {
             name: Box::from(name),
             pattern: Pattern::Keyword(Box::from(keyword)),
+            skip: false,
         }
     }
 
@@ -70,12 +84,72 @@ impl TokenDefinition {
         TokenDefinition {
             name: Box::from(name),
             pattern,
+            skip: false,
         }
     }
 
+    pub fn skipped(mut self) -> Self {
+        self.skip = true;
+        self
+    }
+
     pub fn name(&self) -> &str {
         self.name.as_ref()
     }
+
+    pub fn pattern(&self) -> &Pattern {
+        &self.pattern
+    }
+
+    pub fn is_skipped(&self) -> bool {
+        self.skip
+    }
+
+    // text of the token if it can only match one text (keywords, escaped regex)
+    pub fn literal(&self) -> Option<String> {
+        let regex = match &self.pattern {
+            Pattern::Keyword(str) => return Some(str.to_string()),
+            Pattern::Regex(regex) => regex.as_str(),
+        };
+
+        let mut literal = String::new();
+        let mut chars = regex.strip_prefix('^').unwrap_or(regex).chars();
+        while let Some(char) = chars.next() {
+            match char {
+                '\\' => match chars.next() {
+                    Some(escaped) if escaped.is_ascii_punctuation() => literal.push(escaped),
+                    _ => return None,
+                },
+                '.' | '[' | ']' | '(' | ')' | '{' | '}' | '*' | '+' | '?' | '|' | '^' | '$' => {
+                    return None
+                }
+                _ => literal.push(char),
+            }
+        }
+
+        if literal.is_empty() {
+            None
+        } else {
+            Some(literal)
+        }
+    }
+
+    // length of the match at the start of the text
+    fn match_len(&self, text: &str) -> Option<usize> {
+        match &self.pattern {
+            Pattern::Keyword(str) => {
+                if text.starts_with(str.as_ref()) {
+                    Some(str.len())
+                } else {
+                    None
+                }
+            }
+            Pattern::Regex(regex) => regex
+                .find(text)
+                .filter(|match_| match_.start() == 0)
+                .map(|match_| match_.end()),
+        }
+    }
 }
 
 // endregion
@@ -88,9 +162,13 @@ pub struct Tokenizer<'a> {
     pub(crate) current_text: &'a str,            // slice of text that need to be tokenize
     tokens_definition: &'a Vec<TokenDefinition>, // tokens definition
     tokens: Vec<Token<'a>>,                      // tokens
-    current_position: (usize, usize),            //position
+    current_position: (usize, usize),            // (line, column)
+    current_offset: usize,                       // byte offset of current_text in text
+    fragments: Vec<usize>,                       // fragments being parsed
+    expected_index: usize,                       // farthest index where a token was expected
+    expected: Vec<(usize, Option<usize>)>,       // (definition, fragment) expected at this index
 }
-// TODO: add WS token
+
 impl<'a> Tokenizer<'a> {
     pub fn new(text: &'a str, tokens_definition: &'a Vec<TokenDefinition>) -> Tokenizer<'a> {
         Tokenizer {
@@ -99,6 +177,10 @@ impl<'a> Tokenizer<'a> {
             tokens_definition,
             tokens: Vec::new(),
             current_position: (0, 0),
+            current_offset: 0,
+            fragments: Vec::new(),
+            expected_index: 0,
+            expected: Vec::new(),
         }
     }
 
@@ -117,14 +199,55 @@ impl<'a> Tokenizer<'a> {
         definition_index: usize,
     ) -> Option<&Token<'a>> {
         if index >= self.tokens.len() {
-            self.generate_token_from_token_definition(definition_index)
-        } else {
-            let token = &self.tokens[index];
-            if token.definition_index() == definition_index {
-                Some(token)
-            } else {
-                None
+            if self
+                .generate_token_from_token_definition(definition_index)
+                .is_none()
+            {
+                self.expect(index, definition_index);
+                return None;
             }
+            self.tokens.last()
+        } else if self.tokens[index].definition_index() == definition_index {
+            Some(&self.tokens[index])
+        } else {
+            self.expect(index, definition_index);
+            None
+        }
+    }
+
+    // the fragments being parsed, used to know where a token was expected
+    pub fn enter_fragment(&mut self, fragment_index: usize) {
+        self.fragments.push(fragment_index);
+    }
+
+    pub fn exit_fragment(&mut self) {
+        self.fragments.pop();
+    }
+
+    // farthest token index where a token did not match and the (definition, innermost fragment)
+    // expected there
+    pub fn get_expected(&self) -> (usize, &Vec<(usize, Option<usize>)>) {
+        (self.expected_index, &self.expected)
+    }
+
+    fn expect(&mut self, index: usize, definition_index: usize) {
+        if index > self.expected_index {
+            self.expected_index = index;
+            self.expected.clear();
+        }
+        let expected = (definition_index, self.fragments.last().copied());
+        if index == self.expected_index && !self.expected.contains(&expected) {
+            self.expected.push(expected);
+        }
+    }
+
+    // byte offset of the token at the index, or of the text left after the tokens
+    pub fn get_offset(&mut self, index: usize) -> usize {
+        if index < self.tokens.len() {
+            self.tokens[index].span().0
+        } else {
+            self.skip_tokens();
+            self.current_offset
         }
     }
 
@@ -133,6 +256,8 @@ impl<'a> Tokenizer<'a> {
     }
 
     pub fn generate_token(&mut self) -> Option<&Token<'a>> {
+        self.skip_tokens();
+
         // check if there is text to tokenize
         if self.current_text.is_empty() {
             return None;
@@ -140,38 +265,12 @@ impl<'a> Tokenizer<'a> {
 
         // try to match the text with a token definition
         for (definition_index, definition) in self.tokens_definition.iter().enumerate() {
-            match &definition.pattern {
-                Pattern::Keyword(str) => {
-                    if self.current_text.starts_with(str.as_ref()) {
-                        // make the token
-                        let token = Token::new(definition_index, str, self.current_position);
-                        self.tokens.push(token);
-
-                        // update state
-                        self.current_position.1 += str.len();
-
-                        // return the token
-                        return Some(self.tokens.last().unwrap());
-                    }
-                }
-                Pattern::Regex(regex) => {
-                    // try to match the text with the regex
-                    if let Some(match_) = regex.find(self.current_text) {
-                        // match string
-                        let match_str = match_.as_str();
-
-                        // make the token
-                        let token = Token::new(definition_index, match_str, self.current_position);
-                        self.tokens.push(token);
-
-                        // update state
-                        let last_index = match_.range().end;
-                        self.current_text = &self.current_text[last_index..];
-                        self.current_position.1 += last_index;
-
-                        // return the token
-                        return Some(self.tokens.last().unwrap());
-                    }
+            if definition.is_skipped() {
+                continue;
+            }
+            if let Some(len) = definition.match_len(self.current_text) {
+                if len > 0 {
+                    return Some(self.push_token(definition_index, len));
                 }
             }
         }
@@ -184,51 +283,94 @@ impl<'a> Tokenizer<'a> {
         &mut self,
         definition_index: usize,
     ) -> Option<&Token<'a>> {
-        // check if there is text to tokenize
-        match &self.tokens_definition[definition_index].pattern {
-            Pattern::Keyword(str) => {
-                if self.current_text.starts_with(str.as_ref()) {
-                    // make the token
-                    let token = Token::new(definition_index, str, self.current_position);
-                    self.tokens.push(token);
-
-                    // update state
-                    let len = str.len();
-                    self.current_position.1 += len;
-                    self.current_text = &self.current_text[len..];
-
-                    // return the token
-                    return Some(self.tokens.last().unwrap());
-                }
+        self.skip_tokens();
+
+        match self.tokens_definition[definition_index].match_len(self.current_text) {
+            Some(len) if len > 0 => Some(self.push_token(definition_index, len)),
+            // no token found
+            _ => None,
+        }
+    }
+
+    pub fn get_all_tokens(&mut self) -> &Vec<Token<'a>> {
+        while self.generate_token().is_some() {}
+        &self.tokens
+    }
+
+    // tokens of the whole text, the characters matched by no definition are ignored
+    pub fn get_all_tokens_lossy(&mut self) -> &Vec<Token<'a>> {
+        loop {
+            if self.generate_token().is_some() {
+                continue;
+            }
+            match self.current_text.chars().next() {
+                Some(char) => self.advance(char.len_utf8()),
+                None => break,
             }
-            Pattern::Regex(regex) => {
-                // try to match the text with the regex
-                if let Some(match_) = regex.find(self.current_text) {
-                    // match string
-                    let match_str = match_.as_str();
-
-                    // make the token
-                    let token = Token::new(definition_index, match_str, self.current_position);
-                    self.tokens.push(token);
-
-                    // update state
-                    let last_index = match_.range().end;
-                    self.current_text = &self.current_text[last_index..];
-                    self.current_position.1 = last_index;
-
-                    // return the token
-                    return Some(self.tokens.last().unwrap());
+        }
+        &self.tokens
+    }
+
+    // true if nothing but skipped tokens follows the first `index` tokens
+    pub fn is_end(&mut self, index: usize) -> bool {
+        if index < self.tokens.len() {
+            return false;
+        }
+        self.skip_tokens();
+        self.current_text.is_empty()
+    }
+
+    // text not matched by any token (empty when all the text is tokenized)
+    pub fn remaining_text(&self) -> &'a str {
+        self.current_text
+    }
+
+    pub fn text(&self) -> &'a str {
+        self.text
+    }
+
+    fn skip_tokens(&mut self) {
+        'skip: loop {
+            for definition in self.tokens_definition.iter() {
+                if !definition.is_skipped() {
+                    continue;
+                }
+                if let Some(len) = definition.match_len(self.current_text) {
+                    if len > 0 {
+                        self.advance(len);
+                        continue 'skip;
+                    }
                 }
             }
+            break;
         }
+    }
 
-        // no token found
-        None
+    fn push_token(&mut self, definition_index: usize, len: usize) -> &Token<'a> {
+        // make the token
+        let slice = &self.current_text[..len];
+        let span = (self.current_offset, self.current_offset + len);
+        let token = Token::new(definition_index, slice, self.current_position, span);
+        self.tokens.push(token);
+
+        // update state
+        self.advance(len);
+
+        // return the token
+        self.tokens.last().unwrap()
     }
 
-    pub fn get_all_tokens(&mut self) -> &Vec<Token<'a>> {
-        while self.generate_token().is_some() {}
-        &self.tokens
+    fn advance(&mut self, len: usize) {
+        let consumed = &self.current_text[..len];
+        match consumed.rfind('\n') {
+            Some(index) => {
+                self.current_position.0 += consumed.matches('\n').count();
+                self.current_position.1 = consumed[index + 1..].chars().count();
+            }
+            None => self.current_position.1 += consumed.chars().count(),
+        }
+        self.current_offset += len;
+        self.current_text = &self.current_text[len..];
     }
 }
 
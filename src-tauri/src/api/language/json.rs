@@ -1,9 +1,20 @@
+use crate::api::folding::FoldingRule;
+use crate::api::format::FormatRules;
 use crate::api::grammar::grammar::Grammar;
 use crate::api::grammar::json_to_grammar::json_to_grammar;
+use crate::api::language::Language;
+use crate::api::outline::OutlineRule;
 use serde_json::json;
 
 // url: https://www.json.org/json-en.html
-//TODO: WS, escape characters (\n, \t, \u, \x,...)
+//TODO: escape characters (\n, \t, \u, \x,...)
+
+pub fn get_json_language() -> Language {
+    Language::new(get_json_grammar())
+        .with_format_rules(get_json_format_rules())
+        .with_outline_rules(get_json_outline_rules())
+        .with_folding_rules(get_json_folding_rules())
+}
 
 pub fn get_json_grammar() -> Grammar {
     let grammar_json = json!({
@@ -14,7 +25,7 @@ pub fn get_json_grammar() -> Grammar {
             "NULL": "null",
             "TRUE": "true",
             "FALSE": "false",
-            "STRING": "\"((\\\")|[^\"])*\"", // " (\" or not quote)* "
+            "STRING": "\"(\\\\.|[^\"\\\\])*\"", // " (escaped char or not quote)* "
 
             "LEFT_BRACKET": "\\[",
             "RIGHT_BRACKET": "\\]",
@@ -22,7 +33,14 @@ pub fn get_json_grammar() -> Grammar {
             "RIGHT_BRACE": "\\}",
             "COLON": ":",
             "COMMA": ",",
+
+            "WS": { "regex": "[ \\t\\r\\n]+", "skip": true },
         },
+        "brackets": [
+            ["LEFT_BRACE", "RIGHT_BRACE"],
+            ["LEFT_BRACKET", "RIGHT_BRACKET"]
+        ],
+        "autoClosingPairs": [["{", "}"], ["[", "]"], ["\"", "\""]],
         "fragments": {
             "value": {
                 "main": true,
@@ -44,16 +62,19 @@ pub fn get_json_grammar() -> Grammar {
                     "LEFT_BRACE",
                     {
                         "type": "loop",
-                        "value": [
-                                "STRING",
-                                "COLON",
-                                "value"
-                            ],
+                        "value": "member",
                         "separator": "COMMA"
                     },
                     "RIGHT_BRACE"
                 ]
             },
+            "member": {
+                "rule": [
+                    "STRING",
+                    "COLON",
+                    "value"
+                ]
+            },
             "array": {
                 "rule": [
                     "LEFT_BRACKET",
@@ -70,3 +91,36 @@ pub fn get_json_grammar() -> Grammar {
 
     json_to_grammar(grammar_json).unwrap()
 }
+
+pub fn get_json_format_rules() -> FormatRules {
+    let rules_json = json!({
+        "indent": "    ",
+        "lineWidth": 80,
+        "groups": [
+            ["LEFT_BRACE", "RIGHT_BRACE"],
+            ["LEFT_BRACKET", "RIGHT_BRACKET"]
+        ],
+        "separators": ["COMMA"],
+        "spaceAfter": ["COLON", "COMMA"],
+        "finalNewline": true,
+    });
+
+    serde_json::from_value(rules_json).unwrap()
+}
+
+pub fn get_json_outline_rules() -> Vec<OutlineRule> {
+    let rules_json = json!([
+        { "fragment": "member", "kind": "key", "name": "STRING", "trim": "\"" },
+    ]);
+
+    serde_json::from_value(rules_json).unwrap()
+}
+
+pub fn get_json_folding_rules() -> Vec<FoldingRule> {
+    let rules_json = json!([
+        { "fragment": "object", "kind": "region" },
+        { "fragment": "array", "kind": "region" },
+    ]);
+
+    serde_json::from_value(rules_json).unwrap()
+}
@@ -0,0 +1,79 @@
+use crate::api::folding::FoldingRule;
+use crate::api::grammar::grammar::Grammar;
+use crate::api::grammar::json_to_grammar::json_to_grammar;
+use crate::api::language::Language;
+use crate::api::outline::OutlineRule;
+use serde_json::json;
+
+// url: https://spec.commonmark.org/
+// only the line structure is parsed: frontmatter, fenced code blocks, ATX headings and text lines
+//TODO: setext headings, lists, block quotes, ...
+
+pub fn get_markdown_language() -> Language {
+    Language::new(get_markdown_grammar())
+        .with_outline_rules(get_markdown_outline_rules())
+        .with_folding_rules(get_markdown_folding_rules())
+}
+
+pub fn get_markdown_grammar() -> Grammar {
+    let grammar_json = json!({
+        "grammarName": "Markdown",
+        "tokenDefinitions": {
+            "FRONTMATTER": r"(?m)---[ \t]*\r?\n(?s:.*?\n)?---[ \t]*\r?$", // --- yaml ---
+            "FENCE": r"(?m)(```[^\n]*\n(?s:.*?\n)?```|~~~[^\n]*\n(?s:.*?\n)?~~~)[^\n]*$", // ``` code ```
+            "HEADING": r"(?m)#{1,6}([ \t][^\n]*)?$", // # title
+            "TEXT": r"[^\r\n]+",
+            "NEWLINE": r"\r?\n",
+        },
+        "autoClosingPairs": [["[", "]"], ["(", ")"], ["`", "`"], ["\"", "\""]],
+        "fragments": {
+            "document": {
+                "main": true,
+                "rule": [
+                    {
+                        "type": "optional",
+                        "value": "FRONTMATTER"
+                    },
+                    {
+                        "type": "loop",
+                        "value": "line",
+                        "separator": "NEWLINE"
+                    }
+                ]
+            },
+            "line": {
+                "rule": {
+                    "type": "optional",
+                    "value": {
+                        "type": "or",
+                        "values": [
+                            "FENCE",
+                            "HEADING",
+                            "TEXT"
+                        ]
+                    }
+                }
+            },
+        },
+    });
+
+    json_to_grammar(grammar_json).unwrap()
+}
+
+pub fn get_markdown_outline_rules() -> Vec<OutlineRule> {
+    let rules_json = json!([
+        { "token": "HEADING", "kind": "heading", "level": "#" },
+    ]);
+
+    serde_json::from_value(rules_json).unwrap()
+}
+
+pub fn get_markdown_folding_rules() -> Vec<FoldingRule> {
+    let rules_json = json!([
+        { "token": "FRONTMATTER", "kind": "frontmatter" },
+        { "token": "FENCE", "kind": "code" },
+        { "token": "HEADING", "kind": "section", "level": "#" },
+    ]);
+
+    serde_json::from_value(rules_json).unwrap()
+}
@@ -1 +1,72 @@
 pub(crate) mod json;
+pub(crate) mod markdown;
+pub(crate) mod toml;
+
+use crate::api::folding::FoldingRule;
+use crate::api::format::FormatRules;
+use crate::api::grammar::grammar::Grammar;
+use crate::api::outline::OutlineRule;
+
+// region: ---Language
+
+#[derive(Debug)]
+pub struct Language {
+    grammar: Grammar,
+    format_rules: Option<FormatRules>,
+    outline_rules: Vec<OutlineRule>,
+    folding_rules: Vec<FoldingRule>,
+}
+
+impl Language {
+    pub fn new(grammar: Grammar) -> Self {
+        Language {
+            grammar,
+            format_rules: None,
+            outline_rules: Vec::new(),
+            folding_rules: Vec::new(),
+        }
+    }
+
+    pub fn with_format_rules(mut self, format_rules: FormatRules) -> Self {
+        self.format_rules = Some(format_rules);
+        self
+    }
+
+    pub fn with_outline_rules(mut self, outline_rules: Vec<OutlineRule>) -> Self {
+        self.outline_rules = outline_rules;
+        self
+    }
+
+    pub fn with_folding_rules(mut self, folding_rules: Vec<FoldingRule>) -> Self {
+        self.folding_rules = folding_rules;
+        self
+    }
+
+    pub fn get_grammar(&self) -> &Grammar {
+        &self.grammar
+    }
+
+    pub fn get_format_rules(&self) -> Option<&FormatRules> {
+        self.format_rules.as_ref()
+    }
+
+    pub fn get_outline_rules(&self) -> &Vec<OutlineRule> {
+        &self.outline_rules
+    }
+
+    pub fn get_folding_rules(&self) -> &Vec<FoldingRule> {
+        &self.folding_rules
+    }
+}
+
+// language from its name or the extension of its files
+pub fn get_language(name: &str) -> Option<Language> {
+    match name.to_lowercase().as_str() {
+        "json" => Some(json::get_json_language()),
+        "markdown" | "md" => Some(markdown::get_markdown_language()),
+        "toml" => Some(toml::get_toml_language()),
+        _ => None,
+    }
+}
+
+// endregion
@@ -0,0 +1,171 @@
+use crate::api::folding::FoldingRule;
+use crate::api::grammar::grammar::Grammar;
+use crate::api::grammar::json_to_grammar::json_to_grammar;
+use crate::api::language::Language;
+use crate::api::outline::OutlineRule;
+use serde_json::json;
+
+// url: https://toml.io/en/v1.0.0
+
+pub fn get_toml_language() -> Language {
+    Language::new(get_toml_grammar())
+        .with_outline_rules(get_toml_outline_rules())
+        .with_folding_rules(get_toml_folding_rules())
+}
+
+pub fn get_toml_grammar() -> Grammar {
+    let grammar_json = json!({
+        "grammarName": "TOML",
+        "tokenDefinitions": {
+            "WS": { "regex": r"[ \t\r\n]+", "skip": true },
+            "COMMENT": { "regex": r"#[^\n]*", "skip": true },
+
+            "ML_BASIC_STRING": r#""""(?s:.*?)""""#, // """ multi-line """
+            "ML_LITERAL_STRING": r"'''(?s:.*?)'''",  // ''' multi-line '''
+            "BASIC_STRING": r#""(\\.|[^"\\\n])*""#,  // " (escaped char or not quote)* "
+            "LITERAL_STRING": r"'[^'\n]*'",
+            "DATETIME": r"\d{4}-\d{2}-\d{2}([Tt ]\d{2}:\d{2}:\d{2}(\.\d+)?([Zz]|[+-]\d{2}:\d{2})?)?|\d{2}:\d{2}:\d{2}(\.\d+)?",
+            "NUMBER": r"[+-]?(inf|nan|0x[0-9A-Fa-f_]+|0o[0-7_]+|0b[01_]+|\d[\d_]*(\.\d[\d_]*)?([eE][+-]?\d[\d_]*)?)",
+            "BOOLEAN": "(true|false)",
+            "BARE_KEY": r"[A-Za-z0-9_-]+",
+
+            "DOUBLE_LEFT_BRACKET": r"\[\[",
+            "DOUBLE_RIGHT_BRACKET": r"\]\]",
+            "LEFT_BRACKET": r"\[",
+            "RIGHT_BRACKET": r"\]",
+            "LEFT_BRACE": r"\{",
+            "RIGHT_BRACE": r"\}",
+            "EQUAL": "=",
+            "DOT": r"\.",
+            "COMMA": ",",
+        },
+        "brackets": [
+            ["DOUBLE_LEFT_BRACKET", "DOUBLE_RIGHT_BRACKET"],
+            ["LEFT_BRACKET", "RIGHT_BRACKET"],
+            ["LEFT_BRACE", "RIGHT_BRACE"]
+        ],
+        "autoClosingPairs": [["{", "}"], ["[", "]"], ["\"", "\""], ["'", "'"]],
+        "fragments": {
+            "document": {
+                "main": true,
+                "rule": [
+                    {
+                        "type": "loop",
+                        "value": "pair"
+                    },
+                    {
+                        "type": "loop",
+                        "value": {
+                            "type": "or",
+                            // array_table first: a table would take the first [ of [[
+                            "values": ["array_table", "table"]
+                        }
+                    }
+                ]
+            },
+            "table": {
+                "rule": [
+                    "LEFT_BRACKET",
+                    "key",
+                    "RIGHT_BRACKET",
+                    {
+                        "type": "loop",
+                        "value": "pair"
+                    }
+                ]
+            },
+            "array_table": {
+                "rule": [
+                    "DOUBLE_LEFT_BRACKET",
+                    "key",
+                    "DOUBLE_RIGHT_BRACKET",
+                    {
+                        "type": "loop",
+                        "value": "pair"
+                    }
+                ]
+            },
+            "pair": {
+                "rule": ["key", "EQUAL", "value"]
+            },
+            "key": {
+                "rule": {
+                    "type": "loop",
+                    "value": {
+                        "type": "or",
+                        "values": ["BARE_KEY", "BASIC_STRING", "LITERAL_STRING"]
+                    },
+                    "separator": "DOT",
+                    "min": 1
+                }
+            },
+            "value": {
+                "rule": {
+                    "type": "or",
+                    "values": [
+                        "ML_BASIC_STRING",
+                        "ML_LITERAL_STRING",
+                        "BASIC_STRING",
+                        "LITERAL_STRING",
+                        "DATETIME",
+                        "NUMBER",
+                        "BOOLEAN",
+                        "array",
+                        "inline_table"
+                    ]
+                }
+            },
+            "array": {
+                "rule": [
+                    "LEFT_BRACKET",
+                    {
+                        "type": "loop",
+                        "value": "value",
+                        "separator": "COMMA"
+                    },
+                    {
+                        "type": "optional",
+                        "value": "COMMA"
+                    },
+                    "RIGHT_BRACKET"
+                ]
+            },
+            "inline_table": {
+                "rule": [
+                    "LEFT_BRACE",
+                    {
+                        "type": "loop",
+                        "value": "pair",
+                        "separator": "COMMA"
+                    },
+                    "RIGHT_BRACE"
+                ]
+            },
+        },
+    });
+
+    json_to_grammar(grammar_json).unwrap()
+}
+
+pub fn get_toml_outline_rules() -> Vec<OutlineRule> {
+    let rules_json = json!([
+        { "fragment": "table", "kind": "table", "name": "key" },
+        { "fragment": "array_table", "kind": "array", "name": "key" },
+        { "fragment": "pair", "kind": "key", "name": "key" },
+    ]);
+
+    serde_json::from_value(rules_json).unwrap()
+}
+
+pub fn get_toml_folding_rules() -> Vec<FoldingRule> {
+    let rules_json = json!([
+        { "fragment": "table", "kind": "region" },
+        { "fragment": "array_table", "kind": "region" },
+        { "fragment": "array", "kind": "region" },
+        { "fragment": "inline_table", "kind": "region" },
+        { "token": "ML_BASIC_STRING", "kind": "region" },
+        { "token": "ML_LITERAL_STRING", "kind": "region" },
+    ]);
+
+    serde_json::from_value(rules_json).unwrap()
+}
@@ -1,3 +1,11 @@
+pub mod brackets;
+pub mod completion;
+pub mod diff;
+pub mod folding;
+pub mod format;
 pub mod fs;
 pub(crate) mod grammar;
 pub(crate) mod language;
+pub mod outline;
+pub mod search;
+pub mod workspace;
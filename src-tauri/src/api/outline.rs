@@ -0,0 +1,325 @@
+use crate::api::grammar::grammar::Grammar;
+use crate::api::grammar::rules::Context;
+use crate::api::language::{get_language, Language};
+use std::fmt::{Display, Formatter};
+
+// region: ---Tauri Command
+
+#[tauri::command]
+pub async fn get_outline(text: &str, language: &str) -> Result<Vec<OutlineEntry>, String> {
+    let language = get_language(language).ok_or(Error::UnknownLanguage)?;
+    outline(&language, text).map_err(|err| err.to_string())
+}
+
+// endregion
+
+// region: ---Error
+#[derive(Debug)]
+pub enum Error {
+    UnknownLanguage,
+    UnknownTokenOrFragment(Box<str>),
+    InvalidText,
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::UnknownLanguage => write!(f, "unknown language"),
+            Error::UnknownTokenOrFragment(name) => {
+                write!(f, "unknown token or fragment in outline rules: {}", name)
+            }
+            Error::InvalidText => write!(f, "the text does not match the grammar"),
+        }
+    }
+}
+
+impl From<Error> for String {
+    fn from(err: Error) -> Self {
+        err.to_string()
+    }
+}
+
+// endregion
+
+// region: ---Outline Rule
+
+/// # OutlineRule
+/// {
+///     fragment?: string,  // fragment or token giving an entry
+///     token?: string,
+///     kind: string,       // "heading", "key", "table", ...
+///     name?: string,      // token or fragment inside the entry giving its name (default: all the entry)
+///     trim?: string,      // characters removed around the name
+///     level?: char,       // entries are nested by the number of this char at their start (# for markdown),
+///                         // the run of this char is removed from the start of the name
+/// }
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutlineRule {
+    pub fragment: Option<Box<str>>,
+    pub token: Option<Box<str>>,
+    pub kind: Box<str>,
+    pub name: Option<Box<str>>,
+    #[serde(default)]
+    pub trim: Box<str>,
+    pub level: Option<char>,
+}
+
+// fragment or token of the grammar
+#[derive(Debug, PartialEq)]
+pub(crate) enum Target {
+    Fragment(usize),
+    Token(usize),
+}
+
+impl Target {
+    pub(crate) fn new(name: &str, grammar: &Grammar) -> Result<Self, Error> {
+        if let Some(index) = grammar.get_fragment_index(name) {
+            Ok(Target::Fragment(index))
+        } else if let Some(index) = grammar.get_token_definition_index(name) {
+            Ok(Target::Token(index))
+        } else {
+            Err(Error::UnknownTokenOrFragment(Box::from(name)))
+        }
+    }
+
+    pub(crate) fn matches(&self, context: &Context) -> bool {
+        match (self, context) {
+            (Target::Fragment(index), Context::Fragment(fragment_context)) => {
+                fragment_context.fragment_index() == *index
+            }
+            (Target::Token(index), Context::Token(token_context)) => {
+                token_context.definition_index() == *index
+            }
+            _ => false,
+        }
+    }
+}
+
+// outline rule with the names replaced by their index in the grammar
+struct ResolvedRule<'a> {
+    rule: &'a OutlineRule,
+    target: Target,
+    name: Option<Target>,
+}
+
+impl<'a> ResolvedRule<'a> {
+    fn new(rule: &'a OutlineRule, grammar: &Grammar) -> Result<Self, Error> {
+        let target = match (&rule.fragment, &rule.token) {
+            (Some(fragment), _) => Target::new(fragment, grammar)?,
+            (None, Some(token)) => Target::new(token, grammar)?,
+            (None, None) => return Err(Error::UnknownTokenOrFragment(Box::from(""))),
+        };
+        let name = rule
+            .name
+            .as_ref()
+            .map(|name| Target::new(name, grammar))
+            .transpose()?;
+
+        Ok(ResolvedRule { rule, target, name })
+    }
+
+    fn entry(&self, context: &Context, children: Vec<OutlineEntry>) -> OutlineEntry {
+        let name_context = self
+            .name
+            .as_ref()
+            .and_then(|name| find_context(context, name))
+            .unwrap_or(context);
+        let text = context_text(name_context);
+        let mut name = text.trim();
+        if let Some(level) = self.rule.level {
+            name = name.trim_start_matches(level);
+        }
+        let name = name.trim_matches(|c: char| self.rule.trim.contains(c) || c.is_whitespace());
+
+        let level = self.rule.level.map(|level| {
+            context_text(context)
+                .chars()
+                .take_while(|c| *c == level)
+                .count()
+        });
+
+        OutlineEntry {
+            name: name.to_string(),
+            kind: self.rule.kind.clone(),
+            span: context.span().unwrap_or((0, 0)),
+            level,
+            children,
+        }
+    }
+}
+
+// first context (the context itself included) matched by the target
+fn find_context<'a>(context: &'a Context, target: &Target) -> Option<&'a Context> {
+    if target.matches(context) {
+        return Some(context);
+    }
+    context
+        .children()
+        .into_iter()
+        .find_map(|child| find_context(child, target))
+}
+
+pub(crate) fn context_text(context: &Context) -> String {
+    context
+        .tokens()
+        .iter()
+        .map(|token| token.text())
+        .collect::<Vec<&str>>()
+        .concat()
+}
+
+// endregion
+
+// region: ---Outline
+
+#[derive(Debug, serde::Serialize)]
+pub struct OutlineEntry {
+    pub name: String,
+    pub kind: Box<str>,
+    pub span: (usize, usize), // (start, end) byte offsets
+    #[serde(skip)]
+    pub level: Option<usize>,
+    pub children: Vec<OutlineEntry>,
+}
+
+pub fn outline(language: &Language, text: &str) -> Result<Vec<OutlineEntry>, Error> {
+    let grammar = language.get_grammar();
+    let rules = language
+        .get_outline_rules()
+        .iter()
+        .map(|rule| ResolvedRule::new(rule, grammar))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let context = grammar.parse(text).ok_or(Error::InvalidText)?;
+    let mut entries = Vec::new();
+    collect_entries(&context, &rules, &mut entries);
+    Ok(nest_levels(entries, text.len()))
+}
+
+fn collect_entries(context: &Context, rules: &[ResolvedRule], entries: &mut Vec<OutlineEntry>) {
+    match rules.iter().find(|rule| rule.target.matches(context)) {
+        Some(rule) => {
+            let mut children = Vec::new();
+            for child in context.children() {
+                collect_entries(child, rules, &mut children);
+            }
+            let end = context.span().map(|span| span.1).unwrap_or(0);
+            entries.push(rule.entry(context, nest_levels(children, end)));
+        }
+        None => {
+            for child in context.children() {
+                collect_entries(child, rules, entries);
+            }
+        }
+    }
+}
+
+// nest the entries having a level under the previous entry with a lower level,
+// an entry with a level spans until the next entry with the same or a lower level (or `end`)
+fn nest_levels(entries: Vec<OutlineEntry>, end: usize) -> Vec<OutlineEntry> {
+    let mut roots: Vec<OutlineEntry> = Vec::new();
+    let mut stack: Vec<OutlineEntry> = Vec::new();
+
+    for entry in entries {
+        if let Some(level) = entry.level {
+            while stack.last().is_some_and(|top| top.level >= Some(level)) {
+                let mut top = stack.pop().unwrap();
+                top.span.1 = entry.span.0;
+                push_entry(&mut roots, &mut stack, top);
+            }
+            stack.push(entry);
+        } else {
+            push_entry(&mut roots, &mut stack, entry);
+        }
+    }
+
+    while let Some(mut top) = stack.pop() {
+        top.span.1 = top.span.1.max(end);
+        push_entry(&mut roots, &mut stack, top);
+    }
+
+    roots
+}
+
+fn push_entry(roots: &mut Vec<OutlineEntry>, stack: &mut [OutlineEntry], entry: OutlineEntry) {
+    match stack.last_mut() {
+        Some(parent) => parent.children.push(entry),
+        None => roots.push(entry),
+    }
+}
+
+// endregion
+
+// region: ---Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the names of the entries, with their children in parentheses
+    fn names(entries: &[OutlineEntry]) -> String {
+        entries
+            .iter()
+            .map(|entry| match entry.children.is_empty() {
+                true => entry.name.clone(),
+                false => format!("{}({})", entry.name, names(&entry.children)),
+            })
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    fn outline_of(language: &str, text: &str) -> Vec<OutlineEntry> {
+        outline(&get_language(language).unwrap(), text).unwrap()
+    }
+
+    #[test]
+    fn markdown_headings_are_nested_by_level() {
+        let text = "# A\ntext\n## B\n### C\n## D\n# E\n";
+        let entries = outline_of("markdown", text);
+        assert_eq!(names(&entries), "A(B(C),D),E");
+        assert_eq!(entries[0].kind.as_ref(), "heading");
+    }
+
+    #[test]
+    fn markdown_headings_keep_their_last_char() {
+        let entries = outline_of(
+            "markdown",
+            "## Intro to C#
+#	F#
+",
+        );
+        assert_eq!(names(&entries), "Intro to C#,F#");
+    }
+
+    #[test]
+    fn markdown_sections_span_until_the_next_heading() {
+        let text = "# A\ntext\n## B\n# C";
+        let entries = outline_of("md", text);
+        assert_eq!(entries[0].span, (0, text.find("# C").unwrap()));
+        assert_eq!(entries[0].children[0].span, (9, 14));
+        assert_eq!(entries[1].span, (14, text.len()));
+    }
+
+    #[test]
+    fn json_keys_are_trimmed_and_nested() {
+        let entries = outline_of("json", r#"{"a": {"b": 1}, "c": [{"d": 2}]}"#);
+        assert_eq!(names(&entries), "a(b),c(d)");
+        assert_eq!(entries[0].kind.as_ref(), "key");
+    }
+
+    #[test]
+    fn toml_tables_contain_their_keys() {
+        let entries = outline_of("toml", "title = \"x\"\n\n[server]\nport = 80\n");
+        assert_eq!(names(&entries), "title,server(port)");
+        assert_eq!(entries[1].kind.as_ref(), "table");
+    }
+
+    #[test]
+    fn invalid_text_is_an_error() {
+        let result = outline(&get_language("json").unwrap(), "{\"a\"");
+        assert!(matches!(result, Err(Error::InvalidText)));
+    }
+}
+
+// endregion
@@ -0,0 +1,895 @@
+use super::tokenizer::{tokenize, tokenize_markdown, tokenize_plain};
+use crate::api::fs::listing::{walk_files, ListOptions, Listing};
+use crate::api::fs::text::{read_text, write_atomic};
+use crate::api::fs::vfs;
+use crate::api::workspace::WorkspaceListener;
+use crate::error::Error;
+use crate::event::FsChange;
+use crate::APP_DATA_FOLDER_NAME;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::UNIX_EPOCH;
+use tauri::api::path::data_dir;
+use tauri::State;
+use xxhash_rust::xxh3::xxh3_64;
+
+const INDEX_FOLDER_NAME: &str = "index";
+const SNAPSHOT_FILE_NAME: &str = "snapshot.json.gz";
+const LOG_FILE_NAME: &str = "log.jsonl";
+
+// the snapshots of another version are rebuilt
+const INDEX_VERSION: u32 = 1;
+
+// the log is merged in the snapshot past this number of records
+const COMPACT_AFTER: usize = 1000;
+
+// the records are appended to the log file in batches of this size, and when the index is flushed
+const LOG_BUFFER_SIZE: usize = 64 * 1024;
+
+// larger files are not indexed
+const MAX_FILE_SIZE: u64 = 4 * 1024 * 1024;
+
+const DEFAULT_LIMIT: usize = 50;
+
+// terms of a prefix query
+const MAX_PREFIX_TERMS: usize = 200;
+
+// bm25 parameters, and weight of a term in a heading or a tag on top of its count in the body
+const K1: f64 = 1.2;
+const B: f64 = 0.75;
+const HEADING_BOOST: f64 = 3.0;
+const TAG_BOOST: f64 = 5.0;
+
+// region: ---Tauri Command
+
+/// # query
+/// words are all required: `word`, `prefix*`, `"a phrase"`, `#tag`
+#[tauri::command]
+pub async fn search_index(
+    query: &str,
+    limit: Option<usize>,
+    index: State<'_, SearchIndex>,
+) -> Result<Vec<IndexHit>, Error> {
+    let index = index.index.lock().unwrap();
+    let index = index.as_ref().ok_or_else(no_index)?;
+    Ok(index.search(query, limit.unwrap_or(DEFAULT_LIMIT)))
+}
+
+// forget the index of the workspace and index every file again in the background
+#[tauri::command]
+pub async fn rebuild_index(index: State<'_, SearchIndex>) -> Result<(), Error> {
+    index.rebuild()
+}
+
+#[tauri::command]
+pub async fn get_index_status(index: State<'_, SearchIndex>) -> Result<IndexStatus, Error> {
+    let syncing = index.syncing.load(Ordering::Relaxed);
+    let index = index.index.lock().unwrap();
+    let index = index.as_ref().ok_or_else(no_index)?;
+    Ok(IndexStatus {
+        documents: index.ids.len(),
+        terms: index.terms.len(),
+        syncing,
+    })
+}
+
+fn no_index() -> Error {
+    Error::InvalidPath(String::from("no workspace is open"))
+}
+
+// endregion
+
+// region: ---Search Index
+
+#[derive(Debug, serde::Serialize)]
+pub struct IndexHit {
+    pub path: String,
+    pub relative_path: String,
+    pub title: Option<String>,
+    pub score: f64,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct IndexStatus {
+    pub documents: usize,
+    pub terms: usize,
+    pub syncing: bool, // the files changed while the app was closed are being indexed
+}
+
+// index of the open workspace, kept up to date by the workspace watcher
+#[derive(Clone, Default)]
+pub struct SearchIndex {
+    index: Arc<Mutex<Option<Index>>>,
+    generation: Arc<AtomicUsize>, // a sync stops when the index is opened again
+    syncing: Arc<AtomicBool>,
+}
+
+impl SearchIndex {
+    fn open(&self, root: &Path) {
+        let generation = self.generation.fetch_add(1, Ordering::Relaxed) + 1;
+        let index = Index::open(root, &index_folder(root)).unwrap_or_else(|err| {
+            eprintln!("failed to open the index of {}: {}", root.display(), err);
+            Index::empty(root, &index_folder(root))
+        });
+        *self.index.lock().unwrap() = Some(index);
+        self.sync_in_background(generation);
+    }
+
+    fn rebuild(&self) -> Result<(), Error> {
+        let generation = self.generation.fetch_add(1, Ordering::Relaxed) + 1;
+        self.index
+            .lock()
+            .unwrap()
+            .as_mut()
+            .ok_or_else(no_index)?
+            .clear()?;
+        self.sync_in_background(generation);
+        Ok(())
+    }
+
+    fn sync_in_background(&self, generation: usize) {
+        let search_index = self.clone();
+        thread::spawn(move || {
+            search_index.syncing.store(true, Ordering::Relaxed);
+            if let Err(err) = search_index.sync(generation) {
+                eprintln!("failed to index the workspace: {}", err);
+            }
+            if search_index.generation.load(Ordering::Relaxed) == generation {
+                search_index.syncing.store(false, Ordering::Relaxed);
+            }
+        });
+    }
+
+    // index the files modified since the last sync and forget the removed ones
+    fn sync(&self, generation: usize) -> Result<(), Error> {
+        let root = match self.index.lock().unwrap().as_ref() {
+            Some(index) => index.root.clone(),
+            None => return Ok(()),
+        };
+
+        let mut seen = HashSet::new();
+        let is_current = || self.generation.load(Ordering::Relaxed) == generation;
+        walk_files(&root, &ListOptions::default(), |path| {
+            if !is_current() {
+                return false;
+            }
+            let relative_path = relative_path(&root, path);
+            let modified = modified_time(path);
+            seen.insert(relative_path.clone());
+
+            let up_to_date =
+                self.with_index(|index| index.modified(&relative_path) == Some(modified));
+            if up_to_date == Some(false) {
+                // the file is read without the lock, the searches go on meanwhile
+                let document = read_document(&root, path);
+                self.with_index(|index| match document {
+                    Some(document) => index.put(document),
+                    None => index.remove(&relative_path),
+                });
+            }
+            true
+        })?;
+        if !is_current() {
+            return Ok(());
+        }
+
+        self.with_index(|index| {
+            let removed: Vec<String> = index
+                .ids
+                .keys()
+                .filter(|path| !seen.contains(*path))
+                .cloned()
+                .collect();
+            for path in removed {
+                index.remove(&path);
+            }
+            index.flush()
+        })
+        .unwrap_or(Ok(()))
+    }
+
+    // None when the index was closed or replaced
+    fn with_index<T, F: FnOnce(&mut Index) -> T>(&self, f: F) -> Option<T> {
+        self.index.lock().unwrap().as_mut().map(f)
+    }
+
+    fn update(&self, root: &Path, changes: &[FsChange]) {
+        let mut index = self.index.lock().unwrap();
+        let index = match index.as_mut() {
+            Some(index) if index.root == root => index,
+            _ => return,
+        };
+        let mut listing = match Listing::new(root, ListOptions::default()) {
+            Ok(listing) => listing,
+            Err(_) => return,
+        };
+
+        for change in changes {
+            let (removed, added) = match change {
+                FsChange::Created { path } | FsChange::Modified { path } => (None, Some(path)),
+                FsChange::Removed { path } => (Some(path), None),
+                FsChange::Renamed { from, to } => (Some(from), Some(to)),
+            };
+            if let Some(path) = removed {
+                index.remove_folder(&relative_path(root, Path::new(path)));
+            }
+            if let Some(path) = added {
+                index.add_path(&mut listing, Path::new(path));
+            }
+        }
+        if let Err(err) = index.flush() {
+            eprintln!("failed to save the index: {}", err);
+        }
+    }
+}
+
+impl WorkspaceListener for SearchIndex {
+    fn opened(&self, root: &Path) {
+        self.open(root);
+    }
+
+    fn changed(&self, root: &Path, changes: &[FsChange]) {
+        self.update(root, changes);
+    }
+}
+
+// the index of every workspace has its folder in the app data folder
+// (named by a hash stable across the versions of rust)
+fn index_folder(root: &Path) -> PathBuf {
+    let hash = xxh3_64(root.as_os_str().as_encoded_bytes());
+    data_dir()
+        .unwrap_or_default()
+        .join(APP_DATA_FOLDER_NAME)
+        .join(INDEX_FOLDER_NAME)
+        .join(format!("{:016x}", hash))
+}
+
+fn relative_path(root: &Path, path: &Path) -> String {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+fn modified_time(path: &Path) -> u64 {
+    vfs::stat(path)
+        .ok()
+        .and_then(|metadata| metadata.modified)
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |duration| duration.as_millis() as u64)
+}
+
+// None for the binary, unreadable and too large files
+fn read_document(root: &Path, path: &Path) -> Option<Document> {
+    let modified = modified_time(path);
+    if vfs::stat(path).ok()?.len > MAX_FILE_SIZE {
+        return None;
+    }
+    let text = read_text(path, false).ok()?.text;
+    let is_markdown = path.extension().is_some_and(|extension| {
+        extension.eq_ignore_ascii_case("md") || extension.eq_ignore_ascii_case("markdown")
+    });
+    let tokenized = match is_markdown {
+        true => tokenize_markdown(&text),
+        false => tokenize_plain(&text),
+    };
+
+    Some(Document {
+        path: relative_path(root, path),
+        modified,
+        title: tokenized.title,
+        body: tokenized.body,
+        headings: tokenized.headings,
+        tags: tokenized.tags,
+    })
+}
+
+// endregion
+
+// region: ---Index
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+struct Document {
+    path: String, // relative to the workspace root
+    modified: u64,
+    title: Option<String>,
+    body: Vec<String>,
+    headings: Vec<String>,
+    tags: Vec<String>,
+}
+
+#[derive(Debug)]
+struct Posting {
+    document: u32,
+    positions: Vec<u32>, // in the body
+    headings: u32,
+    tags: u32,
+}
+
+// the log holds the changes made after the snapshot, a partly written last line is skipped
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(tag = "op", rename_all = "camelCase")]
+enum Record {
+    Put { document: Document },
+    Remove { path: String },
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct Snapshot {
+    version: u32,
+    documents: Vec<Document>,
+}
+
+struct Index {
+    root: PathBuf,
+    folder: PathBuf,
+    documents: Vec<Option<Document>>, // by id, None once removed
+    ids: HashMap<String, u32>,
+    terms: BTreeMap<String, Vec<Posting>>,
+    total_length: u64,    // number of terms in the bodies
+    log: Option<Vec<u8>>, // records not appended to the log file yet, None until it is opened
+    log_records: usize,
+}
+
+impl Index {
+    fn empty(root: &Path, folder: &Path) -> Index {
+        Index {
+            root: root.to_path_buf(),
+            folder: folder.to_path_buf(),
+            documents: Vec::new(),
+            ids: HashMap::new(),
+            terms: BTreeMap::new(),
+            total_length: 0,
+            log: None,
+            log_records: 0,
+        }
+    }
+
+    // the snapshot and the log replayed over it
+    fn open(root: &Path, folder: &Path) -> Result<Index, Error> {
+        let mut index = Index::empty(root, folder);
+        vfs::create_dir_all(folder)?;
+
+        if let Ok(file) = vfs::open(&folder.join(SNAPSHOT_FILE_NAME)) {
+            let mut json = Vec::new();
+            let snapshot = GzDecoder::new(file)
+                .read_to_end(&mut json)
+                .ok()
+                .and_then(|_| serde_json::from_slice::<Snapshot>(&json).ok())
+                .filter(|snapshot| snapshot.version == INDEX_VERSION);
+            for document in snapshot
+                .map(|snapshot| snapshot.documents)
+                .unwrap_or_default()
+            {
+                index.insert(document);
+            }
+        }
+
+        let mut replayed = 0;
+        if let Ok(file) = vfs::open(&folder.join(LOG_FILE_NAME)) {
+            for line in BufReader::new(file).lines() {
+                let record = match line.map(|line| serde_json::from_str::<Record>(&line)) {
+                    Ok(Ok(record)) => record,
+                    _ => break,
+                };
+                match record {
+                    Record::Put { document } => index.insert(document),
+                    Record::Remove { path } => index.delete(&path),
+                }
+                replayed += 1;
+            }
+        }
+
+        // the records after a broken line are lost, the log starts again from a snapshot
+        if replayed > 0 || !vfs::exists(&folder.join(LOG_FILE_NAME)) {
+            index.compact()?;
+        }
+        index.open_log();
+        Ok(index)
+    }
+
+    fn clear(&mut self) -> Result<(), Error> {
+        *self = Index::empty(&self.root, &self.folder);
+        self.compact()?;
+        self.open_log();
+        Ok(())
+    }
+
+    fn modified(&self, path: &str) -> Option<u64> {
+        let id = self.ids.get(path)?;
+        self.documents[*id as usize]
+            .as_ref()
+            .map(|document| document.modified)
+    }
+
+    fn put(&mut self, document: Document) {
+        self.append(&Record::Put {
+            document: document.clone(),
+        });
+        self.insert(document);
+    }
+
+    fn remove(&mut self, path: &str) {
+        if self.ids.contains_key(path) {
+            self.append(&Record::Remove {
+                path: path.to_string(),
+            });
+            self.delete(path);
+        }
+    }
+
+    // the document at the path or the documents in the folder at the path
+    fn remove_folder(&mut self, path: &str) {
+        let prefix = format!("{}/", path);
+        let paths: Vec<String> = self
+            .ids
+            .keys()
+            .filter(|document| *document == path || document.starts_with(&prefix))
+            .cloned()
+            .collect();
+        for path in paths {
+            self.remove(&path);
+        }
+    }
+
+    // the file, or the files of the folder, if they are not ignored
+    fn add_path(&mut self, listing: &mut Listing, path: &Path) {
+        for file in listing.included_files(path) {
+            match read_document(&self.root, &file) {
+                Some(document) => self.put(document),
+                None => self.remove(&relative_path(&self.root, &file)),
+            }
+        }
+    }
+
+    fn insert(&mut self, document: Document) {
+        self.delete(&document.path);
+        let id = self.documents.len() as u32;
+
+        let mut postings: HashMap<&str, Posting> = HashMap::new();
+        fn posting<'a, 't>(
+            postings: &'a mut HashMap<&'t str, Posting>,
+            term: &'t str,
+            id: u32,
+        ) -> &'a mut Posting {
+            postings.entry(term).or_insert_with(|| Posting {
+                document: id,
+                positions: Vec::new(),
+                headings: 0,
+                tags: 0,
+            })
+        }
+        for (position, term) in document.body.iter().enumerate() {
+            posting(&mut postings, term, id)
+                .positions
+                .push(position as u32);
+        }
+        for term in &document.headings {
+            posting(&mut postings, term, id).headings += 1;
+        }
+        for tag in &document.tags {
+            posting(&mut postings, tag, id).tags += 1;
+        }
+        for (term, posting) in postings {
+            self.terms
+                .entry(term.to_string())
+                .or_default()
+                .push(posting);
+        }
+        self.total_length += document.body.len() as u64;
+        self.ids.insert(document.path.clone(), id);
+        self.documents.push(Some(document));
+    }
+
+    fn delete(&mut self, path: &str) {
+        let id = match self.ids.remove(path) {
+            Some(id) => id,
+            None => return,
+        };
+        let document = match self.documents[id as usize].take() {
+            Some(document) => document,
+            None => return,
+        };
+
+        let terms: HashSet<&String> = document
+            .body
+            .iter()
+            .chain(&document.headings)
+            .chain(&document.tags)
+            .collect();
+        for term in terms {
+            if let Some(postings) = self.terms.get_mut(term) {
+                postings.retain(|posting| posting.document != id);
+                if postings.is_empty() {
+                    self.terms.remove(term);
+                }
+            }
+        }
+        self.total_length -= document.body.len() as u64;
+    }
+
+    fn append(&mut self, record: &Record) {
+        let line = match serde_json::to_string(record) {
+            Ok(line) => line,
+            Err(_) => return,
+        };
+        if let Some(log) = &mut self.log {
+            log.extend_from_slice(line.as_bytes());
+            log.push(b'\n');
+            self.log_records += 1;
+            if log.len() >= LOG_BUFFER_SIZE {
+                if let Err(err) = self.write_log() {
+                    eprintln!("failed to write the index log: {}", err);
+                }
+            }
+        }
+    }
+
+    // the log is on disk when this returns, or merged in the snapshot when it is long
+    fn flush(&mut self) -> Result<(), Error> {
+        if self.log_records >= COMPACT_AFTER {
+            self.compact()?;
+            self.open_log();
+            return Ok(());
+        }
+        self.write_log()
+    }
+
+    // the records are kept for the next write when it fails
+    fn write_log(&mut self) -> Result<(), Error> {
+        if let Some(log) = self.log.as_mut().filter(|log| !log.is_empty()) {
+            vfs::append(&self.folder.join(LOG_FILE_NAME), log)?;
+            log.clear();
+        }
+        Ok(())
+    }
+
+    // the snapshot replaces the old one at once, the log is emptied after it
+    fn compact(&mut self) -> Result<(), Error> {
+        let snapshot = Snapshot {
+            version: INDEX_VERSION,
+            documents: self.documents.iter().flatten().cloned().collect(),
+        };
+        let json = serde_json::to_vec(&snapshot).map_err(|err| Error::Io(err.to_string()))?;
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::fast());
+        encoder.write_all(&json)?;
+        write_atomic(&self.folder.join(SNAPSHOT_FILE_NAME), &encoder.finish()?)?;
+
+        self.log = None;
+        vfs::write(&self.folder.join(LOG_FILE_NAME), &[])?;
+        self.log_records = 0;
+
+        // the ids of the removed documents are reused
+        let documents = std::mem::take(&mut self.documents);
+        let root = self.root.clone();
+        let folder = self.folder.clone();
+        *self = Index::empty(&root, &folder);
+        for document in documents.into_iter().flatten() {
+            self.insert(document);
+        }
+        Ok(())
+    }
+
+    fn open_log(&mut self) {
+        self.log = Some(Vec::new());
+    }
+}
+
+// endregion
+
+// region: ---Query
+
+#[derive(Debug, PartialEq)]
+enum Clause {
+    Term(String),
+    Prefix(String),
+    Phrase(Vec<String>),
+    Tag(String),
+}
+
+fn parse_query(query: &str) -> Vec<Clause> {
+    let mut clauses = Vec::new();
+    let mut rest = query.trim_start();
+
+    while !rest.is_empty() {
+        let (word, next) = match rest.strip_prefix('"') {
+            Some(quoted) => {
+                let end = quoted.find('"').unwrap_or(quoted.len());
+                let terms = tokenize(&quoted[..end]);
+                match terms.len() {
+                    0 => {}
+                    1 => clauses.push(Clause::Term(terms[0].clone())),
+                    _ => clauses.push(Clause::Phrase(terms)),
+                }
+                (None, &quoted[(end + 1).min(quoted.len())..])
+            }
+            None => {
+                let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+                (Some(&rest[..end]), &rest[end..])
+            }
+        };
+        rest = next.trim_start();
+
+        let word = match word {
+            Some(word) => word,
+            None => continue,
+        };
+        if word.len() > 1 && word.starts_with('#') {
+            clauses.push(Clause::Tag(word.to_lowercase()));
+            continue;
+        }
+        let mut terms = tokenize(word);
+        let prefix = word.ends_with('*') && !terms.is_empty();
+        let last = match (prefix, terms.len()) {
+            (true, _) => terms.pop().map(Clause::Prefix),
+            (false, 1) => terms.pop().map(Clause::Term),
+            (false, 0) => None,
+            // "foo-bar" is a phrase
+            (false, _) => Some(Clause::Phrase(std::mem::take(&mut terms))),
+        };
+        clauses.extend(terms.into_iter().map(Clause::Term));
+        clauses.extend(last);
+    }
+    clauses
+}
+
+impl Index {
+    fn search(&self, query: &str, limit: usize) -> Vec<IndexHit> {
+        let clauses = parse_query(query);
+
+        // every clause is required, the scores are summed
+        let mut scores: Option<HashMap<u32, f64>> = None;
+        for clause in &clauses {
+            let clause_scores = self.clause_scores(clause);
+            scores = Some(match scores {
+                None => clause_scores,
+                Some(mut scores) => {
+                    scores.retain(|document, _| clause_scores.contains_key(document));
+                    for (document, score) in scores.iter_mut() {
+                        *score += clause_scores[document];
+                    }
+                    scores
+                }
+            });
+        }
+
+        let mut hits: Vec<(u32, f64)> = scores.unwrap_or_default().into_iter().collect();
+        hits.sort_by(|left, right| right.1.total_cmp(&left.1));
+        hits.into_iter()
+            .take(limit)
+            .filter_map(|(id, score)| {
+                let document = self.documents[id as usize].as_ref()?;
+                Some(IndexHit {
+                    path: self.root.join(&document.path).to_string_lossy().to_string(),
+                    relative_path: document.path.clone(),
+                    title: document.title.clone(),
+                    score,
+                })
+            })
+            .collect()
+    }
+
+    fn clause_scores(&self, clause: &Clause) -> HashMap<u32, f64> {
+        let mut scores = HashMap::new();
+        match clause {
+            Clause::Term(term) | Clause::Tag(term) => self.add_term_scores(term, &mut scores),
+            Clause::Prefix(prefix) => {
+                for term in self
+                    .terms
+                    .range(prefix.clone()..)
+                    .map(|(term, _)| term)
+                    .take_while(|term| term.starts_with(prefix.as_str()))
+                    .take(MAX_PREFIX_TERMS)
+                {
+                    self.add_term_scores(term, &mut scores);
+                }
+            }
+            Clause::Phrase(terms) => {
+                for term in terms {
+                    let mut term_scores = HashMap::new();
+                    self.add_term_scores(term, &mut term_scores);
+                    if scores.is_empty() && term == &terms[0] {
+                        scores = term_scores;
+                    } else {
+                        scores.retain(|document, _| term_scores.contains_key(document));
+                        for (document, score) in scores.iter_mut() {
+                            *score += term_scores[document];
+                        }
+                    }
+                }
+                scores.retain(|document, _| self.has_phrase(*document, terms));
+            }
+        }
+        scores
+    }
+
+    fn add_term_scores(&self, term: &str, scores: &mut HashMap<u32, f64>) {
+        let postings = match self.terms.get(term) {
+            Some(postings) => postings,
+            None => return,
+        };
+        let count = self.ids.len().max(1) as f64;
+        let average_length = (self.total_length as f64 / count).max(1.0);
+        let frequency = postings.len() as f64;
+        let idf = ((count - frequency + 0.5) / (frequency + 0.5) + 1.0).ln();
+
+        for posting in postings {
+            let length = self.documents[posting.document as usize]
+                .as_ref()
+                .map_or(0, |document| document.body.len()) as f64;
+            let weight = posting.positions.len() as f64
+                + HEADING_BOOST * posting.headings as f64
+                + TAG_BOOST * posting.tags as f64;
+            let score =
+                idf * weight * (K1 + 1.0) / (weight + K1 * (1.0 - B + B * length / average_length));
+            *scores.entry(posting.document).or_insert(0.0) += score;
+        }
+    }
+
+    // the terms follow each other in the body
+    fn has_phrase(&self, document: u32, terms: &[String]) -> bool {
+        let positions: Vec<&Vec<u32>> = match terms
+            .iter()
+            .map(|term| {
+                self.terms.get(term).and_then(|postings| {
+                    postings
+                        .iter()
+                        .find(|posting| posting.document == document)
+                        .map(|posting| &posting.positions)
+                })
+            })
+            .collect::<Option<Vec<_>>>()
+        {
+            Some(positions) => positions,
+            None => return false,
+        };
+
+        positions[0].iter().any(|start| {
+            positions
+                .iter()
+                .enumerate()
+                .skip(1)
+                .all(|(offset, term_positions)| {
+                    term_positions
+                        .binary_search(&(start + offset as u32))
+                        .is_ok()
+                })
+        })
+    }
+}
+
+// endregion
+
+// region: ---Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::fs::vfs::MemoryVfs;
+
+    // index of markdown documents in memory, the log is not written
+    fn index_of(documents: &[(&str, &str)]) -> Index {
+        let mut index = Index::empty(Path::new("/notes"), Path::new("/index"));
+        for (path, text) in documents {
+            let tokenized = tokenize_markdown(text);
+            index.insert(Document {
+                path: path.to_string(),
+                modified: 0,
+                title: tokenized.title,
+                body: tokenized.body,
+                headings: tokenized.headings,
+                tags: tokenized.tags,
+            });
+        }
+        index
+    }
+
+    fn paths(index: &Index, query: &str) -> Vec<String> {
+        index
+            .search(query, DEFAULT_LIMIT)
+            .into_iter()
+            .map(|hit| hit.relative_path)
+            .collect()
+    }
+
+    #[test]
+    fn queries_are_split_in_clauses() {
+        assert_eq!(
+            parse_query("Rust \"borrow checker\" #Todo cach* foo-bar"),
+            [
+                Clause::Term(String::from("rust")),
+                Clause::Phrase(vec![String::from("borrow"), String::from("checker")]),
+                Clause::Tag(String::from("#todo")),
+                Clause::Prefix(String::from("cach")),
+                Clause::Phrase(vec![String::from("foo"), String::from("bar")]),
+            ]
+        );
+        assert_eq!(parse_query("  \"\" # "), []);
+    }
+
+    #[test]
+    fn rarer_and_more_frequent_terms_score_higher() {
+        let index = index_of(&[
+            ("a.md", "apple apple apple banana"),
+            ("b.md", "apple banana cherry"),
+            ("c.md", "banana cherry"),
+        ]);
+        assert_eq!(paths(&index, "apple"), ["a.md", "b.md"]);
+
+        // apple is in fewer documents than banana
+        let apple = index.clause_scores(&Clause::Term(String::from("apple")));
+        let banana = index.clause_scores(&Clause::Term(String::from("banana")));
+        assert!(apple[&1] > banana[&1]);
+    }
+
+    #[test]
+    fn headings_and_tags_are_boosted() {
+        let index = index_of(&[
+            ("body.md", "some text about rust\n"),
+            ("heading.md", "# Rust\nsome text\n"),
+        ]);
+        assert_eq!(paths(&index, "rust"), ["heading.md", "body.md"]);
+    }
+
+    #[test]
+    fn every_clause_is_required() {
+        let index = index_of(&[
+            ("a.md", "red green"),
+            ("b.md", "red blue"),
+            ("c.md", "blue green red"),
+        ]);
+        let mut hits = paths(&index, "red blue");
+        hits.sort();
+        assert_eq!(hits, ["b.md", "c.md"]);
+        assert_eq!(paths(&index, "\"red blue\""), ["b.md"]);
+
+        let mut hits = paths(&index, "gre*");
+        hits.sort();
+        assert_eq!(hits, ["a.md", "c.md"]);
+    }
+
+    #[test]
+    fn the_log_is_replayed_when_the_index_is_opened() {
+        let folder = Path::new("/index-log");
+        vfs::mount(folder, Arc::new(MemoryVfs::new()));
+        let root = folder.join("notes");
+        vfs::create_dir_all(&root).unwrap();
+        vfs::write(&root.join("a.md"), b"# Apple\npie").unwrap();
+        vfs::write(&root.join("b.txt"), b"banana").unwrap();
+
+        let index_folder = folder.join("index");
+        let mut index = Index::open(&root, &index_folder).unwrap();
+        let mut listing = Listing::new(&root, ListOptions::default()).unwrap();
+        index.add_path(&mut listing, &root);
+        index.remove("b.txt");
+        index.flush().unwrap();
+        let log = vfs::read(&index_folder.join(LOG_FILE_NAME)).unwrap();
+        assert_eq!(String::from_utf8_lossy(&log).lines().count(), 3);
+
+        // the log is merged in the snapshot when the index is opened again
+        let index = Index::open(&root, &index_folder).unwrap();
+        assert_eq!(paths(&index, "apple"), ["a.md"]);
+        assert!(paths(&index, "banana").is_empty());
+        assert!(vfs::read(&index_folder.join(LOG_FILE_NAME))
+            .unwrap()
+            .is_empty());
+        vfs::unmount(folder);
+    }
+
+    #[test]
+    fn removed_documents_are_not_found() {
+        let mut index = index_of(&[("a.md", "apple"), ("b.md", "apple pie")]);
+        index.delete("a.md");
+        assert_eq!(paths(&index, "apple"), ["b.md"]);
+        assert_eq!(index.total_length, 2);
+    }
+}
+
+// endregion
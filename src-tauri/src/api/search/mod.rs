@@ -0,0 +1,473 @@
+pub mod index;
+pub mod quick_open;
+pub mod replace;
+pub mod tokenizer;
+
+use crate::api::folding::LineIndex;
+use crate::api::fs::listing::{walk_files, ListOptions};
+use crate::api::fs::text::read_text;
+use crate::api::fs::vfs;
+use crate::api::workspace::Workspace;
+use crate::error::Error;
+use crate::event::{emit_search_finished, emit_search_result, SearchFinished, SearchResult};
+use regex::{Regex, RegexBuilder};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use tauri::{AppHandle, State};
+
+// larger files are not searched
+const MAX_FILE_SIZE: u64 = 16 * 1024 * 1024;
+
+// longest preview of a line, in bytes
+const PREVIEW_LEN: usize = 250;
+
+// text kept in the preview before the first match of a long line
+const PREVIEW_CONTEXT: usize = 50;
+
+// region: ---Tauri Command
+
+// search the workspace on a background thread, the files with matches are emitted as
+// `search-result` events and the end of the search as a `search-finished` event
+#[tauri::command]
+pub async fn start_search(
+    query: SearchQuery,
+    options: Option<ListOptions>,
+    app: AppHandle,
+    workspace: State<'_, Workspace>,
+    searches: State<'_, Searches>,
+) -> Result<u32, Error> {
+    let root = workspace
+        .root()
+        .ok_or_else(|| Error::InvalidPath(String::from("no workspace is open")))?;
+    let options = options.unwrap_or_default();
+    query.to_regex()?;
+
+    let (id, cancelled) = searches.start();
+    let running = searches.running.clone();
+    thread::spawn(move || {
+        let result = search(&root, &query, &options, &cancelled, |file| {
+            emit_search_result(&app, SearchResult { id, file })
+        });
+        running.lock().unwrap().remove(&id);
+
+        let finished = match result {
+            Ok(summary) => SearchFinished {
+                id,
+                summary,
+                error: None,
+            },
+            Err(err) => SearchFinished {
+                id,
+                summary: SearchSummary::default(),
+                error: Some(err),
+            },
+        };
+        emit_search_finished(&app, finished);
+    });
+    Ok(id)
+}
+
+#[tauri::command]
+pub async fn cancel_search(id: u32, searches: State<'_, Searches>) -> Result<(), Error> {
+    searches.cancel(id);
+    Ok(())
+}
+
+// endregion
+
+// region: ---Search Query
+
+/// # SearchQuery
+/// {
+///     pattern: string,
+///     regex: bool,         // false, the pattern is a literal
+///     caseSensitive: bool, // false
+///     wholeWord: bool,     // false
+///     maxResults?: usize,  // the search stops after this number of matches
+/// }
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchQuery {
+    pub pattern: String,
+    #[serde(default)]
+    pub regex: bool,
+    #[serde(default)]
+    pub case_sensitive: bool,
+    #[serde(default)]
+    pub whole_word: bool,
+    #[serde(default)]
+    pub max_results: Option<usize>,
+}
+
+impl SearchQuery {
+    pub fn to_regex(&self) -> Result<Regex, Error> {
+        if self.pattern.is_empty() {
+            return Err(Error::InvalidPattern(String::from("empty pattern")));
+        }
+        let pattern = match self.regex {
+            true => self.pattern.clone(),
+            false => regex::escape(&self.pattern),
+        };
+        let pattern = match self.whole_word {
+            true => format!(r"\b(?:{})\b", pattern),
+            false => pattern,
+        };
+        RegexBuilder::new(&pattern)
+            .case_insensitive(!self.case_sensitive)
+            .multi_line(true)
+            .build()
+            .map_err(|err| Error::InvalidPattern(err.to_string()))
+    }
+}
+
+// endregion
+
+// region: ---Search
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct FileMatches {
+    pub path: String,
+    pub relative_path: String,
+    pub lines: Vec<LineMatch>,
+}
+
+impl FileMatches {
+    pub fn count(&self) -> usize {
+        self.lines.iter().map(|line| line.offsets.len()).sum()
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct LineMatch {
+    pub line: usize,                  // 0 based
+    pub column: usize,                // byte offset of the first match in the line
+    pub preview: String,              // the line, shortened around the matches when long
+    pub ranges: Vec<(usize, usize)>,  // byte ranges of the matches in the preview
+    pub offsets: Vec<(usize, usize)>, // byte ranges of the matches in the text of the file
+}
+
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct SearchSummary {
+    pub files: usize, // files with a match
+    pub matches: usize,
+    pub cancelled: bool,
+}
+
+// `on_file` is called from the threads of the walk for every file with a match
+pub fn search<F>(
+    root: &Path,
+    query: &SearchQuery,
+    options: &ListOptions,
+    cancelled: &AtomicBool,
+    on_file: F,
+) -> Result<SearchSummary, Error>
+where
+    F: Fn(FileMatches) + Sync,
+{
+    let regex = query.to_regex()?;
+    let max_results = query.max_results.unwrap_or(usize::MAX);
+    let files = AtomicUsize::new(0);
+    let matches = AtomicUsize::new(0);
+
+    for_each_file(root, options, |path| {
+        if cancelled.load(Ordering::Relaxed) || matches.load(Ordering::Relaxed) >= max_results {
+            return false;
+        }
+        if let Some(file) = search_file(root, path, &regex) {
+            files.fetch_add(1, Ordering::Relaxed);
+            matches.fetch_add(file.count(), Ordering::Relaxed);
+            on_file(file);
+        }
+        true
+    })?;
+
+    Ok(SearchSummary {
+        files: files.into_inner(),
+        matches: matches.into_inner(),
+        cancelled: cancelled.load(Ordering::Relaxed),
+    })
+}
+
+// the files of the workspace are found first, then visited from several threads until `f`
+// returns false
+pub fn for_each_file<F>(root: &Path, options: &ListOptions, f: F) -> Result<(), Error>
+where
+    F: Fn(&Path) -> bool + Sync,
+{
+    let mut files = Vec::new();
+    walk_files(root, options, |path| {
+        files.push(path.to_path_buf());
+        true
+    })?;
+
+    let threads = thread::available_parallelism().map_or(4, |threads| threads.get());
+    let next = AtomicUsize::new(0);
+    let stopped = AtomicBool::new(false);
+    thread::scope(|scope| {
+        for _ in 0..threads.min(files.len()) {
+            scope.spawn(|| {
+                while !stopped.load(Ordering::Relaxed) {
+                    let path = match files.get(next.fetch_add(1, Ordering::Relaxed)) {
+                        Some(path) => path,
+                        None => break,
+                    };
+                    if !f(path) {
+                        stopped.store(true, Ordering::Relaxed);
+                    }
+                }
+            });
+        }
+    });
+    Ok(())
+}
+
+// None for the binary, unreadable and too large files, or without a match
+pub fn search_file(root: &Path, path: &Path, regex: &Regex) -> Option<FileMatches> {
+    if vfs::stat(path).ok()?.len > MAX_FILE_SIZE {
+        return None;
+    }
+    let text = read_text(path, false).ok()?.text;
+    let lines = find_matches(&text, regex);
+    if lines.is_empty() {
+        return None;
+    }
+
+    Some(FileMatches {
+        path: path.to_string_lossy().to_string(),
+        relative_path: path
+            .strip_prefix(root)
+            .unwrap_or(path)
+            .to_string_lossy()
+            .replace('\\', "/"),
+        lines,
+    })
+}
+
+// the matches grouped by line, a match over several lines is cut at the end of its first line
+pub fn find_matches(text: &str, regex: &Regex) -> Vec<LineMatch> {
+    let index = LineIndex::new(text);
+    let mut lines: Vec<LineMatch> = Vec::new();
+
+    for found in regex.find_iter(text).filter(|found| !found.is_empty()) {
+        let line = index.line(found.start());
+        let line_start = index.line_start(line);
+        let line_end = text[line_start..]
+            .find('\n')
+            .map_or(text.len(), |end| line_start + end);
+        let range = (
+            found.start() - line_start,
+            found.end().min(line_end) - line_start,
+        );
+
+        match lines.last_mut() {
+            Some(last) if last.line == line => {
+                last.ranges.push(range);
+                last.offsets.push((found.start(), found.end()));
+            }
+            _ => lines.push(LineMatch {
+                line,
+                column: range.0,
+                preview: text[line_start..line_end].to_string(),
+                ranges: vec![range],
+                offsets: vec![(found.start(), found.end())],
+            }),
+        }
+    }
+
+    for line in &mut lines {
+        shorten_preview(line);
+    }
+    lines
+}
+
+// keep PREVIEW_LEN bytes of a long line, from a little before its first match
+fn shorten_preview(line: &mut LineMatch) {
+    if line.preview.len() <= PREVIEW_LEN {
+        return;
+    }
+    let floor = |mut index: usize| {
+        while !line.preview.is_char_boundary(index) {
+            index -= 1;
+        }
+        index
+    };
+    let start = floor(line.column.saturating_sub(PREVIEW_CONTEXT));
+    let end = floor((start + PREVIEW_LEN).min(line.preview.len()));
+
+    line.preview = line.preview[start..end].to_string();
+    line.ranges = line
+        .ranges
+        .iter()
+        .filter(|(range_start, _)| *range_start < end)
+        .map(|(range_start, range_end)| (range_start - start, range_end.min(&end) - start))
+        .collect();
+}
+
+// endregion
+
+// region: ---Searches
+
+// cancellation flags of the running searches
+#[derive(Default)]
+pub struct Searches {
+    next_id: AtomicU32,
+    pub(crate) running: Arc<Mutex<HashMap<u32, Arc<AtomicBool>>>>,
+}
+
+impl Searches {
+    pub(crate) fn start(&self) -> (u32, Arc<AtomicBool>) {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let cancelled = Arc::new(AtomicBool::new(false));
+        self.running.lock().unwrap().insert(id, cancelled.clone());
+        (id, cancelled)
+    }
+
+    pub fn cancel(&self, id: u32) {
+        if let Some(cancelled) = self.running.lock().unwrap().get(&id) {
+            cancelled.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+// endregion
+
+// region: ---Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::fs::vfs::MemoryVfs;
+
+    fn query(pattern: &str) -> SearchQuery {
+        SearchQuery {
+            pattern: pattern.to_string(),
+            regex: false,
+            case_sensitive: false,
+            whole_word: false,
+            max_results: None,
+        }
+    }
+
+    #[test]
+    fn queries_are_literal_unless_regex() {
+        assert!(query("a.c").to_regex().unwrap().is_match("A.C"));
+        assert!(!query("a.c").to_regex().unwrap().is_match("abc"));
+
+        let regex = SearchQuery {
+            regex: true,
+            case_sensitive: true,
+            ..query("a.c")
+        };
+        assert!(regex.to_regex().unwrap().is_match("abc"));
+        assert!(!regex.to_regex().unwrap().is_match("ABC"));
+
+        let whole_word = SearchQuery {
+            whole_word: true,
+            ..query("cat")
+        };
+        assert!(whole_word.to_regex().unwrap().is_match("a cat."));
+        assert!(!whole_word.to_regex().unwrap().is_match("concat"));
+    }
+
+    #[test]
+    fn empty_and_invalid_patterns_are_refused() {
+        assert!(matches!(
+            query("").to_regex(),
+            Err(Error::InvalidPattern(_))
+        ));
+        let invalid = SearchQuery {
+            regex: true,
+            ..query("(a")
+        };
+        assert!(matches!(invalid.to_regex(), Err(Error::InvalidPattern(_))));
+    }
+
+    #[test]
+    fn matches_are_grouped_by_line() {
+        let regex = query("ab").to_regex().unwrap();
+        let lines = find_matches("ab ab\nc\nxab", &regex);
+        assert_eq!(lines.len(), 2);
+        assert_eq!((lines[0].line, lines[0].column), (0, 0));
+        assert_eq!(lines[0].ranges, [(0, 2), (3, 5)]);
+        assert_eq!((lines[1].line, lines[1].column), (2, 1));
+        assert_eq!(lines[1].preview, "xab");
+        assert_eq!(lines[1].offsets, [(9, 11)]);
+    }
+
+    #[test]
+    fn multiline_matches_are_cut_at_the_end_of_their_line() {
+        let regex = SearchQuery {
+            regex: true,
+            ..query(r"b\nc")
+        }
+        .to_regex()
+        .unwrap();
+        let lines = find_matches("ab\ncd", &regex);
+        assert_eq!(lines[0].ranges, [(1, 2)]);
+        assert_eq!(lines[0].offsets, [(1, 4)]);
+    }
+
+    #[test]
+    fn long_lines_are_shortened_around_the_first_match() {
+        let text = format!("{}needle{}", "é".repeat(200), "x".repeat(400));
+        let lines = find_matches(&text, &query("needle").to_regex().unwrap());
+        let line = &lines[0];
+        assert!(line.preview.len() <= PREVIEW_LEN);
+        let (start, end) = line.ranges[0];
+        assert_eq!(&line.preview[start..end], "needle");
+        assert_eq!(line.column, 400);
+    }
+
+    #[test]
+    fn files_are_searched_with_their_relative_path() {
+        let root = Path::new("/search-file");
+        vfs::mount(root, Arc::new(MemoryVfs::new()));
+        vfs::create_dir_all(&root.join("notes")).unwrap();
+        vfs::write(&root.join("notes/a.md"), b"one\r\ntwo one").unwrap();
+        vfs::write(&root.join("b.md"), b"two").unwrap();
+        let regex = query("one").to_regex().unwrap();
+
+        let file = search_file(root, &root.join("notes/a.md"), &regex).unwrap();
+        assert_eq!(file.relative_path, "notes/a.md");
+        assert_eq!(file.count(), 2);
+        assert!(search_file(root, &root.join("b.md"), &regex).is_none());
+        assert!(search_file(root, &root.join("missing.md"), &regex).is_none());
+        vfs::unmount(root);
+    }
+
+    #[test]
+    fn workspaces_are_searched_through_the_vfs() {
+        let root = Path::new("/search-workspace");
+        vfs::mount(root, Arc::new(MemoryVfs::new()));
+        for (path, text) in [
+            (".gitignore", "ignored/\n"),
+            ("notes/a.md", "a needle"),
+            ("ignored/b.md", "a needle"),
+            (".git/config", "needle"),
+            (".trash/1-0/c.md", "needle"),
+            ("src/main.rs", "no match"),
+        ] {
+            let path = root.join(path);
+            vfs::create_dir_all(path.parent().unwrap()).unwrap();
+            vfs::write(&path, text.as_bytes()).unwrap();
+        }
+
+        let found = Mutex::new(Vec::new());
+        let summary = search(
+            root,
+            &query("needle"),
+            &ListOptions::default(),
+            &AtomicBool::new(false),
+            |file| found.lock().unwrap().push(file.relative_path),
+        )
+        .unwrap();
+        assert_eq!(found.into_inner().unwrap(), ["notes/a.md"]);
+        assert_eq!((summary.files, summary.matches), (1, 1));
+        vfs::unmount(root);
+    }
+}
+
+// endregion
@@ -0,0 +1,586 @@
+use crate::api::fs::listing::{walk_files, ListOptions, Listing};
+use crate::api::workspace::WorkspaceListener;
+use crate::error::Error;
+use crate::event::FsChange;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use tauri::State;
+
+const DEFAULT_LIMIT: usize = 50;
+
+// longer paths are matched without the best alignment
+const MAX_MATCHED_LEN: usize = 512;
+
+// score of a matched character and of where it is
+const SCORE_MATCH: i32 = 16;
+const BONUS_SEGMENT_START: i32 = 10; // first character of the path or after a /
+const BONUS_WORD_START: i32 = 8; // after _ - . or a space, or a camelCase hump
+const BONUS_CONSECUTIVE: i32 = 12;
+const BONUS_FILE_NAME: i32 = 6;
+const PENALTY_GAP_START: i32 = 3;
+const PENALTY_GAP_EXTENSION: i32 = 1;
+
+// region: ---Tauri Command
+
+// the files of the workspace matching the characters of the query in order, best first
+#[tauri::command]
+pub async fn quick_open(
+    query: &str,
+    limit: Option<usize>,
+    file_list: State<'_, FileList>,
+) -> Result<Vec<QuickOpenHit>, Error> {
+    let files = file_list.files.lock().unwrap();
+    let files = files
+        .as_ref()
+        .ok_or_else(|| Error::InvalidPath(String::from("no workspace is open")))?;
+    Ok(files.find(query, limit.unwrap_or(DEFAULT_LIMIT)))
+}
+
+// endregion
+
+// region: ---File List
+
+#[derive(Debug, serde::Serialize)]
+pub struct QuickOpenHit {
+    pub path: String,
+    pub relative_path: String,
+    pub score: i32,
+    pub indices: Vec<usize>, // char indices of the matched characters in the relative path
+}
+
+// paths of the files of the open workspace, kept up to date by the workspace watcher
+#[derive(Clone, Default)]
+pub struct FileList {
+    files: Arc<Mutex<Option<Files>>>,
+    generation: Arc<AtomicUsize>, // a listing stops when the workspace is opened again
+}
+
+impl FileList {
+    fn open(&self, root: &Path) {
+        let generation = self.generation.fetch_add(1, Ordering::Relaxed) + 1;
+        *self.files.lock().unwrap() = Some(Files::new(root));
+
+        // the files are found on a background thread, the list fills up meanwhile
+        let file_list = self.clone();
+        let root = root.to_path_buf();
+        thread::spawn(move || {
+            let mut found = Vec::new();
+            let _ = walk_files(&root, &ListOptions::default(), |path| {
+                if file_list.generation.load(Ordering::Relaxed) != generation {
+                    return false;
+                }
+                found.push(path.to_path_buf());
+                if found.len() >= 1000 {
+                    file_list.add_files(generation, std::mem::take(&mut found));
+                }
+                true
+            });
+            file_list.add_files(generation, found);
+        });
+    }
+
+    fn add_files(&self, generation: usize, paths: Vec<PathBuf>) {
+        if self.generation.load(Ordering::Relaxed) != generation {
+            return;
+        }
+        if let Some(files) = self.files.lock().unwrap().as_mut() {
+            for path in paths {
+                files.insert(&path);
+            }
+        }
+    }
+
+    fn update(&self, root: &Path, changes: &[FsChange]) {
+        let mut files = self.files.lock().unwrap();
+        let files = match files.as_mut() {
+            Some(files) if files.root == root => files,
+            _ => return,
+        };
+        let mut listing = match Listing::new(root, ListOptions::default()) {
+            Ok(listing) => listing,
+            Err(_) => return,
+        };
+
+        for change in changes {
+            let (removed, added) = match change {
+                FsChange::Created { path } | FsChange::Modified { path } => (None, Some(path)),
+                FsChange::Removed { path } => (Some(path), None),
+                FsChange::Renamed { from, to } => (Some(from), Some(to)),
+            };
+            if let Some(path) = removed {
+                files.remove(Path::new(path));
+            }
+            if let Some(path) = added {
+                for file in listing.included_files(Path::new(path)) {
+                    files.insert(&file);
+                }
+            }
+        }
+    }
+}
+
+impl WorkspaceListener for FileList {
+    fn opened(&self, root: &Path) {
+        self.open(root);
+    }
+
+    fn changed(&self, root: &Path, changes: &[FsChange]) {
+        self.update(root, changes);
+    }
+}
+
+struct FileEntry {
+    path: String,  // relative to the root, with / separators
+    lower: String, // for the quick check of the query characters
+}
+
+struct Files {
+    root: PathBuf,
+    entries: Vec<FileEntry>,
+    positions: HashMap<String, usize>,
+}
+
+impl Files {
+    fn new(root: &Path) -> Files {
+        Files {
+            root: root.to_path_buf(),
+            entries: Vec::new(),
+            positions: HashMap::new(),
+        }
+    }
+
+    fn insert(&mut self, path: &Path) {
+        let path = match path.strip_prefix(&self.root) {
+            Ok(relative) => relative.to_string_lossy().replace('\\', "/"),
+            Err(_) => return,
+        };
+        if self.positions.contains_key(&path) {
+            return;
+        }
+        self.positions.insert(path.clone(), self.entries.len());
+        self.entries.push(FileEntry {
+            lower: path.to_lowercase(),
+            path,
+        });
+    }
+
+    // the file at the path or the files in the folder at the path
+    fn remove(&mut self, path: &Path) {
+        let path = match path.strip_prefix(&self.root) {
+            Ok(relative) => relative.to_string_lossy().replace('\\', "/"),
+            Err(_) => return,
+        };
+        if let Some(position) = self.positions.remove(&path) {
+            self.entries.swap_remove(position);
+            if let Some(moved) = self.entries.get(position) {
+                self.positions.insert(moved.path.clone(), position);
+            }
+            return;
+        }
+
+        let prefix = format!("{}/", path);
+        let count = self.entries.len();
+        self.entries
+            .retain(|entry| !entry.path.starts_with(&prefix));
+        if self.entries.len() != count {
+            self.positions = self
+                .entries
+                .iter()
+                .enumerate()
+                .map(|(position, entry)| (entry.path.clone(), position))
+                .collect();
+        }
+    }
+
+    fn find(&self, query: &str, limit: usize) -> Vec<QuickOpenHit> {
+        let query: Vec<char> = query
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .map(|c| if c == '\\' { '/' } else { c })
+            .flat_map(char::to_lowercase)
+            .collect();
+
+        // the files are scored on several threads, the indices are only found for the best ones
+        let threads = thread::available_parallelism().map_or(4, |threads| threads.get());
+        let chunk_len = (self.entries.len() / threads).max(1024);
+        let mut hits: Vec<(i32, &FileEntry)> = thread::scope(|scope| {
+            let workers: Vec<_> = self
+                .entries
+                .chunks(chunk_len)
+                .map(|entries| {
+                    let query = &query;
+                    scope.spawn(move || {
+                        let mut matcher = Matcher::new(query);
+                        entries
+                            .iter()
+                            .filter_map(|entry| Some((matcher.score(entry)?, entry)))
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect();
+            workers
+                .into_iter()
+                .flat_map(|worker| worker.join().unwrap_or_default())
+                .collect()
+        });
+
+        // best score, then shortest path
+        let compare = |left: &(i32, &FileEntry), right: &(i32, &FileEntry)| {
+            right
+                .0
+                .cmp(&left.0)
+                .then(left.1.path.len().cmp(&right.1.path.len()))
+                .then(left.1.path.cmp(&right.1.path))
+        };
+        if hits.len() > limit && limit > 0 {
+            hits.select_nth_unstable_by(limit - 1, compare);
+        }
+        hits.truncate(limit);
+        hits.sort_by(compare);
+
+        let mut matcher = Matcher::new(&query);
+        hits.into_iter()
+            .map(|(score, entry)| QuickOpenHit {
+                path: self.root.join(&entry.path).to_string_lossy().to_string(),
+                relative_path: entry.path.clone(),
+                score,
+                indices: matcher.indices(entry),
+            })
+            .collect()
+    }
+}
+
+// endregion
+
+// region: ---Fuzzy Match
+
+// buffers of the scoring of the paths, reused from one path to the next
+struct Matcher<'q> {
+    query: &'q [char], // lowercase
+    ascii_query: Option<Vec<u8>>,
+    chars: Vec<char>,
+    lower: Vec<char>,
+    bonuses: Vec<i32>,
+    scores: Vec<i32>, // scores[i * n + j]: best score of query[..=i] with query[i] at j
+    from: Vec<u16>,   // position of query[i - 1] for that score
+}
+
+const NO_SCORE: i32 = i32::MIN;
+
+impl<'q> Matcher<'q> {
+    fn new(query: &'q [char]) -> Matcher<'q> {
+        Matcher {
+            query,
+            ascii_query: query
+                .iter()
+                .all(char::is_ascii)
+                .then(|| query.iter().map(|c| *c as u8).collect()),
+            chars: Vec::new(),
+            lower: Vec::new(),
+            bonuses: Vec::new(),
+            scores: Vec::new(),
+            from: Vec::new(),
+        }
+    }
+
+    // None when the characters of the query are not all in the path in order
+    fn score(&mut self, entry: &FileEntry) -> Option<i32> {
+        if !self.contains_in_order(&entry.lower) {
+            return None;
+        }
+        if self.query.is_empty() {
+            return Some(0);
+        }
+        self.load(&entry.path);
+        if self.chars.len() > MAX_MATCHED_LEN {
+            return self.greedy_match().map(|(score, _)| score);
+        }
+        self.align().map(|(score, _)| score)
+    }
+
+    // char indices of the best alignment in the path
+    fn indices(&mut self, entry: &FileEntry) -> Vec<usize> {
+        if self.query.is_empty() {
+            return Vec::new();
+        }
+        self.load(&entry.path);
+        let best = match self.chars.len() > MAX_MATCHED_LEN {
+            true => {
+                return self
+                    .greedy_match()
+                    .map(|(_, indices)| indices)
+                    .unwrap_or_default()
+            }
+            false => self.align(),
+        };
+        let (m, n) = (self.query.len(), self.chars.len());
+        let mut j = match best {
+            Some((_, last)) => last,
+            None => return Vec::new(),
+        };
+        let mut indices = vec![0; m];
+        for i in (0..m).rev() {
+            indices[i] = j;
+            j = self.from[i * n + j] as usize;
+        }
+        indices
+    }
+
+    fn contains_in_order(&self, lower: &str) -> bool {
+        match &self.ascii_query {
+            Some(query) => {
+                let mut query = query.iter().peekable();
+                for byte in lower.bytes() {
+                    if query.peek() == Some(&&byte) {
+                        query.next();
+                    }
+                }
+                query.peek().is_none()
+            }
+            None => {
+                let mut query = self.query.iter().peekable();
+                for c in lower.chars() {
+                    if query.peek() == Some(&&c) {
+                        query.next();
+                    }
+                }
+                query.peek().is_none()
+            }
+        }
+    }
+
+    fn load(&mut self, path: &str) {
+        self.chars.clear();
+        self.chars.extend(path.chars());
+        self.lower.clear();
+        self.lower
+            .extend(self.chars.iter().map(|c| match c.is_ascii() {
+                true => c.to_ascii_lowercase(),
+                false => c.to_lowercase().next().unwrap_or(*c),
+            }));
+
+        let name_start = self
+            .chars
+            .iter()
+            .rposition(|c| *c == '/')
+            .map_or(0, |slash| slash + 1);
+        self.bonuses.clear();
+        for (index, c) in self.chars.iter().enumerate() {
+            let previous = index.checked_sub(1).map(|previous| self.chars[previous]);
+            let mut bonus = match previous {
+                None | Some('/') => BONUS_SEGMENT_START,
+                Some('_' | '-' | '.' | ' ') => BONUS_WORD_START,
+                Some(previous) if previous.is_lowercase() && c.is_uppercase() => BONUS_WORD_START,
+                _ => 0,
+            };
+            if index >= name_start {
+                bonus += BONUS_FILE_NAME;
+            }
+            self.bonuses.push(bonus);
+        }
+    }
+
+    // best score and position of the last query character
+    fn align(&mut self) -> Option<(i32, usize)> {
+        let (m, n) = (self.query.len(), self.chars.len());
+        self.scores.clear();
+        self.scores.resize(m * n, NO_SCORE);
+        self.from.clear();
+        self.from.resize(m * n, 0);
+
+        for i in 0..m {
+            // best score of query[i - 1] before j - 1, with the penalty of the gap to j
+            let mut gapped = (NO_SCORE, 0);
+            for j in i..n {
+                if i > 0 && j >= 2 {
+                    let candidate = self.scores[(i - 1) * n + j - 2];
+                    if gapped.0 != NO_SCORE {
+                        gapped.0 -= PENALTY_GAP_EXTENSION;
+                    }
+                    if candidate != NO_SCORE && candidate - PENALTY_GAP_START >= gapped.0 {
+                        gapped = (candidate - PENALTY_GAP_START, j - 2);
+                    }
+                }
+                if self.lower[j] != self.query[i] {
+                    continue;
+                }
+                let score = SCORE_MATCH + self.bonuses[j];
+                if i == 0 {
+                    self.scores[j] = score;
+                    continue;
+                }
+                let consecutive = match self.scores[(i - 1) * n + j - 1] {
+                    NO_SCORE => NO_SCORE,
+                    previous => previous + BONUS_CONSECUTIVE,
+                };
+                let (best, previous) = match consecutive >= gapped.0 {
+                    true => (consecutive, j - 1),
+                    false => gapped,
+                };
+                if best != NO_SCORE {
+                    self.scores[i * n + j] = best + score;
+                    self.from[i * n + j] = previous as u16;
+                }
+            }
+        }
+
+        self.scores[(m - 1) * n..]
+            .iter()
+            .enumerate()
+            .filter(|(_, score)| **score != NO_SCORE)
+            .max_by_key(|(_, score)| **score)
+            .map(|(last, score)| (*score, last))
+    }
+
+    // the first occurrence of every query character, for the very long paths
+    fn greedy_match(&self) -> Option<(i32, Vec<usize>)> {
+        let mut indices: Vec<usize> = Vec::with_capacity(self.query.len());
+        let mut score = 0;
+        let mut start = 0;
+        for c in self.query {
+            let index = start + self.lower[start..].iter().position(|lower| lower == c)?;
+            score += SCORE_MATCH + self.bonuses[index];
+            if indices.last().is_some_and(|last| last + 1 == index) {
+                score += BONUS_CONSECUTIVE;
+            }
+            indices.push(index);
+            start = index + 1;
+        }
+        Some((score, indices))
+    }
+}
+
+// endregion
+
+// region: ---Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::fs::vfs::{self, MemoryVfs};
+    use std::time::Duration;
+
+    fn files(paths: &[&str]) -> Files {
+        let mut files = Files::new(Path::new("/root"));
+        for path in paths {
+            files.insert(&Path::new("/root").join(path));
+        }
+        files
+    }
+
+    fn found(files: &Files, query: &str) -> Vec<String> {
+        files
+            .find(query, DEFAULT_LIMIT)
+            .into_iter()
+            .map(|hit| hit.relative_path)
+            .collect()
+    }
+
+    #[test]
+    fn query_characters_are_matched_in_order() {
+        let files = files(&["abc.rs", "cba.rs"]);
+        assert_eq!(found(&files, "ac"), ["abc.rs"]);
+        assert_eq!(found(&files, "A C"), ["abc.rs"]);
+        assert!(found(&files, "abcd").is_empty());
+    }
+
+    #[test]
+    fn segment_starts_and_consecutive_characters_rank_first() {
+        let files = files(&["src/random_order.rs", "src/api/mod.rs"]);
+        assert_eq!(
+            found(&files, "mod"),
+            ["src/api/mod.rs", "src/random_order.rs"]
+        );
+    }
+
+    #[test]
+    fn indices_are_the_best_alignment() {
+        let files = files(&["src/QuickOpen.ts", "src/quick_open.rs"]);
+        let hits = files.find("qo", DEFAULT_LIMIT);
+        let indices: Vec<(&str, &[usize])> = hits
+            .iter()
+            .map(|hit| (hit.relative_path.as_str(), hit.indices.as_slice()))
+            .collect();
+        assert!(indices.contains(&("src/QuickOpen.ts", &[4, 9][..])));
+        assert!(indices.contains(&("src/quick_open.rs", &[4, 10][..])));
+    }
+
+    #[test]
+    fn equal_scores_prefer_the_shorter_path() {
+        let files = files(&["b/a.rs", "a.rs"]);
+        assert_eq!(found(&files, "a.rs"), ["a.rs", "b/a.rs"]);
+    }
+
+    #[test]
+    fn very_long_paths_are_matched_greedily() {
+        let path = format!("{}/x.rs", "a".repeat(MAX_MATCHED_LEN));
+        let files = files(&[&path]);
+        let hits = files.find("ax", DEFAULT_LIMIT);
+        assert_eq!(hits[0].indices, [0, MAX_MATCHED_LEN + 1]);
+    }
+
+    #[test]
+    fn removed_folders_remove_their_files() {
+        let mut files = files(&["a/b.rs", "a/c/d.rs", "ab.rs"]);
+        files.remove(Path::new("/root/a"));
+        assert_eq!(found(&files, ""), ["ab.rs"]);
+        files.remove(Path::new("/root/ab.rs"));
+        assert!(files.entries.is_empty() && files.positions.is_empty());
+    }
+
+    #[test]
+    fn workspaces_are_listed_through_the_vfs() {
+        let root = Path::new("/quick-open-workspace");
+        vfs::mount(root, Arc::new(MemoryVfs::new()));
+        for path in [
+            ".gitignore",
+            "notes/a.md",
+            "ignored/b.md",
+            ".git/config",
+            ".trash/1-0/c.md",
+        ] {
+            let path = root.join(path);
+            vfs::create_dir_all(path.parent().unwrap()).unwrap();
+            vfs::write(&path, b"ignored/").unwrap();
+        }
+
+        // the files are listed on a background thread
+        let file_list = FileList::default();
+        file_list.open(root);
+        let mut found = Vec::new();
+        for _ in 0..100 {
+            if let Some(files) = file_list.files.lock().unwrap().as_ref() {
+                found = files
+                    .entries
+                    .iter()
+                    .map(|entry| entry.path.clone())
+                    .collect();
+            }
+            if found.len() >= 2 {
+                break;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        found.sort();
+        assert_eq!(found, [".gitignore", "notes/a.md"]);
+
+        vfs::write(&root.join("notes/d.md"), b"").unwrap();
+        file_list.update(
+            root,
+            &[FsChange::Created {
+                path: root.join("notes/d.md").to_string_lossy().to_string(),
+            }],
+        );
+        let has_file = |path: &str| {
+            let files = file_list.files.lock().unwrap();
+            files.as_ref().unwrap().positions.contains_key(path)
+        };
+        assert!(has_file("notes/d.md"));
+        vfs::unmount(root);
+    }
+}
+
+// endregion
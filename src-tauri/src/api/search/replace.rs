@@ -0,0 +1,453 @@
+use super::{for_each_file, SearchQuery, MAX_FILE_SIZE, PREVIEW_CONTEXT, PREVIEW_LEN};
+use crate::api::folding::LineIndex;
+use crate::api::fs::history::History;
+use crate::api::fs::journal::{Journal, Operation};
+use crate::api::fs::listing::ListOptions;
+use crate::api::fs::text::{decode_text, read_text, stage_write, write_atomic};
+use crate::api::fs::vfs;
+use crate::api::workspace::Workspace;
+use crate::error::Error;
+use regex::Regex;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use tauri::State;
+
+// region: ---Tauri Command
+
+// the changes of a replace in the workspace, nothing is written
+#[tauri::command]
+pub async fn preview_replace(
+    query: SearchQuery,
+    replacement: &str,
+    options: Option<ListOptions>,
+    workspace: State<'_, Workspace>,
+) -> Result<Vec<FilePreview>, Error> {
+    let root = workspace
+        .root()
+        .ok_or_else(|| Error::InvalidPath(String::from("no workspace is open")))?;
+    preview(&root, &query, replacement, &options.unwrap_or_default())
+}
+
+// the edits of the previews, without the deselected ones, are written in all the files or in none
+// (the text before and after the replace is kept in the history of every file)
+#[tauri::command]
+pub async fn apply_replace(
+    files: Vec<FileEdits>,
+    workspace: State<'_, Workspace>,
+    journal: State<'_, Journal>,
+    history: State<'_, History>,
+) -> Result<ReplaceReport, Error> {
+    let files = files
+        .into_iter()
+        .filter(|file| !file.edits.is_empty())
+        .map(|mut file| {
+            let path = workspace.resolve_writable(&file.path)?;
+            // the edits are recorded in order for their inversion
+            file.edits.sort_by_key(|edit| edit.start);
+            Ok(FileEdits {
+                path: path.to_string_lossy().to_string(),
+                edits: file.edits,
+            })
+        })
+        .collect::<Result<Vec<FileEdits>, Error>>()?;
+    let record = || {
+        for file in &files {
+            history.record(Path::new(&file.path));
+        }
+    };
+    record();
+    apply_edits(&files)?;
+    record();
+
+    let report = ReplaceReport {
+        files: files.len(),
+        replacements: files.iter().map(|file| file.edits.len()).sum(),
+    };
+    if !files.is_empty() {
+        journal.record(Operation::Replace { files }, workspace.root().as_deref());
+    }
+    Ok(report)
+}
+
+// endregion
+
+// region: ---Preview
+
+/// # TextEdit
+/// {
+///     start: usize, // byte offsets in the text of the file, with \n line endings
+///     end: usize,
+///     original: string, // text between start and end, checked before the edit
+///     replacement: string,
+/// }
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct TextEdit {
+    pub start: usize,
+    pub end: usize,
+    pub original: String,
+    pub replacement: String,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct FileEdits {
+    pub path: String,
+    pub edits: Vec<TextEdit>,
+}
+
+impl FileEdits {
+    // the edits that undo these ones once applied, the edits are sorted by their start
+    pub fn inverted(&self) -> FileEdits {
+        let mut shift: isize = 0;
+        let edits = self
+            .edits
+            .iter()
+            .map(|edit| {
+                let start = (edit.start as isize + shift) as usize;
+                shift += edit.replacement.len() as isize - (edit.end - edit.start) as isize;
+                TextEdit {
+                    start,
+                    end: start + edit.replacement.len(),
+                    original: edit.replacement.clone(),
+                    replacement: edit.original.clone(),
+                }
+            })
+            .collect();
+        FileEdits {
+            path: self.path.clone(),
+            edits,
+        }
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct FilePreview {
+    pub path: String,
+    pub relative_path: String,
+    pub matches: Vec<ReplaceMatch>,
+}
+
+// `before` and `after` are the line of the match, shortened around it when long
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ReplaceMatch {
+    pub line: usize, // 0 based
+    pub column: usize,
+    pub before: String,
+    pub after: String,
+    pub edit: TextEdit,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ReplaceReport {
+    pub files: usize,
+    pub replacements: usize,
+}
+
+// with a regex query `$1` and `${name}` in the replacement are the groups of the match
+pub fn preview(
+    root: &Path,
+    query: &SearchQuery,
+    replacement: &str,
+    options: &ListOptions,
+) -> Result<Vec<FilePreview>, Error> {
+    let regex = query.to_regex()?;
+    let max_results = query.max_results.unwrap_or(usize::MAX);
+    let matches = AtomicUsize::new(0);
+    let previews = Mutex::new(Vec::new());
+
+    for_each_file(root, options, |path| {
+        if matches.load(Ordering::Relaxed) >= max_results {
+            return false;
+        }
+        if let Some(file) = preview_file(root, path, &regex, query.regex, replacement) {
+            matches.fetch_add(file.matches.len(), Ordering::Relaxed);
+            previews.lock().unwrap().push(file);
+        }
+        true
+    })?;
+
+    let mut previews = previews.into_inner().unwrap();
+    previews.sort_by(|left, right| left.relative_path.cmp(&right.relative_path));
+    Ok(previews)
+}
+
+fn preview_file(
+    root: &Path,
+    path: &Path,
+    regex: &Regex,
+    expand: bool,
+    replacement: &str,
+) -> Option<FilePreview> {
+    if vfs::stat(path).ok()?.len > MAX_FILE_SIZE {
+        return None;
+    }
+    let text = read_text(path, false).ok()?.text;
+    let index = LineIndex::new(&text);
+    let mut matches = Vec::new();
+
+    for captures in regex.captures_iter(&text) {
+        let found = captures.get(0)?;
+        if found.is_empty() {
+            continue;
+        }
+        let mut replaced = String::new();
+        match expand {
+            true => captures.expand(replacement, &mut replaced),
+            false => replaced.push_str(replacement),
+        }
+
+        let line = index.line(found.start());
+        let line_start = index.line_start(line);
+        let line_end = text[line_start..]
+            .find('\n')
+            .map_or(text.len(), |end| line_start + end);
+        let end = found.end().min(line_end);
+        let (before, after) = preview_lines(
+            &text[line_start..line_end],
+            found.start() - line_start,
+            end - line_start,
+            &replaced,
+        );
+
+        matches.push(ReplaceMatch {
+            line,
+            column: found.start() - line_start,
+            before,
+            after,
+            edit: TextEdit {
+                start: found.start(),
+                end: found.end(),
+                original: found.as_str().to_string(),
+                replacement: replaced,
+            },
+        });
+    }
+    if matches.is_empty() {
+        return None;
+    }
+
+    Some(FilePreview {
+        path: path.to_string_lossy().to_string(),
+        relative_path: path
+            .strip_prefix(root)
+            .unwrap_or(path)
+            .to_string_lossy()
+            .replace('\\', "/"),
+        matches,
+    })
+}
+
+// the line before and after the replacement of start..end, from a little before the match
+fn preview_lines(line: &str, start: usize, end: usize, replacement: &str) -> (String, String) {
+    let floor = |mut index: usize| {
+        while !line.is_char_boundary(index) {
+            index -= 1;
+        }
+        index
+    };
+    let from = match line.len() > PREVIEW_LEN {
+        true => floor(start.saturating_sub(PREVIEW_CONTEXT)),
+        false => 0,
+    };
+    let to = floor((from + PREVIEW_LEN).max(end).min(line.len()));
+
+    let before = line[from..to].to_string();
+    let after = format!("{}{}{}", &line[from..start], replacement, &line[end..to]);
+    (before, after)
+}
+
+// endregion
+
+// region: ---Apply
+
+struct StagedFile {
+    path: PathBuf,
+    temp_path: PathBuf,
+    original: Vec<u8>, // bytes on disk before the edits, written back on failure
+}
+
+// every file is checked and written in a temporary file before the first one is replaced
+pub fn apply_edits(files: &[FileEdits]) -> Result<(), Error> {
+    let mut contents = Vec::new();
+    for file in files {
+        let path = Path::new(&file.path);
+        let original = vfs::read(path)?;
+        let edited = edited_bytes(path, &original, &file.edits)?;
+        contents.push((path, original, edited));
+    }
+
+    let mut staged: Vec<StagedFile> = Vec::new();
+    for (path, original, edited) in contents {
+        match stage_write(path, &edited) {
+            Ok(temp_path) => staged.push(StagedFile {
+                path: path.to_path_buf(),
+                temp_path,
+                original,
+            }),
+            Err(err) => {
+                for file in &staged {
+                    let _ = vfs::remove_file(&file.temp_path);
+                }
+                return Err(err);
+            }
+        }
+    }
+
+    for (replaced, file) in staged.iter().enumerate() {
+        if let Err(err) = vfs::rename(&file.temp_path, &file.path) {
+            // the replaced files get their bytes back
+            for file in &staged[..replaced] {
+                if let Err(err) = write_atomic(&file.path, &file.original) {
+                    eprintln!("failed to restore {}: {}", file.path.display(), err);
+                }
+            }
+            for file in &staged[replaced..] {
+                let _ = vfs::remove_file(&file.temp_path);
+            }
+            return Err(err.into());
+        }
+    }
+    Ok(())
+}
+
+// the bytes with the edits, in their encoding, the line endings outside of the edits are kept
+// (the offsets of the edits are in the text with \n line endings)
+fn edited_bytes(path: &Path, bytes: &[u8], edits: &[TextEdit]) -> Result<Vec<u8>, Error> {
+    let file = decode_text(path, bytes, false)?;
+    let raw = file.encoding.decode(bytes)?;
+    // offsets in the text with \n line endings of the \r\n line endings
+    let crlf: Vec<usize> = raw
+        .match_indices("\r\n")
+        .enumerate()
+        .map(|(index, (offset, _))| offset - index)
+        .collect();
+    let raw_offset = |offset: usize| offset + crlf.partition_point(|crlf| *crlf < offset);
+
+    let mut edits: Vec<&TextEdit> = edits.iter().collect();
+    edits.sort_by_key(|edit| edit.start);
+
+    let mut text = String::with_capacity(raw.len());
+    let mut copied = 0;
+    for edit in edits {
+        let unchanged = edit.start >= copied
+            && edit.end >= edit.start
+            && file.text.get(edit.start..edit.end) == Some(edit.original.as_str());
+        if !unchanged {
+            return Err(Error::Modified(path.display().to_string()));
+        }
+        text.push_str(&raw[raw_offset(copied)..raw_offset(edit.start)]);
+        text.push_str(&file.line_ending.apply(&edit.replacement));
+        copied = edit.end;
+    }
+    text.push_str(&raw[raw_offset(copied)..]);
+
+    file.encoding.encode(&text)
+}
+
+// endregion
+
+// region: ---Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::fs::vfs::MemoryVfs;
+    use std::sync::Arc;
+
+    fn edit(start: usize, original: &str, replacement: &str) -> TextEdit {
+        TextEdit {
+            start,
+            end: start + original.len(),
+            original: original.to_string(),
+            replacement: replacement.to_string(),
+        }
+    }
+
+    #[test]
+    fn inverted_edits_are_shifted_by_the_previous_ones() {
+        let file = FileEdits {
+            path: String::from("a.txt"),
+            edits: vec![edit(2, "bb", "x"), edit(5, "ccc", "yyyy")],
+        };
+        let inverted = file.inverted();
+        assert_eq!((inverted.edits[0].start, inverted.edits[0].end), (2, 3));
+        assert_eq!(inverted.edits[0].replacement, "bb");
+        assert_eq!((inverted.edits[1].start, inverted.edits[1].end), (4, 8));
+        assert_eq!(inverted.edits[1].original, "yyyy");
+    }
+
+    #[test]
+    fn applied_edits_are_undone_by_their_inversion() {
+        let folder = Path::new("/replace-undo");
+        vfs::mount(folder, Arc::new(MemoryVfs::new()));
+        vfs::create_dir_all(folder).unwrap();
+        let path = folder.join("a.txt");
+        vfs::write(&path, b"a bb ccc\r\nd").unwrap();
+
+        let file = FileEdits {
+            path: path.to_string_lossy().to_string(),
+            edits: vec![edit(2, "bb", "x"), edit(5, "ccc", "yyyy")],
+        };
+        apply_edits(std::slice::from_ref(&file)).unwrap();
+        assert_eq!(vfs::read(&path).unwrap(), b"a x yyyy\r\nd");
+
+        apply_edits(&[file.inverted()]).unwrap();
+        assert_eq!(vfs::read(&path).unwrap(), b"a bb ccc\r\nd");
+        vfs::unmount(folder);
+    }
+
+    #[test]
+    fn mixed_line_endings_are_kept() {
+        let folder = Path::new("/replace-line-endings");
+        vfs::mount(folder, Arc::new(MemoryVfs::new()));
+        vfs::create_dir_all(folder).unwrap();
+        let path = folder.join("a.txt");
+        let content = b"a\r\nbb\nccc\r\nbb\r\n";
+        vfs::write(&path, content).unwrap();
+
+        // offsets in "a\nbb\nccc\nbb\n"
+        let file = FileEdits {
+            path: path.to_string_lossy().to_string(),
+            edits: vec![edit(2, "bb", "x"), edit(9, "bb\n", "y")],
+        };
+        apply_edits(std::slice::from_ref(&file)).unwrap();
+        assert_eq!(vfs::read(&path).unwrap(), b"a\r\nx\nccc\r\ny");
+
+        apply_edits(&[file.inverted()]).unwrap();
+        assert_eq!(vfs::read(&path).unwrap(), content);
+        vfs::unmount(folder);
+    }
+
+    #[test]
+    fn edits_of_a_modified_file_are_refused() {
+        let folder = Path::new("/replace-modified");
+        vfs::mount(folder, Arc::new(MemoryVfs::new()));
+        vfs::create_dir_all(folder).unwrap();
+        let path = folder.join("a.txt");
+        vfs::write(&path, b"a bc").unwrap();
+
+        let file = FileEdits {
+            path: path.to_string_lossy().to_string(),
+            edits: vec![edit(2, "bb", "x")],
+        };
+        assert!(matches!(apply_edits(&[file]), Err(Error::Modified(_))));
+        assert_eq!(vfs::read(&path).unwrap(), b"a bc");
+        vfs::unmount(folder);
+    }
+
+    #[test]
+    fn previews_are_shortened_around_the_match() {
+        let (before, after) = preview_lines("let a = 1;", 4, 5, "b");
+        assert_eq!(
+            (before.as_str(), after.as_str()),
+            ("let a = 1;", "let b = 1;")
+        );
+
+        let line = format!("{}match{}", "x".repeat(200), "y".repeat(200));
+        let (before, after) = preview_lines(&line, 200, 205, "found");
+        assert!(before.len() <= PREVIEW_LEN && before.contains("match"));
+        assert!(after.contains("found") && !after.contains("match"));
+    }
+}
+
+// endregion
@@ -0,0 +1,116 @@
+use regex::Regex;
+use std::sync::OnceLock;
+
+// region: ---Tokenizer
+
+// terms of an indexed file, the headings and tag words are also in the body
+#[derive(Debug, Default)]
+pub struct TokenizedText {
+    pub title: Option<String>,
+    pub body: Vec<String>, // in the order of the text, for the phrase queries
+    pub headings: Vec<String>, // terms of the headings
+    pub tags: Vec<String>, // "#tag", "#project/alpha"
+}
+
+// lowercase words of letters and digits
+pub fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+pub fn tokenize_plain(text: &str) -> TokenizedText {
+    TokenizedText {
+        body: tokenize(text),
+        ..TokenizedText::default()
+    }
+}
+
+// the markdown syntax and link targets are skipped, the headings and tags are kept apart
+pub fn tokenize_markdown(text: &str) -> TokenizedText {
+    let mut tokenized = TokenizedText::default();
+    let mut lines = text.lines().peekable();
+
+    // yaml front matter, only the title and the tags are indexed
+    if lines.peek().map(|line| line.trim_end()) == Some("---") {
+        lines.next();
+        for line in lines.by_ref() {
+            let line = line.trim_end();
+            if line == "---" || line == "..." {
+                break;
+            }
+            if let Some(title) = line.strip_prefix("title:") {
+                let title = title.trim().trim_matches(['"', '\'']);
+                tokenized.title = Some(title.to_string());
+                tokenized.headings.extend(tokenize(title));
+                tokenized.body.extend(tokenize(title));
+            } else if let Some(tags) = line.strip_prefix("tags:") {
+                for tag in tags
+                    .split([',', '[', ']', ' '])
+                    .map(|tag| tag.trim().trim_matches(['"', '\'', '#']))
+                    .filter(|tag| !tag.is_empty())
+                {
+                    tokenized.tags.push(format!("#{}", tag.to_lowercase()));
+                    tokenized.body.extend(tokenize(tag));
+                }
+            }
+        }
+    }
+
+    let mut in_code = false;
+    for line in lines {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_code = !in_code;
+            continue;
+        }
+        if in_code {
+            tokenized.body.extend(tokenize(line));
+            continue;
+        }
+
+        if let Some(heading) = heading_text(trimmed) {
+            let terms = tokenize(heading);
+            if tokenized.title.is_none() && trimmed.starts_with("# ") {
+                tokenized.title = Some(heading.trim().to_string());
+            }
+            tokenized.headings.extend(terms.iter().cloned());
+            tokenized.body.extend(terms);
+            continue;
+        }
+
+        let line = link_target_regex().replace_all(line, "]");
+        for captures in tag_regex().captures_iter(&line) {
+            tokenized
+                .tags
+                .push(format!("#{}", captures[1].to_lowercase()));
+        }
+        tokenized.body.extend(tokenize(&line));
+    }
+    tokenized
+}
+
+// "## Title" -> "Title"
+fn heading_text(line: &str) -> Option<&str> {
+    let level = line.chars().take_while(|c| *c == '#').count();
+    if (1..=6).contains(&level) && line[level..].starts_with(' ') {
+        Some(line[level..].trim_end_matches(['#', ' ']))
+    } else {
+        None
+    }
+}
+
+// "](https://...)" of the links and images
+fn link_target_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| Regex::new(r"\]\([^)]*\)").unwrap())
+}
+
+// "#tag" after a space or at the start of the line, not a heading
+fn tag_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| Regex::new(r"(?:^|\s)#([\p{L}\p{N}_/-]*\p{L}[\p{L}\p{N}_/-]*)").unwrap())
+}
+
+// endregion
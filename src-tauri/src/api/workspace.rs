@@ -0,0 +1,260 @@
+use crate::api::fs::sandbox::{self, Access};
+use crate::api::fs::text::write_atomic;
+use crate::api::fs::vfs::{self, WatchGuard};
+use crate::api::fs::watcher::is_own_write;
+use crate::error::Error;
+use crate::event::{emit_fs_changed, FsChange, FsChanged};
+use crate::APP_DATA_FOLDER_NAME;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tauri::api::dialog::blocking::FileDialogBuilder;
+use tauri::api::path::data_dir;
+use tauri::{AppHandle, State};
+
+const RECENT_WORKSPACES_FILE_NAME: &str = "workspaces.json";
+
+const MAX_RECENT_WORKSPACES: usize = 10;
+
+// region: ---Tauri Command
+
+// watch the folder, the changes are emitted as `fs-changed` events and the other fs commands
+// are kept in it, the opened root is returned (None when the dialog is cancelled)
+// (without a path the user chooses the folder in a dialog, a path must be a recent workspace:
+// the webview never decides what the fs commands can reach)
+#[tauri::command]
+pub async fn open_workspace(
+    path: Option<&str>,
+    app: AppHandle,
+    workspace: State<'_, Workspace>,
+    recent: State<'_, RecentWorkspaces>,
+) -> Result<Option<String>, Error> {
+    let path = match path {
+        Some(path) if recent.contains(Path::new(path)) => PathBuf::from(path),
+        Some(path) => {
+            return Err(Error::PermissionDenied(format!(
+                "{} was not chosen as a workspace",
+                path
+            )))
+        }
+        None => match FileDialogBuilder::new().pick_folder() {
+            Some(path) => path,
+            None => return Ok(None),
+        },
+    };
+    workspace.open(&path, app)?;
+    let root = workspace.root().unwrap_or(path);
+    recent.add(&root);
+    Ok(Some(root.to_string_lossy().to_string()))
+}
+
+// most recent first
+#[tauri::command]
+pub async fn list_recent_workspaces(
+    recent: State<'_, RecentWorkspaces>,
+) -> Result<Vec<String>, Error> {
+    Ok(recent.list())
+}
+
+#[tauri::command]
+pub async fn close_workspace(workspace: State<'_, Workspace>) -> Result<(), Error> {
+    workspace.close();
+    Ok(())
+}
+
+// endregion
+
+// region: ---Workspace
+
+// subsystems kept up to date with the workspace (index, caches)
+pub trait WorkspaceListener: Send + Sync {
+    fn opened(&self, _root: &Path) {}
+
+    // called with all the changes of the workspace, including the ones made by the app
+    fn changed(&self, root: &Path, changes: &[FsChange]);
+}
+
+#[derive(Default)]
+pub struct Workspace {
+    watch: Mutex<Option<(PathBuf, WatchGuard)>>, // root and its watch
+    listeners: Arc<Mutex<Vec<Box<dyn WorkspaceListener>>>>,
+}
+
+impl Workspace {
+    pub fn open(&self, path: &Path, app: AppHandle) -> Result<(), Error> {
+        if !vfs::is_dir(path) {
+            return Err(Error::InvalidPath(format!(
+                "{} is not a folder",
+                path.display()
+            )));
+        }
+        let root = vfs::canonicalize(path)?;
+
+        // the previous watcher is stopped before the new one starts
+        self.close();
+
+        let listeners = self.listeners.clone();
+        let watched_root = root.clone();
+        let on_change = move |changes: Vec<FsChange>| {
+            for listener in listeners.lock().unwrap().iter() {
+                listener.changed(&watched_root, &changes);
+            }
+
+            // the frontend already knows the changes made by the app
+            let changes: Vec<FsChange> = changes
+                .into_iter()
+                .filter(|change| !is_own_write(change))
+                .collect();
+            if changes.is_empty() {
+                return;
+            }
+            emit_fs_changed(
+                &app,
+                FsChanged {
+                    root: watched_root.to_string_lossy().to_string(),
+                    changes,
+                },
+            );
+        };
+        let guard = vfs::watch(&root, Box::new(on_change))?;
+        *self.watch.lock().unwrap() = Some((root.clone(), guard));
+
+        for listener in self.listeners.lock().unwrap().iter() {
+            listener.opened(&root);
+        }
+        Ok(())
+    }
+
+    pub fn close(&self) {
+        self.watch.lock().unwrap().take();
+    }
+
+    pub fn root(&self) -> Option<PathBuf> {
+        self.watch
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|(root, _)| root.clone())
+    }
+
+    // the path read by an fs command, refused outside of the workspace and the app data caches
+    pub fn resolve(&self, path: &str) -> Result<PathBuf, Error> {
+        sandbox::resolve(self.root().as_deref(), Path::new(path), Access::Read)
+    }
+
+    // the path written by an fs command, refused outside of the workspace
+    pub fn resolve_writable(&self, path: &str) -> Result<PathBuf, Error> {
+        sandbox::resolve(self.root().as_deref(), Path::new(path), Access::Write)
+    }
+
+    pub fn add_listener(&self, listener: Box<dyn WorkspaceListener>) {
+        self.listeners.lock().unwrap().push(listener);
+    }
+}
+
+// endregion
+
+// region: ---Recent Workspaces
+
+// the folders chosen by the user in the dialog, the only ones opened without it
+pub struct RecentWorkspaces {
+    path: PathBuf,
+    roots: Mutex<Vec<String>>,
+}
+
+impl RecentWorkspaces {
+    // the list of the app data folder, empty if it can't be read
+    pub fn load() -> RecentWorkspaces {
+        let path = data_dir()
+            .unwrap_or_default()
+            .join(APP_DATA_FOLDER_NAME)
+            .join(RECENT_WORKSPACES_FILE_NAME);
+        RecentWorkspaces::load_from(path)
+    }
+
+    pub fn load_from(path: PathBuf) -> RecentWorkspaces {
+        let roots = vfs::read(&path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default();
+        RecentWorkspaces {
+            path,
+            roots: Mutex::new(roots),
+        }
+    }
+
+    // the folders are compared by their canonical path
+    pub fn contains(&self, path: &Path) -> bool {
+        let path = match vfs::canonicalize(path) {
+            Ok(path) => path,
+            Err(_) => return false,
+        };
+        self.roots
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|root| Path::new(root) == path)
+    }
+
+    pub fn list(&self) -> Vec<String> {
+        self.roots.lock().unwrap().clone()
+    }
+
+    // the root moves first, the oldest ones are forgotten
+    pub fn add(&self, root: &Path) {
+        let root = root.to_string_lossy().to_string();
+        let mut roots = self.roots.lock().unwrap();
+        roots.retain(|recent| *recent != root);
+        roots.insert(0, root);
+        roots.truncate(MAX_RECENT_WORKSPACES);
+
+        let result = (|| {
+            if let Some(parent) = self.path.parent() {
+                vfs::create_dir_all(parent)?;
+            }
+            let json = serde_json::to_vec(&*roots).map_err(|err| Error::Io(err.to_string()))?;
+            write_atomic(&self.path, &json)
+        })();
+        if let Err(err) = result {
+            eprintln!("failed to save the recent workspaces: {}", err);
+        }
+    }
+}
+
+// endregion
+
+// region: ---Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::fs::vfs::MemoryVfs;
+
+    #[test]
+    fn recent_workspaces_are_kept_most_recent_first() {
+        let folder = Path::new("/recent-workspaces");
+        vfs::mount(folder, Arc::new(MemoryVfs::new()));
+        for index in 0..=MAX_RECENT_WORKSPACES {
+            vfs::create_dir_all(&folder.join(index.to_string())).unwrap();
+        }
+        let path = folder.join("data/workspaces.json");
+
+        let recent = RecentWorkspaces::load_from(path.clone());
+        for index in (0..=MAX_RECENT_WORKSPACES).rev() {
+            recent.add(&folder.join(index.to_string()));
+        }
+        recent.add(&folder.join("1"));
+        let roots = recent.list();
+        assert_eq!(roots.len(), MAX_RECENT_WORKSPACES);
+        assert_eq!(roots[..2], ["/recent-workspaces/1", "/recent-workspaces/0"]);
+
+        // the list is read back, the forgotten and unknown folders are not opened
+        let recent = RecentWorkspaces::load_from(path);
+        assert_eq!(recent.list(), roots);
+        assert!(recent.contains(&folder.join("0/../1")));
+        assert!(!recent.contains(&folder.join(MAX_RECENT_WORKSPACES.to_string())));
+        assert!(!recent.contains(&folder.join("data")));
+        vfs::unmount(folder);
+    }
+}
+
+// endregion
@@ -1 +1,52 @@
+use std::fmt::{Display, Formatter};
+use std::io;
 
+// region: ---Error
+
+// error returned by the fs commands, serialized as { kind, message }
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(tag = "kind", content = "message")]
+pub enum Error {
+    NotFound(String),
+    PermissionDenied(String),
+    AlreadyExists(String),
+    InvalidPath(String),
+    InvalidPattern(String),
+    BinaryFile(String),
+    Unsupported(String),
+    Modified(String),         // the file changed on disk since it was read
+    OutsideWorkspace(String), // the path is not in the workspace nor in the app data folder
+    Encoding(String),
+    Io(String),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::NotFound(message) => write!(f, "not found: {}", message),
+            Error::PermissionDenied(message) => write!(f, "permission denied: {}", message),
+            Error::AlreadyExists(message) => write!(f, "already exists: {}", message),
+            Error::InvalidPath(message) => write!(f, "invalid path: {}", message),
+            Error::InvalidPattern(message) => write!(f, "invalid pattern: {}", message),
+            Error::BinaryFile(message) => write!(f, "binary file: {}", message),
+            Error::Unsupported(message) => write!(f, "unsupported: {}", message),
+            Error::Modified(message) => write!(f, "modified on disk: {}", message),
+            Error::OutsideWorkspace(message) => write!(f, "outside of the workspace: {}", message),
+            Error::Encoding(message) => write!(f, "encoding: {}", message),
+            Error::Io(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        match err.kind() {
+            io::ErrorKind::NotFound => Error::NotFound(err.to_string()),
+            io::ErrorKind::PermissionDenied => Error::PermissionDenied(err.to_string()),
+            io::ErrorKind::AlreadyExists => Error::AlreadyExists(err.to_string()),
+            _ => Error::Io(err.to_string()),
+        }
+    }
+}
+
+// endregion
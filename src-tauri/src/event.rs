@@ -1 +1,189 @@
+use crate::api::fs::duplicates::{DuplicateGroup, DuplicateSummary};
+use crate::api::search::{FileMatches, SearchSummary};
+use crate::error::Error;
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
 
+// region: ---Fs Event
+
+// emitted with the changes of the workspace made outside of the app
+pub const FS_CHANGED: &str = "fs-changed";
+
+/// # FsChanged
+/// {
+///     root: string,
+///     changes: [
+///         { type: "created" | "modified" | "removed", path: string }
+///         | { type: "renamed", from: string, to: string },
+///         ...
+///     ],
+/// }
+#[derive(Debug, Clone, Serialize)]
+pub struct FsChanged {
+    pub root: String,
+    pub changes: Vec<FsChange>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum FsChange {
+    Created { path: String },
+    Modified { path: String },
+    Removed { path: String },
+    Renamed { from: String, to: String },
+}
+
+impl FsChange {
+    // paths touched by the change
+    pub fn paths(&self) -> Vec<&str> {
+        match self {
+            FsChange::Created { path }
+            | FsChange::Modified { path }
+            | FsChange::Removed { path } => {
+                vec![path]
+            }
+            FsChange::Renamed { from, to } => vec![from, to],
+        }
+    }
+}
+
+pub fn emit_fs_changed(app: &AppHandle, payload: FsChanged) {
+    if let Err(err) = app.emit_all(FS_CHANGED, payload) {
+        eprintln!("failed to emit {}: {}", FS_CHANGED, err);
+    }
+}
+
+// endregion
+
+// region: ---Search Event
+
+// emitted for every file with a match of a running search
+pub const SEARCH_RESULT: &str = "search-result";
+
+// emitted once when the search ends, is cancelled or fails
+pub const SEARCH_FINISHED: &str = "search-finished";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchResult {
+    pub id: u32,
+    pub file: FileMatches,
+}
+
+/// # SearchFinished
+/// {
+///     id: number,
+///     files: number,
+///     matches: number,
+///     cancelled: bool,
+///     error?: { kind, message },
+/// }
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchFinished {
+    pub id: u32,
+    #[serde(flatten)]
+    pub summary: SearchSummary,
+    pub error: Option<Error>,
+}
+
+pub fn emit_search_result(app: &AppHandle, payload: SearchResult) {
+    if let Err(err) = app.emit_all(SEARCH_RESULT, payload) {
+        eprintln!("failed to emit {}: {}", SEARCH_RESULT, err);
+    }
+}
+
+pub fn emit_search_finished(app: &AppHandle, payload: SearchFinished) {
+    if let Err(err) = app.emit_all(SEARCH_FINISHED, payload) {
+        eprintln!("failed to emit {}: {}", SEARCH_FINISHED, err);
+    }
+}
+
+// endregion
+
+// region: ---Duplicates Event
+
+// emitted for every group of duplicates found by a running scan
+pub const DUPLICATES_FOUND: &str = "duplicates-found";
+
+// emitted once when the scan ends, is cancelled or fails
+pub const DUPLICATES_FINISHED: &str = "duplicates-finished";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DuplicatesFound {
+    pub id: u32,
+    pub group: DuplicateGroup,
+}
+
+/// # DuplicatesFinished
+/// {
+///     id: number,
+///     files: number,
+///     groups: number,
+///     wasted: number,
+///     cancelled: bool,
+///     error?: { kind, message },
+/// }
+#[derive(Debug, Clone, Serialize)]
+pub struct DuplicatesFinished {
+    pub id: u32,
+    #[serde(flatten)]
+    pub summary: DuplicateSummary,
+    pub error: Option<Error>,
+}
+
+pub fn emit_duplicates_found(app: &AppHandle, payload: DuplicatesFound) {
+    if let Err(err) = app.emit_all(DUPLICATES_FOUND, payload) {
+        eprintln!("failed to emit {}: {}", DUPLICATES_FOUND, err);
+    }
+}
+
+pub fn emit_duplicates_finished(app: &AppHandle, payload: DuplicatesFinished) {
+    if let Err(err) = app.emit_all(DUPLICATES_FINISHED, payload) {
+        eprintln!("failed to emit {}: {}", DUPLICATES_FINISHED, err);
+    }
+}
+
+// endregion
+
+// region: ---Document Event
+
+// emitted when the file of a document with unsaved edits changes on disk
+pub const DOCUMENT_CONFLICT: &str = "document-conflict";
+
+/// # DocumentConflict
+/// {
+///     path: string,
+///     deleted: bool, // the file is not on disk anymore
+/// }
+#[derive(Debug, Clone, Serialize)]
+pub struct DocumentConflict {
+    pub path: String,
+    pub deleted: bool,
+}
+
+pub fn emit_document_conflict(app: &AppHandle, payload: DocumentConflict) {
+    if let Err(err) = app.emit_all(DOCUMENT_CONFLICT, payload) {
+        eprintln!("failed to emit {}: {}", DOCUMENT_CONFLICT, err);
+    }
+}
+
+// emitted when a document without unsaved edits takes the text of its file changed on disk
+pub const DOCUMENT_RELOADED: &str = "document-reloaded";
+
+/// # DocumentReloaded
+/// {
+///     path: string,
+///     text: string, // the new saved text of the document, with \n line endings
+/// }
+#[derive(Debug, Clone, Serialize)]
+pub struct DocumentReloaded {
+    pub path: String,
+    pub text: String,
+}
+
+pub fn emit_document_reloaded(app: &AppHandle, payload: DocumentReloaded) {
+    if let Err(err) = app.emit_all(DOCUMENT_RELOADED, payload) {
+        eprintln!("failed to emit {}: {}", DOCUMENT_RELOADED, err);
+    }
+}
+
+// endregion
@@ -9,29 +9,64 @@ mod event;
 mod model;
 
 use serde_json::json;
-use std::fs::create_dir;
-use std::fs::write;
 
-use tauri::api::dir::read_dir;
 use tauri::api::path::data_dir;
 
-use crate::api::fs::get_disk_entry_from_path;
+use crate::api::brackets::{get_auto_closing_pairs, get_bracket_match};
+use crate::api::completion::{
+    get_completions, register_completion_words, register_json_schema, CompletionProviders,
+};
+use crate::api::diff::{diff_files, diff_texts, merge_texts};
+use crate::api::folding::get_folding_ranges;
+use crate::api::format::{format_document, format_selection};
+use crate::api::fs::archive::extract_archive_file;
+use crate::api::fs::documents::{
+    close_document, merge_document, open_document, save_document, set_document_dirty, Documents,
+};
+use crate::api::fs::duplicates::start_duplicate_scan;
+use crate::api::fs::history::{
+    diff_file_version, get_history_retention, list_file_versions, restore_file_version,
+    set_history_retention, History,
+};
+use crate::api::fs::journal::{get_journal, redo_operations, undo_operations, Journal};
+use crate::api::fs::operations::{
+    copy_entries, create_file, create_folder, delete_entries, list_trash, move_entries,
+    rename_entry, restore_from_trash,
+};
+use crate::api::fs::text::{read_text_file, write_text_file};
+use crate::api::fs::thumbnail::{get_file_type, get_thumbnail};
+use crate::api::fs::usage::{get_disk_usage, DiskUsage};
+use crate::api::fs::vfs;
+use crate::api::fs::{get_disk_entry_from_path, list_directory};
 use crate::api::grammar::json_to_grammar::json_to_grammar;
 use crate::api::language::json::get_json_grammar;
+use crate::api::outline::get_outline;
+use crate::api::search::index::{get_index_status, rebuild_index, search_index, SearchIndex};
+use crate::api::search::quick_open::{quick_open, FileList};
+use crate::api::search::replace::{apply_replace, preview_replace};
+use crate::api::search::{cancel_search, start_search, Searches};
+use crate::api::workspace::{
+    close_workspace, list_recent_workspaces, open_workspace, RecentWorkspaces, Workspace,
+};
 
 const APP_DATA_FOLDER_NAME: &str = "Workspace";
 
 #[tauri::command]
-async fn log_roaming_data(path_dir: &str) -> Result<String, String> {
+async fn log_roaming_data(
+    path_dir: &str,
+    workspace: tauri::State<'_, Workspace>,
+) -> Result<String, String> {
+    let path_dir = workspace.resolve(path_dir).map_err(|err| err.to_string())?;
     let path = data_dir().unwrap().join(APP_DATA_FOLDER_NAME);
-    let _ = create_dir(path.clone());
-    let _ = write(path.join("test.txt"), "Some text...");
+    let _ = vfs::create_dir_all(&path);
+    let _ = vfs::write(&path.join("test.txt"), b"Some text...");
 
     let mut str = String::from("");
-    for entry in read_dir(path_dir, false).unwrap().iter() {
-        let x = entry.name.as_ref().unwrap();
-        str.push_str(x);
-        str.push_str("/");
+    for entry in vfs::list(&path_dir).map_err(|err| err.to_string())? {
+        if let Some(name) = entry.file_name() {
+            str.push_str(&name.to_string_lossy());
+            str.push('/');
+        }
     }
 
     Ok(str)
@@ -50,10 +85,83 @@ fn main() {
 }
 
 fn setup() {
+    // the index and the file list follow the changes of the workspace
+    let workspace = Workspace::default();
+    let index = SearchIndex::default();
+    let file_list = FileList::default();
+    let disk_usage = DiskUsage::default();
+    let documents = Documents::default();
+    workspace.add_listener(Box::new(index.clone()));
+    workspace.add_listener(Box::new(file_list.clone()));
+    workspace.add_listener(Box::new(disk_usage.clone()));
+    workspace.add_listener(Box::new(documents.clone()));
+
     tauri::Builder::default()
+        .manage(CompletionProviders::default())
+        .manage(workspace)
+        .manage(RecentWorkspaces::load())
+        .manage(Journal::load())
+        .manage(History::load())
+        .manage(Searches::default())
+        .manage(index)
+        .manage(file_list)
+        .manage(disk_usage)
+        .manage(documents)
         .invoke_handler(tauri::generate_handler![
             log_roaming_data,
-            get_disk_entry_from_path
+            open_workspace,
+            close_workspace,
+            list_recent_workspaces,
+            get_disk_entry_from_path,
+            list_directory,
+            get_file_type,
+            get_thumbnail,
+            get_disk_usage,
+            extract_archive_file,
+            create_file,
+            create_folder,
+            rename_entry,
+            move_entries,
+            copy_entries,
+            delete_entries,
+            list_trash,
+            restore_from_trash,
+            undo_operations,
+            redo_operations,
+            get_journal,
+            start_search,
+            cancel_search,
+            start_duplicate_scan,
+            preview_replace,
+            apply_replace,
+            search_index,
+            quick_open,
+            rebuild_index,
+            get_index_status,
+            read_text_file,
+            write_text_file,
+            list_file_versions,
+            diff_file_version,
+            restore_file_version,
+            get_history_retention,
+            set_history_retention,
+            diff_texts,
+            diff_files,
+            merge_texts,
+            open_document,
+            set_document_dirty,
+            save_document,
+            merge_document,
+            close_document,
+            format_document,
+            format_selection,
+            get_outline,
+            get_folding_ranges,
+            get_bracket_match,
+            get_auto_closing_pairs,
+            get_completions,
+            register_completion_words,
+            register_json_schema
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
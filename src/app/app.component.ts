@@ -12,9 +12,13 @@ export class AppComponent implements OnInit {
   constructor(private fs: FsService) {}
 
   async ngOnInit() {
-    let project = await this.fs.getDiskEntry(
-      "C:\\Users\\nolan\\OneDrive\\Bureau\\Project\\logisim"
-    );
+    // the fs commands only reach the opened workspace, the last one or a folder chosen by the user
+    const recent = await this.fs.listRecentWorkspaces();
+    const root = await this.fs.openWorkspace(recent[0]).catch(() => this.fs.openWorkspace());
+    if (root === null) {
+      return;
+    }
+    let project = await this.fs.getDiskEntry(root);
     console.log(project);
   }
 }
@@ -0,0 +1,97 @@
+import { Injectable } from "@angular/core";
+import { invoke } from "@tauri-apps/api";
+
+@Injectable({
+  providedIn: "root",
+})
+export class DiffService {
+  constructor() {}
+
+  public diffTexts(
+    oldText: string,
+    newText: string,
+    options?: DiffOptions
+  ): Promise<TextDiff> {
+    return invoke<TextDiff>("diff_texts", {
+      old: oldText,
+      new: newText,
+      options: options,
+    });
+  }
+
+  // relative paths are from the workspace root
+  public diffFiles(
+    oldPath: string,
+    newPath: string,
+    options?: DiffOptions
+  ): Promise<TextDiff> {
+    return invoke<TextDiff>("diff_files", {
+      oldPath: oldPath,
+      newPath: newPath,
+      options: options,
+    });
+  }
+
+  // the lines changed differently on both sides are left between conflict markers
+  public mergeTexts(
+    base: string,
+    ours: string,
+    theirs: string,
+    labels?: { ours?: string; theirs?: string }
+  ): Promise<Merge> {
+    return invoke<Merge>("merge_texts", {
+      base: base,
+      ours: ours,
+      theirs: theirs,
+      labels: labels,
+    });
+  }
+}
+
+export interface DiffOptions {
+  context?: number;
+  words?: boolean;
+  ignoreWhitespace?: boolean;
+  unified?: boolean;
+  oldName?: string;
+  newName?: string;
+}
+
+// lines are 0 based, words are byte ranges of the changed words in the text
+export interface DiffLine {
+  kind: "equal" | "added" | "removed";
+  text: string;
+  oldLine?: number;
+  newLine?: number;
+  words?: [number, number][];
+}
+
+export interface Hunk {
+  oldStart: number;
+  oldLines: number;
+  newStart: number;
+  newLines: number;
+  lines: DiffLine[];
+}
+
+export interface TextDiff {
+  hunks: Hunk[];
+  added: number;
+  removed: number;
+  unified?: string;
+}
+
+// line is the 0 based line of the <<<<<<< marker in the merged text
+export interface MergeConflict {
+  line: number;
+  lines: number;
+  base: string;
+  ours: string;
+  theirs: string;
+}
+
+export interface Merge {
+  text: string;
+  conflicts: MergeConflict[];
+  clean: boolean;
+}
@@ -1,5 +1,7 @@
 import { Injectable } from "@angular/core";
 import { invoke } from "@tauri-apps/api";
+import { listen, UnlistenFn } from "@tauri-apps/api/event";
+import { DiffOptions, Merge, TextDiff } from "./diff.service";
 
 @Injectable({
   providedIn: "root",
@@ -7,27 +9,288 @@ import { invoke } from "@tauri-apps/api";
 export class FsService {
   constructor() {}
 
-  public async getDiskEntry(path: string): Promise<DiskEntry> {
+  public async getDiskEntry(
+    path: string,
+    options?: ListOptions
+  ): Promise<DiskEntry> {
     const diskEntry = await invoke<Object>("get_disk_entry_from_path", {
       path: path,
+      options: options,
     });
 
     return this.getDiskEntryFromObject(diskEntry);
   }
 
+  public async listDirectory(
+    path: string,
+    offset?: number,
+    limit?: number,
+    options?: ListOptions
+  ): Promise<DirectoryPage> {
+    const page = await invoke<any>("list_directory", {
+      path: path,
+      offset: offset,
+      limit: limit,
+      options: options,
+    });
+
+    return new DirectoryPage(
+      page.entries.map((diskEntry: any) =>
+        this.getDiskEntryFromObject(diskEntry)
+      ),
+      page.offset,
+      page.total,
+      page.has_more
+    );
+  }
+
+  // kind and mime type sniffed from the content, with the dimensions of the images
+  public getFileType(
+    path: string
+  ): Promise<FileType & { dimensions: Dimensions | null }> {
+    return invoke("get_file_type", { path: path });
+  }
+
+  public getThumbnail(path: string, size?: number): Promise<Thumbnail> {
+    return invoke<Thumbnail>("get_thumbnail", { path: path, size: size });
+  }
+
+  // read-only copy of a file of a .zip archive, for the viewers of binary files
+  public extractArchiveFile(path: string): Promise<string> {
+    return invoke<string>("extract_archive_file", { path: path });
+  }
+
+  // cumulative sizes of the folder (the workspace root by default) and of its subfolders
+  public getDiskUsage(path?: string, depth?: number): Promise<FolderUsage> {
+    return invoke<FolderUsage>("get_disk_usage", { path: path, depth: depth });
+  }
+
+  // watch the folder, changes made outside of the app are sent to onFsChanged
+  // without a path the user chooses the folder in a dialog, a path must be a recent workspace
+  // returns the opened root, null when the dialog is cancelled
+  public openWorkspace(path?: string): Promise<string | null> {
+    return invoke<string | null>("open_workspace", { path: path });
+  }
+
+  // folders opened from the dialog, most recent first
+  public listRecentWorkspaces(): Promise<string[]> {
+    return invoke<string[]>("list_recent_workspaces");
+  }
+
+  public async closeWorkspace(): Promise<void> {
+    await invoke("close_workspace");
+  }
+
+  public onFsChanged(
+    callback: (root: string, changes: FsChange[]) => void
+  ): Promise<UnlistenFn> {
+    return listen<any>("fs-changed", (event) =>
+      callback(event.payload.root, event.payload.changes)
+    );
+  }
+
+  // hash the files of the workspace, groups are sent to onDuplicatesFound as they are found
+  // (the scan is cancelled with SearchService.cancelSearch)
+  public startDuplicateScan(options?: ListOptions): Promise<number> {
+    return invoke<number>("start_duplicate_scan", { options: options });
+  }
+
+  public onDuplicatesFound(
+    callback: (id: number, group: DuplicateGroup) => void
+  ): Promise<UnlistenFn> {
+    return listen<any>("duplicates-found", (event) =>
+      callback(event.payload.id, event.payload.group)
+    );
+  }
+
+  public onDuplicatesFinished(
+    callback: (finished: DuplicatesFinished) => void
+  ): Promise<UnlistenFn> {
+    return listen<DuplicatesFinished>("duplicates-finished", (event) =>
+      callback(event.payload)
+    );
+  }
+
+  public async createFile(path: string): Promise<void> {
+    await invoke("create_file", { path: path });
+  }
+
+  public async createFolder(path: string): Promise<void> {
+    await invoke("create_folder", { path: path });
+  }
+
+  // openFiles are returned with their new path in MoveReport.openFiles
+  public renameEntry(
+    path: string,
+    name: string,
+    openFiles?: string[]
+  ): Promise<MoveReport> {
+    return invoke<MoveReport>("rename_entry", {
+      path: path,
+      name: name,
+      openFiles: openFiles,
+    });
+  }
+
+  public moveEntries(
+    paths: string[],
+    destination: string,
+    conflict?: ConflictPolicy,
+    openFiles?: string[]
+  ): Promise<MoveReport> {
+    return invoke<MoveReport>("move_entries", {
+      paths: paths,
+      destination: destination,
+      conflict: conflict,
+      openFiles: openFiles,
+    });
+  }
+
+  public copyEntries(
+    paths: string[],
+    destination: string,
+    conflict?: ConflictPolicy
+  ): Promise<MoveReport> {
+    return invoke<MoveReport>("copy_entries", {
+      paths: paths,
+      destination: destination,
+      conflict: conflict,
+    });
+  }
+
+  // the entries are moved to the .trash folder of the workspace
+  public deleteEntries(paths: string[]): Promise<TrashItem[]> {
+    return invoke<TrashItem[]>("delete_entries", { paths: paths });
+  }
+
+  public listTrash(): Promise<TrashItem[]> {
+    return invoke<TrashItem[]>("list_trash");
+  }
+
+  public restoreFromTrash(
+    id: string,
+    conflict?: ConflictPolicy
+  ): Promise<string | null> {
+    return invoke<string | null>("restore_from_trash", {
+      id: id,
+      conflict: conflict,
+    });
+  }
+
+  // the journal of the explorer operations is kept between sessions
+  public undoOperations(count?: number): Promise<any[]> {
+    return invoke<any[]>("undo_operations", { count: count });
+  }
+
+  public redoOperations(count?: number): Promise<any[]> {
+    return invoke<any[]>("redo_operations", { count: count });
+  }
+
+  public getJournal(): Promise<{ done: any[]; undone: any[] }> {
+    return invoke<{ done: any[]; undone: any[] }>("get_journal");
+  }
+
+  // the text of the file, changes on disk while it has unsaved edits are sent to
+  // onDocumentConflict until it is closed, the others to onDocumentReloaded
+  public openDocument(path: string): Promise<any> {
+    return invoke<any>("open_document", { path: path });
+  }
+
+  public async setDocumentDirty(path: string, dirty: boolean): Promise<void> {
+    await invoke("set_document_dirty", { path: path, dirty: dirty });
+  }
+
+  // refused when the file changed on disk since it was opened, saved or merged, unless force
+  public async saveDocument(
+    path: string,
+    text: string,
+    force?: boolean
+  ): Promise<void> {
+    await invoke("save_document", { path: path, text: text, force: force });
+  }
+
+  // the unsaved text merged with the file on disk, which becomes the saved text
+  public mergeDocument(path: string, text: string): Promise<DocumentMerge> {
+    return invoke<DocumentMerge>("merge_document", { path: path, text: text });
+  }
+
+  public async closeDocument(path: string): Promise<void> {
+    await invoke("close_document", { path: path });
+  }
+
+  public onDocumentConflict(
+    callback: (path: string, deleted: boolean) => void
+  ): Promise<UnlistenFn> {
+    return listen<any>("document-conflict", (event) =>
+      callback(event.payload.path, event.payload.deleted)
+    );
+  }
+
+  // the new text of a document without unsaved edits whose file changed on disk
+  public onDocumentReloaded(
+    callback: (path: string, text: string) => void
+  ): Promise<UnlistenFn> {
+    return listen<any>("document-reloaded", (event) =>
+      callback(event.payload.path, event.payload.text)
+    );
+  }
+
+  // versions kept on every save of the file, newest first
+  public listFileVersions(path: string): Promise<FileVersion[]> {
+    return invoke<FileVersion[]>("list_file_versions", { path: path });
+  }
+
+  // lines from the version to the file on disk
+  public diffFileVersion(
+    path: string,
+    id: number,
+    options?: DiffOptions
+  ): Promise<VersionDiff> {
+    return invoke<VersionDiff>("diff_file_version", {
+      path: path,
+      id: id,
+      options: options,
+    });
+  }
+
+  // the text written back, the text it replaced is kept as a version
+  public restoreFileVersion(path: string, id: number): Promise<any> {
+    return invoke<any>("restore_file_version", { path: path, id: id });
+  }
+
+  public getHistoryRetention(): Promise<Retention> {
+    return invoke<Retention>("get_history_retention");
+  }
+
+  public async setHistoryRetention(retention: Retention): Promise<void> {
+    await invoke("set_history_retention", { retention: retention });
+  }
+
   getDiskEntryFromObject(diskEntry: any): DiskEntry {
     // folder or file
     if ("File" in diskEntry) {
       return this.getFileFromObject(diskEntry.File);
     } else if ("Folder" in diskEntry) {
       return this.getFolderFromObject(diskEntry.Folder);
+    } else if ("Error" in diskEntry) {
+      return new EntryError(
+        diskEntry.Error.name,
+        diskEntry.Error.error.kind,
+        diskEntry.Error.error.message
+      );
     } else {
       throw new Error("Error");
     }
   }
 
   getFileFromObject(file: any): File {
-    return new File(file.name, file.extension, file.data);
+    return new File(
+      file.name,
+      file.extension,
+      file.data,
+      file.file_type,
+      this.getMetadataFromObject(file.metadata)
+    );
   }
 
   getFolderFromObject(folder: any): Folder {
@@ -35,21 +298,206 @@ export class FsService {
       folder.name,
       folder.disk_entries.map((diskEntry: any) =>
         this.getDiskEntryFromObject(diskEntry)
-      )
+      ),
+      folder.has_children,
+      this.getMetadataFromObject(folder.metadata),
+      folder.usage
+    );
+  }
+
+  getMetadataFromObject(metadata: any): Metadata {
+    return new Metadata(
+      metadata.path,
+      metadata.relative_path,
+      metadata.size,
+      metadata.created,
+      metadata.modified,
+      metadata.accessed,
+      metadata.readonly,
+      metadata.hidden,
+      metadata.symlink &&
+        new Symlink(
+          metadata.symlink.target,
+          metadata.symlink.broken,
+          metadata.symlink.is_loop,
+          metadata.symlink.outside
+        )
     );
   }
 }
 
-export type DiskEntry = File | Folder;
+export type DiskEntry = File | Folder | EntryError;
 
 export class File {
   constructor(
     public name: string | null,
     public extension: string | null,
-    public data: string | null
+    public data: string | null,
+    public fileType: FileType,
+    public metadata: Metadata
   ) {}
 }
 
 export class Folder {
-  constructor(public name: string | null, public diskEntries: DiskEntry[]) {}
+  constructor(
+    public name: string | null,
+    public diskEntries: DiskEntry[],
+    public hasChildren: boolean,
+    public metadata: Metadata,
+    public usage: Usage | null // of the listed entries, null without the children
+  ) {}
+}
+
+// times are in milliseconds since the unix epoch
+export class Metadata {
+  constructor(
+    public path: string,
+    public relativePath: string | null,
+    public size: number,
+    public created: number | null,
+    public modified: number | null,
+    public accessed: number | null,
+    public readonly: boolean,
+    public hidden: boolean,
+    public symlink: Symlink | null
+  ) {}
+}
+
+export class Symlink {
+  constructor(
+    public target: string,
+    public broken: boolean,
+    public isLoop: boolean,
+    public outside: boolean // the target is outside of the workspace, it is not read
+  ) {}
+}
+
+export class EntryError {
+  constructor(
+    public name: string | null,
+    public kind: string,
+    public message: string
+  ) {}
+}
+
+export class DirectoryPage {
+  constructor(
+    public entries: DiskEntry[],
+    public offset: number,
+    public total: number,
+    public hasMore: boolean
+  ) {}
+}
+
+export type FileKind =
+  | "text"
+  | "image"
+  | "pdf"
+  | "audio"
+  | "video"
+  | "archive"
+  | "binary";
+
+export interface FileType {
+  kind: FileKind;
+  mime: string;
+}
+
+export interface Dimensions {
+  width: number;
+  height: number;
+}
+
+// path is the png file in the cache, for convertFileSrc
+export interface Thumbnail {
+  path: string;
+  width: number;
+  height: number;
+  image: Dimensions;
+}
+
+export type FsChange =
+  | { type: "created" | "modified" | "removed"; path: string }
+  | { type: "renamed"; from: string; to: string };
+
+// exclude holds gitignore patterns relative to the workspace root
+export interface ListOptions {
+  sortBy?: "name" | "modified" | "size" | "type";
+  descending?: boolean;
+  foldersFirst?: boolean;
+  showHidden?: boolean;
+  useIgnoreFiles?: boolean;
+  exclude?: string[];
+  openArchives?: boolean; // .zip files are listed as read-only folders
+}
+
+export interface Usage {
+  size: number;
+  files: number;
+  folders: number;
+}
+
+// types are by lowercase extension, "" for the files without one
+// complete is false while the workspace is still being measured
+export interface FolderUsage extends Usage {
+  path: string;
+  types: ({ extension: string } & Omit<Usage, "folders">)[];
+  children: FolderUsage[];
+  complete: boolean;
+}
+
+export interface FileVersion {
+  id: number;
+  time: number;
+  size: number;
+  hash: string;
+}
+
+// <<<<<<< editor marks the unsaved lines, >>>>>>> disk the ones of the file
+export interface DocumentMerge extends Merge {
+  disk: string;
+}
+
+export interface VersionDiff extends TextDiff {
+  version: FileVersion;
+}
+
+// 0 for no limit, the last version of a file is always kept
+export interface Retention {
+  maxVersions: number;
+  maxAgeDays: number;
+}
+
+export type ConflictPolicy = "fail" | "skip" | "overwrite" | "keepBoth";
+
+export interface MoveReport {
+  // an entry replaced by the overwrite policy is in the trash
+  moved: { from: string; to: string; overwritten?: TrashItem }[];
+  skipped: string[];
+  open_files: { from: string; to: string }[];
+}
+
+export interface TrashItem {
+  id: string;
+  name: string;
+  originalPath: string;
+  deletedAt: number;
+}
+
+// similar groups are notes with the same text once case and blanks are ignored
+export interface DuplicateGroup {
+  kind: "identical" | "similar";
+  hash: string;
+  size: number;
+  files: { path: string; relativePath: string; modified: number }[];
+}
+
+// wasted is the size of the identical copies past the first one
+export interface DuplicatesFinished {
+  id: number;
+  files: number;
+  groups: number;
+  wasted: number;
+  cancelled: boolean;
+  error?: { kind: string; message: string };
 }
@@ -0,0 +1,155 @@
+import { Injectable } from "@angular/core";
+import { invoke } from "@tauri-apps/api";
+import { listen, UnlistenFn } from "@tauri-apps/api/event";
+import { ListOptions } from "./fs.service";
+
+@Injectable({
+  providedIn: "root",
+})
+export class SearchService {
+  constructor() {}
+
+  // the results come with the id of the search in onSearchResult
+  public startSearch(
+    query: SearchQuery,
+    options?: ListOptions
+  ): Promise<number> {
+    return invoke<number>("start_search", { query: query, options: options });
+  }
+
+  public async cancelSearch(id: number): Promise<void> {
+    await invoke("cancel_search", { id: id });
+  }
+
+  public onSearchResult(
+    callback: (id: number, file: FileMatches) => void
+  ): Promise<UnlistenFn> {
+    return listen<any>("search-result", (event) =>
+      callback(event.payload.id, event.payload.file)
+    );
+  }
+
+  public onSearchFinished(
+    callback: (finished: SearchFinished) => void
+  ): Promise<UnlistenFn> {
+    return listen<SearchFinished>("search-finished", (event) =>
+      callback(event.payload)
+    );
+  }
+
+  // with a regex query `$1` and `${name}` in the replacement are the groups of the match
+  public previewReplace(
+    query: SearchQuery,
+    replacement: string,
+    options?: ListOptions
+  ): Promise<FilePreview[]> {
+    return invoke<FilePreview[]>("preview_replace", {
+      query: query,
+      replacement: replacement,
+      options: options,
+    });
+  }
+
+  // the edits are written in all the files or in none, the deselected ones are left out
+  public applyReplace(files: FileEdits[]): Promise<ReplaceReport> {
+    return invoke<ReplaceReport>("apply_replace", { files: files });
+  }
+
+  // files of the workspace matching the characters of the query in order, best first
+  public quickOpen(query: string, limit?: number): Promise<QuickOpenHit[]> {
+    return invoke<QuickOpenHit[]>("quick_open", { query: query, limit: limit });
+  }
+
+  // words are all required: `word`, `prefix*`, `"a phrase"`, `#tag`
+  public searchIndex(query: string, limit?: number): Promise<IndexHit[]> {
+    return invoke<IndexHit[]>("search_index", { query: query, limit: limit });
+  }
+
+  public async rebuildIndex(): Promise<void> {
+    await invoke("rebuild_index");
+  }
+
+  public getIndexStatus(): Promise<IndexStatus> {
+    return invoke<IndexStatus>("get_index_status");
+  }
+}
+
+export interface SearchQuery {
+  pattern: string;
+  regex?: boolean;
+  caseSensitive?: boolean;
+  wholeWord?: boolean;
+  maxResults?: number;
+}
+
+// ranges are byte ranges in the preview, offsets in the text of the file
+export interface FileMatches {
+  path: string;
+  relative_path: string;
+  lines: {
+    line: number;
+    column: number;
+    preview: string;
+    ranges: [number, number][];
+    offsets: [number, number][];
+  }[];
+}
+
+export interface SearchFinished {
+  id: number;
+  files: number;
+  matches: number;
+  cancelled: boolean;
+  error: { kind: string; message: string } | null;
+}
+
+// offsets in the text of the file with \n line endings
+export interface TextEdit {
+  start: number;
+  end: number;
+  original: string;
+  replacement: string;
+}
+
+export interface FileEdits {
+  path: string;
+  edits: TextEdit[];
+}
+
+export interface FilePreview {
+  path: string;
+  relative_path: string;
+  matches: {
+    line: number;
+    column: number;
+    before: string;
+    after: string;
+    edit: TextEdit;
+  }[];
+}
+
+export interface ReplaceReport {
+  files: number;
+  replacements: number;
+}
+
+// indices are the char indices of the matched characters in relative_path
+export interface QuickOpenHit {
+  path: string;
+  relative_path: string;
+  score: number;
+  indices: number[];
+}
+
+export interface IndexHit {
+  path: string;
+  relative_path: string;
+  title: string | null;
+  score: number;
+}
+
+export interface IndexStatus {
+  documents: number;
+  terms: number;
+  syncing: boolean;
+}